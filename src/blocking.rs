@@ -0,0 +1,204 @@
+//! # 同步客户端
+//! 启用 `blocking` feature 后可用，适用于批处理任务、命令行工具等不使用异步运行时的程序。
+//!
+//! [blocking::Client](Client) 内部持有一个异步 [Client](crate::Client) 和一个单线程的 tokio 运行时，
+//! 每个方法都直接调用异步客户端的同名方法并等待其完成，因此与异步客户端共享模型、签名、验签、重试和限流逻辑。
+//! 同步方法由宏生成，参数和返回值类型与异步方法不一致时无法通过编译；新增接口时需要在本模块登记。
+//!
+//! 不能在异步运行时中调用同步客户端的方法，否则会 panic。
+//!
+//! # Example
+//! ```no_run
+//! # fn main() -> Result<(), wechat_pay_sdk::WeChatPayError> {
+//! use wechat_pay_sdk::Client;
+//!
+//! let client = Client::builder("1900000100", "5157F09EFDC096DE15EBE81A47057A72", "0123456789abcdef0123456789abcdef")
+//!   .private_key_path("apiclient_key.pem")
+//!   .build_blocking()?;
+//! let certificates = client.download_certificates()?;
+//! # Ok(())
+//! # }
+//! ```
+use crate::sdk::basic::app::invoke::AppPayParams;
+use crate::sdk::basic::app::order::{AppOrderRequest, AppOrderResponse};
+use crate::sdk::basic::combine::close::CombineCloseRequest;
+use crate::sdk::basic::combine::order::{
+  CombineAppOrderResponse, CombineH5OrderResponse, CombineJSApiOrderResponse,
+  CombineNativeOrderResponse, CombineOrderRequest,
+};
+use crate::sdk::basic::combine::query::CombineTransaction;
+use crate::sdk::basic::h5::order::{H5OrderRequest, H5OrderResponse};
+use crate::sdk::basic::jsapi::invoke::JSApiPayParams;
+use crate::sdk::basic::jsapi::order::{JSApiOrderRequest, JSApiOrderResponse};
+use crate::sdk::basic::jsapi::refund::{RefundRequest, RefundResponse};
+use crate::sdk::basic::native::order::{NativeOrderRequest, NativeOrderResponse};
+use crate::sdk::cert::GetCertificatesResponse;
+use crate::sdk::fund::transfer::{BatchTransferRequest, BatchTransferResponse};
+use crate::sdk::media::UploadImageResponse;
+use crate::sdk::partner::ecommerce::application::{MerchantApplyInfo, MerchantApplyResponse};
+use crate::webhook::event::WebhookEvent;
+use crate::webhook::handler::{Notification, WebhookAck};
+use crate::webhook::WeChatWebhook;
+use crate::{ClientBuilder, PlatformPubKey, WeChatPayError};
+use reqwest::header::HeaderMap;
+use reqwest::Method;
+use rsa::RsaPrivateKey;
+use serde::de::DeserializeOwned;
+use std::fmt::Display;
+use std::time::Duration;
+use tokio::runtime::{Builder, Runtime};
+
+/// # 同步客户端
+#[derive(Debug)]
+pub struct Client {
+  inner: crate::Client,
+  runtime: Runtime,
+}
+
+impl Client {
+  /// 包装异步客户端
+  pub fn new(client: crate::Client) -> Result<Self, WeChatPayError> {
+    let runtime = Builder::new_current_thread().enable_all().build()?;
+    Ok(Self {
+      inner: client,
+      runtime,
+    })
+  }
+
+  /// 从环境变量创建客户端，见 [ClientBuilder::from_env]
+  pub fn from_env() -> Result<Self, WeChatPayError> {
+    ClientBuilder::from_env()?.build_blocking()
+  }
+
+  /// 内部的异步客户端，可以用于调用不涉及网络请求的方法，如[解析回调](crate::webhook::WeChatWebhook::parse)
+  pub fn inner(&self) -> &crate::Client {
+    &self.inner
+  }
+
+  /// 同步版本的 [send_request](crate::Client::send_request)
+  pub fn send_request<Request, Response>(
+    &self,
+    method: Method,
+    url: &str,
+    query: Option<&[(&str, &str)]>,
+    body: Option<&Request>,
+  ) -> Result<Option<Response>, WeChatPayError>
+  where
+    Request: serde::Serialize,
+    Response: serde::de::DeserializeOwned + Send + 'static,
+  {
+    self
+      .runtime
+      .block_on(self.inner.send_request(method, url, query, body))
+  }
+
+  /// 同步版本的 [parse_webhook](crate::Client::parse_webhook)
+  pub fn parse_webhook<Message: DeserializeOwned>(
+    &self,
+    headers: &HeaderMap,
+    body: &[u8],
+  ) -> Result<(WeChatWebhook, Message), WeChatPayError> {
+    self
+      .runtime
+      .block_on(self.inner.parse_webhook(headers, body))
+  }
+
+  /// 同步版本的 [handle_webhook](crate::Client::handle_webhook)，处理函数为同步闭包
+  pub fn handle_webhook<Message, F, E>(
+    &self,
+    headers: &HeaderMap,
+    body: &[u8],
+    handler: F,
+  ) -> WebhookAck
+  where
+    Message: DeserializeOwned,
+    F: FnOnce(Notification<Message>) -> Result<(), E>,
+    E: Display,
+  {
+    match self
+      .runtime
+      .block_on(self.inner.notification(headers, body))
+    {
+      Ok(notification) => handler(notification).into(),
+      Err(err) => WebhookAck::from_error(&err),
+    }
+  }
+}
+
+impl ClientBuilder {
+  /// 构建[同步客户端](Client)
+  pub fn build_blocking(self) -> Result<Client, WeChatPayError> {
+    Client::new(self.build()?)
+  }
+}
+
+/// 为异步客户端的方法生成同名的同步方法
+macro_rules! blocking {
+  ($($(#[$meta:meta])* fn $name:ident(&self $(, $arg:ident: $ty:ty)*) -> $ret:ty;)*) => {
+    impl Client {
+      $(
+        $(#[$meta])*
+        pub fn $name(&self $(, $arg: $ty)*) -> Result<$ret, WeChatPayError> {
+          self.runtime.block_on(self.inner.$name($($arg),*))
+        }
+      )*
+    }
+  };
+}
+
+blocking! {
+  /// 同步版本的 [jsapi_order](crate::Client::jsapi_order)
+  fn jsapi_order(&self, req: &JSApiOrderRequest) -> JSApiOrderResponse;
+  /// 同步版本的 [jsapi_pay_params](crate::Client::jsapi_pay_params)
+  fn jsapi_pay_params(&self, appid: &str, prepay_id: &str) -> JSApiPayParams;
+  /// 同步版本的 [partner_jsapi_pay_params](crate::Client::partner_jsapi_pay_params)
+  fn partner_jsapi_pay_params(&self, sp_appid: &str, sub_appid: Option<&str>, prepay_id: &str) -> JSApiPayParams;
+  /// 同步版本的 [app_order](crate::Client::app_order)
+  fn app_order(&self, req: &AppOrderRequest) -> AppOrderResponse;
+  /// 同步版本的 [app_pay_params](crate::Client::app_pay_params)
+  fn app_pay_params(&self, appid: &str, prepay_id: &str) -> AppPayParams;
+  /// 同步版本的 [h5_order](crate::Client::h5_order)
+  fn h5_order(&self, req: &H5OrderRequest) -> H5OrderResponse;
+  /// 同步版本的 [native_order](crate::Client::native_order)
+  fn native_order(&self, req: &NativeOrderRequest) -> NativeOrderResponse;
+  /// 同步版本的 [combine_app_order](crate::Client::combine_app_order)
+  fn combine_app_order(&self, req: &CombineOrderRequest) -> CombineAppOrderResponse;
+  /// 同步版本的 [combine_jsapi_order](crate::Client::combine_jsapi_order)
+  fn combine_jsapi_order(&self, req: &CombineOrderRequest) -> CombineJSApiOrderResponse;
+  /// 同步版本的 [combine_h5_order](crate::Client::combine_h5_order)
+  fn combine_h5_order(&self, req: &CombineOrderRequest) -> CombineH5OrderResponse;
+  /// 同步版本的 [combine_native_order](crate::Client::combine_native_order)
+  fn combine_native_order(&self, req: &CombineOrderRequest) -> CombineNativeOrderResponse;
+  /// 同步版本的 [combine_query](crate::Client::combine_query)
+  fn combine_query(&self, combine_out_trade_no: &str) -> CombineTransaction;
+  /// 同步版本的 [combine_close](crate::Client::combine_close)
+  fn combine_close(&self, combine_out_trade_no: &str, req: &CombineCloseRequest) -> ();
+  /// 同步版本的 [combine_app_pay_params](crate::Client::combine_app_pay_params)
+  fn combine_app_pay_params(&self, combine_appid: &str, prepay_id: &str) -> AppPayParams;
+  /// 同步版本的 [combine_jsapi_pay_params](crate::Client::combine_jsapi_pay_params)
+  fn combine_jsapi_pay_params(&self, combine_appid: &str, prepay_id: &str) -> JSApiPayParams;
+  /// 同步版本的 [refund](crate::Client::refund)
+  fn refund(&self, req: &RefundRequest) -> RefundResponse;
+  /// 同步版本的 [batch_transfer](crate::Client::batch_transfer)
+  fn batch_transfer(&self, req: &BatchTransferRequest) -> BatchTransferResponse;
+  /// 同步版本的 [merchant_apply](crate::Client::merchant_apply)
+  fn merchant_apply(&self, req: &MerchantApplyInfo) -> MerchantApplyResponse;
+  /// 同步版本的 [upload_image](crate::Client::upload_image)
+  fn upload_image(&self, image: Vec<u8>, filename: &str) -> UploadImageResponse;
+  /// 同步版本的 [get_certificates](crate::Client::get_certificates)
+  fn get_certificates(&self) -> GetCertificatesResponse;
+  /// 同步版本的 [download_certificates](crate::Client::download_certificates)
+  fn download_certificates(&self) -> Vec<PlatformPubKey>;
+  /// 同步版本的 [refresh_certificates](crate::Client::refresh_certificates)
+  fn refresh_certificates(&self) -> ();
+  /// 同步版本的 [sync_certificates](crate::Client::sync_certificates)
+  fn sync_certificates(&self, max_age: Duration) -> ();
+  /// 同步版本的 [sha256_with_rsa](crate::Client::sha256_with_rsa)
+  fn sha256_with_rsa(&self, content: &[u8], private_key: Option<RsaPrivateKey>) -> String;
+  /// 同步版本的 [request_authorization](crate::Client::request_authorization)
+  fn request_authorization(&self, method: &Method, path: &str, content: &str) -> String;
+  /// 同步版本的 [verify_webhook](crate::Client::verify_webhook)
+  fn verify_webhook(&self, headers: &HeaderMap, body: &[u8]) -> WeChatWebhook;
+  /// 同步版本的 [webhook_event](crate::Client::webhook_event)
+  fn webhook_event(&self, headers: &HeaderMap, body: &[u8]) -> Notification<WebhookEvent>;
+}
@@ -0,0 +1,459 @@
+//! # 客户端构建
+//! [ClientBuilder] 负责组装 [Client]，并创建一个在所有接口间共享的 HTTP 客户端，以复用连接池。
+use super::key::{CertificateSource, MerchantCertificate, PrivateKeySource};
+use super::{
+  Client, Failover, PlatformPubKey, PlatformPubKeyInner, RateLimiter, RetryPolicy,
+  VerificationMode, DEFAULT_BASE_URL, PUB_KEY_ID_PREFIX,
+};
+use crate::signer::{RsaSigner, Signer};
+use crate::store::PlatformKeyStore;
+use crate::WeChatPayError;
+use aes_gcm::aead::generic_array::GenericArray;
+use rsa::{pkcs8::DecodePublicKey, RsaPrivateKey, RsaPublicKey};
+use std::fmt;
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+use tokio::sync::Mutex;
+use url::Url;
+
+/// 默认的 User-Agent，微信支付要求请求必须携带 User-Agent
+pub const DEFAULT_USER_AGENT: &str = concat!("wechat-pay-sdk-rs/", env!("CARGO_PKG_VERSION"));
+
+const ENV_MERCHANT_ID: &str = "WECHAT_PAY_MERCHANT_ID";
+const ENV_MERCHANT_SERIAL_NUMBER: &str = "WECHAT_PAY_MERCHANT_SERIAL_NUMBER";
+const ENV_MERCHANT_CERT_PATH: &str = "WECHAT_PAY_MERCHANT_CERT_PATH";
+const ENV_API_V3_KEY: &str = "WECHAT_PAY_API_V3_KEY";
+const ENV_PRIVATE_KEY: &str = "WECHAT_PAY_PRIVATE_KEY";
+const ENV_PRIVATE_KEY_PATH: &str = "WECHAT_PAY_PRIVATE_KEY_PATH";
+const ENV_PUBLIC_KEY_ID: &str = "WECHAT_PAY_PUBLIC_KEY_ID";
+const ENV_PUBLIC_KEY: &str = "WECHAT_PAY_PUBLIC_KEY";
+const ENV_BASE_URL: &str = "WECHAT_PAY_BASE_URL";
+
+/// # 客户端构建器
+/// # Example
+/// ```no_run
+/// # fn main() -> Result<(), wechat_pay_sdk::WeChatPayError> {
+/// use std::time::Duration;
+/// use wechat_pay_sdk::Client;
+///
+/// let client = Client::builder("1900000100", "5157F09EFDC096DE15EBE81A47057A72", "0123456789abcdef0123456789abcdef")
+///   .private_key_path("apiclient_key.pem")
+///   .connect_timeout(Duration::from_secs(3))
+///   .timeout(Duration::from_secs(10))
+///   .pool_max_idle_per_host(32)
+///   .build()?;
+/// # Ok(())
+/// # }
+/// ```
+pub struct ClientBuilder {
+  merchant_id: String,
+  merchant_serial_number: String,
+  api_key: String,
+  private_key: Option<PrivateKeySource>,
+  signer: Option<Arc<dyn Signer>>,
+  merchant_certificate: Option<CertificateSource>,
+  platform_pub_keys: Vec<PlatformPubKey>,
+  wechatpay_public_key: Option<(String, String)>,
+  verification_mode: Option<VerificationMode>,
+  base_url: String,
+  http_client: Option<reqwest::Client>,
+  timeout: Option<Duration>,
+  connect_timeout: Option<Duration>,
+  proxy: Option<reqwest::Proxy>,
+  pool_max_idle_per_host: Option<usize>,
+  pool_idle_timeout: Option<Duration>,
+  user_agent: String,
+  auto_refresh_certificates: bool,
+  key_store: Option<Arc<dyn PlatformKeyStore>>,
+  retry_policy: RetryPolicy,
+  rate_limiter: Option<RateLimiter>,
+  failover: Option<Failover>,
+}
+
+/// 不输出 APIv3 密钥和商户私钥
+impl fmt::Debug for ClientBuilder {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    f.debug_struct("ClientBuilder")
+      .field("merchant_id", &self.merchant_id)
+      .field("merchant_serial_number", &self.merchant_serial_number)
+      .field("api_key", &"<redacted>")
+      .field("private_key", &self.private_key)
+      .field("signer", &self.signer)
+      .field("merchant_certificate", &self.merchant_certificate)
+      .field("platform_pub_keys", &self.platform_pub_keys)
+      .field("wechatpay_public_key", &self.wechatpay_public_key)
+      .field("verification_mode", &self.verification_mode)
+      .field("base_url", &self.base_url)
+      .field("http_client", &self.http_client)
+      .field("timeout", &self.timeout)
+      .field("connect_timeout", &self.connect_timeout)
+      .field("proxy", &self.proxy)
+      .field("pool_max_idle_per_host", &self.pool_max_idle_per_host)
+      .field("pool_idle_timeout", &self.pool_idle_timeout)
+      .field("user_agent", &self.user_agent)
+      .field("auto_refresh_certificates", &self.auto_refresh_certificates)
+      .field("key_store", &self.key_store)
+      .field("retry_policy", &self.retry_policy)
+      .field("rate_limiter", &self.rate_limiter)
+      .field("failover", &self.failover)
+      .finish()
+  }
+}
+
+impl ClientBuilder {
+  /// # Arguments
+  ///
+  /// * `merchant_id` - 商户号
+  /// * `merchant_serial_number` - 商户 API 证书序列号，设置了商户证书时可以为空字符串
+  /// * `api_key` - 商户 APIv3 密钥
+  pub fn new(merchant_id: &str, merchant_serial_number: &str, api_key: &str) -> Self {
+    Self {
+      merchant_id: merchant_id.to_string(),
+      merchant_serial_number: merchant_serial_number.to_string(),
+      api_key: api_key.to_string(),
+      private_key: None,
+      signer: None,
+      merchant_certificate: None,
+      platform_pub_keys: Vec::new(),
+      wechatpay_public_key: None,
+      verification_mode: None,
+      base_url: DEFAULT_BASE_URL.to_string(),
+      http_client: None,
+      timeout: None,
+      connect_timeout: None,
+      proxy: None,
+      pool_max_idle_per_host: None,
+      pool_idle_timeout: None,
+      user_agent: DEFAULT_USER_AGENT.to_string(),
+      auto_refresh_certificates: true,
+      key_store: None,
+      retry_policy: RetryPolicy::none(),
+      rate_limiter: None,
+      failover: None,
+    }
+  }
+  /// 从环境变量读取配置
+  ///
+  /// | 环境变量 | 说明 |
+  /// | --- | --- |
+  /// | `WECHAT_PAY_MERCHANT_ID` | 商户号，必填 |
+  /// | `WECHAT_PAY_MERCHANT_SERIAL_NUMBER` | 商户 API 证书序列号，未设置 `WECHAT_PAY_MERCHANT_CERT_PATH` 时必填 |
+  /// | `WECHAT_PAY_MERCHANT_CERT_PATH` | 商户 API 证书文件路径，可选 |
+  /// | `WECHAT_PAY_API_V3_KEY` | 商户 APIv3 密钥，必填 |
+  /// | `WECHAT_PAY_PRIVATE_KEY` | 商户 API 私钥（PEM），与 `WECHAT_PAY_PRIVATE_KEY_PATH` 二选一 |
+  /// | `WECHAT_PAY_PRIVATE_KEY_PATH` | 商户 API 私钥文件路径 |
+  /// | `WECHAT_PAY_PUBLIC_KEY_ID` | 微信支付公钥 ID，可选 |
+  /// | `WECHAT_PAY_PUBLIC_KEY` | 微信支付公钥（PEM），设置了公钥 ID 时必填 |
+  /// | `WECHAT_PAY_BASE_URL` | API 请求域名，可选 |
+  pub fn from_env() -> Result<Self, WeChatPayError> {
+    let required = |name: &str| {
+      std::env::var(name)
+        .map_err(|_| WeChatPayError::ConfigError(format!("Missing environment variable {}", name)))
+    };
+    let optional = |name: &str| std::env::var(name).ok().filter(|v| !v.is_empty());
+    let cert_path = optional(ENV_MERCHANT_CERT_PATH);
+    let serial_number = match &cert_path {
+      Some(_) => optional(ENV_MERCHANT_SERIAL_NUMBER).unwrap_or_default(),
+      None => required(ENV_MERCHANT_SERIAL_NUMBER)?,
+    };
+    let mut builder = Self::new(
+      &required(ENV_MERCHANT_ID)?,
+      &serial_number,
+      &required(ENV_API_V3_KEY)?,
+    );
+    if let Some(path) = cert_path {
+      builder = builder.merchant_certificate_path(&path);
+    }
+    builder = match (optional(ENV_PRIVATE_KEY), optional(ENV_PRIVATE_KEY_PATH)) {
+      (Some(pem), _) => builder.private_key_pem(&pem),
+      (None, Some(path)) => builder.private_key_path(&path),
+      (None, None) => {
+        return Err(WeChatPayError::ConfigError(format!(
+          "Missing environment variable {} or {}",
+          ENV_PRIVATE_KEY, ENV_PRIVATE_KEY_PATH
+        )))
+      }
+    };
+    if let Some(id) = optional(ENV_PUBLIC_KEY_ID) {
+      builder = builder.wechatpay_public_key(&id, &required(ENV_PUBLIC_KEY)?);
+    }
+    if let Some(base_url) = optional(ENV_BASE_URL) {
+      builder = builder.base_url(&base_url);
+    }
+    Ok(builder)
+  }
+  /// 商户 API 私钥路径（PEM 格式，PKCS#8 或 PKCS#1）
+  pub fn private_key_path(mut self, path: &str) -> Self {
+    self.private_key = Some(PrivateKeySource::Path(path.to_string()));
+    self
+  }
+  /// 商户 API 私钥（PEM 格式，PKCS#8 或 PKCS#1），适用于从密钥管理服务或挂载的 secret 中读取的私钥
+  pub fn private_key_pem(mut self, pem: &str) -> Self {
+    self.private_key = Some(PrivateKeySource::Pem(pem.to_string()));
+    self
+  }
+  /// 从 PKCS#12 证书包（`apiclient_cert.p12`）中读取商户 API 私钥
+  ///
+  /// 微信支付下发的证书包默认密码为商户号
+  pub fn pkcs12(mut self, der: &[u8], password: &str) -> Self {
+    self.private_key = Some(PrivateKeySource::Pkcs12 {
+      der: der.to_vec(),
+      password: password.to_string(),
+    });
+    self
+  }
+  /// 商户 API 证书（`apiclient_cert.pem`）路径
+  ///
+  /// 设置后将从证书中读取证书序列号（`merchant_serial_number` 可以传入空字符串），
+  /// 并在构建时检查私钥与证书是否匹配。使用 [pkcs12](Self::pkcs12) 时自动读取证书包中的证书。
+  pub fn merchant_certificate_path(mut self, path: &str) -> Self {
+    self.merchant_certificate = Some(CertificateSource::Path(path.to_string()));
+    self
+  }
+  /// 商户 API 证书（PEM 格式），见 [merchant_certificate_path](Self::merchant_certificate_path)
+  pub fn merchant_certificate_pem(mut self, pem: &str) -> Self {
+    self.merchant_certificate = Some(CertificateSource::Pem(pem.to_string()));
+    self
+  }
+  /// 已解析的商户 API 私钥
+  pub fn private_key(mut self, key: RsaPrivateKey) -> Self {
+    self.private_key = Some(PrivateKeySource::Key(Box::new(key)));
+    self
+  }
+  /// 使用外部签名器代替商户 API 私钥签名，见 [signer](crate::signer)
+  ///
+  /// 只配置签名器时无法[解密敏感信息](Client::decrypt_sensitive)；同时配置了私钥时，私钥只用于解密
+  pub fn signer(mut self, signer: Arc<dyn Signer>) -> Self {
+    self.signer = Some(signer);
+    self
+  }
+  /// 初始的平台公钥
+  pub fn platform_pub_keys(mut self, platform_pub_keys: Vec<PlatformPubKey>) -> Self {
+    self.platform_pub_keys = platform_pub_keys;
+    self
+  }
+  /// 微信支付公钥
+  ///
+  /// 未指定 [verification_mode](Self::verification_mode) 时，设置公钥后使用 [VerificationMode::PublicKey]
+  ///
+  /// # Arguments
+  ///
+  /// * `public_key_id` - 微信支付公钥 ID，以 `PUB_KEY_ID_` 开头
+  /// * `public_key_pem` - 微信支付公钥（PEM 格式）
+  pub fn wechatpay_public_key(mut self, public_key_id: &str, public_key_pem: &str) -> Self {
+    self.wechatpay_public_key = Some((public_key_id.to_string(), public_key_pem.to_string()));
+    self
+  }
+  /// 验签模式，默认根据是否设置了微信支付公钥决定
+  pub fn verification_mode(mut self, mode: VerificationMode) -> Self {
+    self.verification_mode = Some(mode);
+    self
+  }
+  /// API 请求域名，默认为 [DEFAULT_BASE_URL]
+  pub fn base_url(mut self, base_url: &str) -> Self {
+    self.base_url = base_url.trim_end_matches('/').to_string();
+    self
+  }
+  /// 使用预先构建的 HTTP 客户端
+  ///
+  /// 设置后将忽略超时、代理和连接池相关配置
+  pub fn http_client(mut self, http_client: reqwest::Client) -> Self {
+    self.http_client = Some(http_client);
+    self
+  }
+  /// 整个请求（从连接到读取完响应）的超时时间
+  pub fn timeout(mut self, timeout: Duration) -> Self {
+    self.timeout = Some(timeout);
+    self
+  }
+  /// 建立连接的超时时间
+  pub fn connect_timeout(mut self, timeout: Duration) -> Self {
+    self.connect_timeout = Some(timeout);
+    self
+  }
+  /// 出口代理
+  pub fn proxy(mut self, proxy: reqwest::Proxy) -> Self {
+    self.proxy = Some(proxy);
+    self
+  }
+  /// 每个域名最多保留的空闲连接数
+  pub fn pool_max_idle_per_host(mut self, max: usize) -> Self {
+    self.pool_max_idle_per_host = Some(max);
+    self
+  }
+  /// 空闲连接的保留时间
+  pub fn pool_idle_timeout(mut self, timeout: Duration) -> Self {
+    self.pool_idle_timeout = Some(timeout);
+    self
+  }
+  /// 请求携带的 User-Agent，默认为 [DEFAULT_USER_AGENT]
+  pub fn user_agent(mut self, user_agent: &str) -> Self {
+    self.user_agent = user_agent.to_string();
+    self
+  }
+  /// 遇到未知的 `Wechatpay-Serial` 时是否自动下载平台证书，默认开启
+  pub fn auto_refresh_certificates(mut self, enabled: bool) -> Self {
+    self.auto_refresh_certificates = enabled;
+    self
+  }
+  /// 多实例共享平台公钥，见 [store](crate::store)
+  pub fn key_store(mut self, key_store: Arc<dyn PlatformKeyStore>) -> Self {
+    self.key_store = Some(key_store);
+    self
+  }
+  /// 失败重试策略，默认不重试
+  pub fn retry_policy(mut self, retry_policy: RetryPolicy) -> Self {
+    self.retry_policy = retry_policy;
+    self
+  }
+  /// 客户端限流，见 [RateLimiter]
+  pub fn rate_limiter(mut self, rate_limiter: RateLimiter) -> Self {
+    self.rate_limiter = Some(rate_limiter);
+    self
+  }
+  /// 主域名连接失败时改用备用域名，默认不启用，见 [Failover]
+  pub fn failover(mut self, failover: Failover) -> Self {
+    self.failover = Some(failover);
+    self
+  }
+
+  fn build_http_client(&mut self) -> Result<reqwest::Client, WeChatPayError> {
+    if let Some(http_client) = self.http_client.take() {
+      return Ok(http_client);
+    }
+    let mut builder = reqwest::Client::builder();
+    if let Some(timeout) = self.timeout {
+      builder = builder.timeout(timeout);
+    }
+    if let Some(timeout) = self.connect_timeout {
+      builder = builder.connect_timeout(timeout);
+    }
+    if let Some(proxy) = self.proxy.take() {
+      builder = builder.proxy(proxy);
+    }
+    if let Some(max) = self.pool_max_idle_per_host {
+      builder = builder.pool_max_idle_per_host(max);
+    }
+    if let Some(timeout) = self.pool_idle_timeout {
+      builder = builder.pool_idle_timeout(timeout);
+    }
+    Ok(builder.build()?)
+  }
+
+  pub fn build(mut self) -> Result<Client, WeChatPayError> {
+    Url::parse(&self.base_url)?;
+    if let Some(failover) = &self.failover {
+      failover.validate()?;
+    }
+    if self.api_key.len() != 32 {
+      return Err(WeChatPayError::ConfigError(
+        "Invalid APIv3 key: must be 32 bytes".to_string(),
+      ));
+    }
+    let (private_key, pkcs12_cert) = match self.private_key.take() {
+      Some(source) => {
+        let (private_key, cert) = source.load()?;
+        (Some(private_key), cert)
+      }
+      None => (None, None),
+    };
+    let signer: Arc<dyn Signer> = match (self.signer.take(), &private_key) {
+      (Some(signer), _) => signer,
+      (None, Some(private_key)) => Arc::new(RsaSigner::new(private_key.clone())),
+      (None, None) => {
+        return Err(WeChatPayError::ConfigError(
+          "Missing merchant private key".to_string(),
+        ))
+      }
+    };
+    let signer_key = signer.public_key();
+    let merchant_certificate = match self.merchant_certificate.take() {
+      Some(source) => Some(source.load()?),
+      None => pkcs12_cert,
+    }
+    .map(|x509| MerchantCertificate::from_x509(&x509, signer_key.as_ref()))
+    .transpose()?;
+    let merchant_serial_number = match &merchant_certificate {
+      None if self.merchant_serial_number.is_empty() => {
+        return Err(WeChatPayError::ConfigError(
+          "Missing merchant serial number".to_string(),
+        ))
+      }
+      None => std::mem::take(&mut self.merchant_serial_number),
+      Some(cert) if self.merchant_serial_number.is_empty() => cert.serial_number.clone(),
+      Some(cert) => {
+        if !cert
+          .serial_number
+          .eq_ignore_ascii_case(&self.merchant_serial_number)
+        {
+          return Err(WeChatPayError::ConfigError(format!(
+            "Merchant serial number {} does not match the merchant certificate {}",
+            self.merchant_serial_number, cert.serial_number
+          )));
+        }
+        cert.serial_number.clone()
+      }
+    };
+    let wechatpay_public_key = self
+      .wechatpay_public_key
+      .take()
+      .map(|(id, pem)| parse_wechatpay_public_key(id, &pem))
+      .transpose()?;
+    let verification_mode = self
+      .verification_mode
+      .unwrap_or(match wechatpay_public_key {
+        Some(_) => VerificationMode::PublicKey,
+        None => VerificationMode::PlatformCertificate,
+      });
+    if verification_mode != VerificationMode::PlatformCertificate && wechatpay_public_key.is_none()
+    {
+      return Err(WeChatPayError::ConfigError(
+        "Missing WeChat Pay public key".to_string(),
+      ));
+    }
+    let http_client = self.build_http_client()?;
+    let client = Client {
+      merchant_id: self.merchant_id,
+      private_key,
+      signer,
+      merchant_serial_number,
+      merchant_certificate,
+      api_key: GenericArray::from_slice(self.api_key.as_bytes()).to_owned(),
+      public_keys: RwLock::new(Vec::new()),
+      verification_mode,
+      wechatpay_public_key,
+      auto_refresh_certificates: self.auto_refresh_certificates,
+      certificates_refreshed_at: Mutex::new(None),
+      timeout: self.timeout,
+      key_store: self.key_store,
+      retry_policy: self.retry_policy,
+      rate_limiter: self.rate_limiter,
+      failover: self.failover,
+      base_url: self.base_url,
+      http_client,
+      user_agent: self.user_agent,
+    };
+    client.update_public_keys(self.platform_pub_keys);
+    Ok(client)
+  }
+}
+
+fn parse_wechatpay_public_key(
+  id: String,
+  pem: &str,
+) -> Result<PlatformPubKeyInner, WeChatPayError> {
+  if !id.starts_with(PUB_KEY_ID_PREFIX) {
+    return Err(WeChatPayError::ConfigError(format!(
+      "WeChat Pay public key id must start with {}",
+      PUB_KEY_ID_PREFIX
+    )));
+  }
+  let key = RsaPublicKey::from_public_key_pem(pem)
+    .map_err(|e| WeChatPayError::ConfigError(format!("Invalid WeChat Pay public key: {}", e)))?;
+  Ok(PlatformPubKeyInner {
+    serial_no: id,
+    effective_time: 0,
+    expire_time: u64::MAX,
+    key,
+  })
+}
@@ -0,0 +1,84 @@
+//! # 接口清单
+//! 客户端已知的接口及其属性，用于决定重试等行为。路径模板中以 `{}` 包裹的段匹配任意值。
+use reqwest::Method;
+
+#[derive(Debug)]
+pub(crate) struct Endpoint {
+  pub method: &'static str,
+  pub template: &'static str,
+  /// 使用相同参数重复调用是否安全
+  ///
+  /// 下单、退款、转账等接口以商户单号去重，重复调用不会重复扣款
+  pub idempotent: bool,
+}
+
+const fn endpoint(method: &'static str, template: &'static str, idempotent: bool) -> Endpoint {
+  Endpoint {
+    method,
+    template,
+    idempotent,
+  }
+}
+
+pub(crate) static ENDPOINTS: &[Endpoint] = &[
+  endpoint("GET", "/v3/certificates", true),
+  endpoint("POST", "/v3/merchant/media/upload", true),
+  endpoint("POST", "/v3/pay/transactions/jsapi", true),
+  endpoint("POST", "/v3/pay/transactions/app", true),
+  endpoint("POST", "/v3/pay/transactions/h5", true),
+  endpoint("POST", "/v3/pay/transactions/native", true),
+  endpoint("POST", "/v3/combine-transactions/app", true),
+  endpoint("POST", "/v3/combine-transactions/jsapi", true),
+  endpoint("POST", "/v3/combine-transactions/h5", true),
+  endpoint("POST", "/v3/combine-transactions/native", true),
+  endpoint(
+    "GET",
+    "/v3/combine-transactions/out-trade-no/{combine_out_trade_no}",
+    true,
+  ),
+  endpoint(
+    "POST",
+    "/v3/combine-transactions/out-trade-no/{combine_out_trade_no}/close",
+    true,
+  ),
+  endpoint("POST", "/v3/refund/domestic/refunds", true),
+  endpoint("POST", "/v3/transfer/batches", true),
+  endpoint("POST", "/v3/ecommerce/applyments", true),
+];
+
+/// 查找路径（不含查询参数）对应的接口
+pub(crate) fn find_endpoint(method: &Method, path: &str) -> Option<&'static Endpoint> {
+  ENDPOINTS.iter().find(|endpoint| {
+    endpoint.method == method.as_str() && matches_template(endpoint.template, path)
+  })
+}
+
+pub(crate) fn matches_template(template: &str, path: &str) -> bool {
+  let mut template = template.split('/');
+  let mut path = path.split('/');
+  loop {
+    match (template.next(), path.next()) {
+      (None, None) => return true,
+      (Some(t), Some(p)) if t == p || (t.starts_with('{') && t.ends_with('}') && !p.is_empty()) => {
+        continue
+      }
+      _ => return false,
+    }
+  }
+}
+
+/// 接口的路径模板，未知接口返回原路径
+pub(crate) fn endpoint_template<'a>(method: &Method, path: &'a str) -> &'a str {
+  match find_endpoint(method, path) {
+    Some(endpoint) => endpoint.template,
+    None => path,
+  }
+}
+
+/// 接口是否可以使用相同参数重试，未知接口中只有 GET 请求视为可重试
+pub(crate) fn is_idempotent(method: &Method, path: &str) -> bool {
+  match find_endpoint(method, path) {
+    Some(endpoint) => endpoint.idempotent,
+    None => method == Method::GET,
+  }
+}
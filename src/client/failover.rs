@@ -0,0 +1,293 @@
+//! # 备用域名容灾
+//! 微信支付提供备用域名 [BACKUP_BASE_URL]，用于主域名无法访问的情况。启用 [Failover] 后：
+//! - 只有在与主域名建立连接失败（包括连接超时）时才改用备用域名。此时请求还没有发出，改发到备用域名不会导致重复处理，
+//!   因此非幂等的接口同样适用；连接建立后的超时、5xx 等错误不会切换域名，其中 5xx 由 [RetryPolicy](crate::RetryPolicy) 处理
+//! - 连续失败达到阈值后熔断打开，后续请求直接发往备用域名；经过恢复时间后放行一个请求探测主域名，成功则关闭熔断
+//! - 每次改用备用域名以及熔断状态的变化都会通过 [on_event](Failover::on_event) 通知
+//!
+//! # Example
+//! ```no_run
+//! # fn main() -> Result<(), wechat_pay_sdk::WeChatPayError> {
+//! use std::time::Duration;
+//! use wechat_pay_sdk::{Client, Failover, FailoverEvent};
+//!
+//! let failover = Failover::new()
+//!   .failure_threshold(3)
+//!   .recovery_timeout(Duration::from_secs(30))
+//!   .on_event(|event| match event {
+//!     FailoverEvent::FailedOver { path, .. } => eprintln!("{} sent to backup domain", path),
+//!     event => eprintln!("{:?}", event),
+//!   });
+//! let client = Client::builder("1900000100", "5157F09EFDC096DE15EBE81A47057A72", "0123456789abcdef0123456789abcdef")
+//!   .private_key_path("apiclient_key.pem")
+//!   .failover(failover)
+//!   .build()?;
+//! # Ok(())
+//! # }
+//! ```
+use super::Client;
+use crate::WeChatPayError;
+use reqwest::Response;
+use std::fmt;
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use url::Url;
+
+/// 微信支付 API 备用域名
+pub const BACKUP_BASE_URL: &str = "https://api2.mch.weixin.qq.com";
+
+/// 熔断状态
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CircuitState {
+  /// 请求发往主域名
+  Closed,
+  /// 请求直接发往备用域名
+  Open,
+  /// 正在用一个请求探测主域名
+  HalfOpen,
+}
+
+/// 请求改用备用域名的原因
+#[derive(Debug, Clone)]
+pub enum FailoverReason {
+  /// 连接主域名失败
+  ConnectError(String),
+  /// 熔断打开
+  CircuitOpen,
+}
+
+/// 容灾事件
+#[derive(Debug, Clone)]
+#[non_exhaustive]
+pub enum FailoverEvent {
+  /// 请求改为发往备用域名，`path` 为接口路径模板
+  FailedOver {
+    path: String,
+    reason: FailoverReason,
+  },
+  /// 熔断打开
+  CircuitOpened { consecutive_failures: u32 },
+  /// 主域名恢复，熔断关闭
+  Recovered,
+}
+
+type EventHook = Arc<dyn Fn(&FailoverEvent) + Send + Sync>;
+
+#[derive(Debug, Default)]
+struct Health {
+  consecutive_failures: u32,
+  opened_at: Option<Instant>,
+  probing: bool,
+}
+
+/// # 备用域名容灾
+/// 通过 [ClientBuilder::failover](crate::ClientBuilder::failover) 启用
+///
+/// 克隆后的实例共享熔断状态
+#[derive(Clone)]
+pub struct Failover {
+  backup_base_url: String,
+  failure_threshold: u32,
+  recovery_timeout: Duration,
+  on_event: Option<EventHook>,
+  health: Arc<Mutex<Health>>,
+}
+
+impl Default for Failover {
+  /// 备用域名为 [BACKUP_BASE_URL]，连续失败 3 次后熔断，30 秒后探测主域名
+  fn default() -> Self {
+    Self {
+      backup_base_url: BACKUP_BASE_URL.to_string(),
+      failure_threshold: 3,
+      recovery_timeout: Duration::from_secs(30),
+      on_event: None,
+      health: Arc::default(),
+    }
+  }
+}
+
+impl fmt::Debug for Failover {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    f.debug_struct("Failover")
+      .field("backup_base_url", &self.backup_base_url)
+      .field("failure_threshold", &self.failure_threshold)
+      .field("recovery_timeout", &self.recovery_timeout)
+      .field("state", &self.state())
+      .finish()
+  }
+}
+
+/// 本次请求发往的域名
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Route {
+  Primary,
+  Backup,
+}
+
+impl Failover {
+  pub fn new() -> Self {
+    Self::default()
+  }
+  /// 备用域名，默认为 [BACKUP_BASE_URL]
+  pub fn backup_base_url(mut self, base_url: &str) -> Self {
+    self.backup_base_url = base_url.trim_end_matches('/').to_string();
+    self
+  }
+  /// 连续连接失败多少次后熔断
+  pub fn failure_threshold(mut self, threshold: u32) -> Self {
+    self.failure_threshold = threshold.max(1);
+    self
+  }
+  /// 熔断后多久探测主域名
+  pub fn recovery_timeout(mut self, timeout: Duration) -> Self {
+    self.recovery_timeout = timeout;
+    self
+  }
+  /// 容灾事件回调，在发送请求的任务中同步调用，不应阻塞
+  pub fn on_event<F>(mut self, hook: F) -> Self
+  where
+    F: Fn(&FailoverEvent) + Send + Sync + 'static,
+  {
+    self.on_event = Some(Arc::new(hook));
+    self
+  }
+  /// 当前的熔断状态
+  pub fn state(&self) -> CircuitState {
+    let health = self.health.lock().unwrap();
+    match health.opened_at {
+      None => CircuitState::Closed,
+      Some(_) if health.probing => CircuitState::HalfOpen,
+      Some(opened_at) if opened_at.elapsed() >= self.recovery_timeout => CircuitState::HalfOpen,
+      Some(_) => CircuitState::Open,
+    }
+  }
+
+  pub(crate) fn validate(&self) -> Result<(), WeChatPayError> {
+    Url::parse(&self.backup_base_url)?;
+    Ok(())
+  }
+
+  fn emit(&self, event: FailoverEvent) {
+    if let Some(hook) = &self.on_event {
+      hook(&event);
+    }
+  }
+
+  /// 选择本次请求的域名，熔断打开超过恢复时间后只放行一个请求探测主域名
+  fn route(&self) -> Route {
+    let mut health = self.health.lock().unwrap();
+    match health.opened_at {
+      None => Route::Primary,
+      Some(opened_at) if !health.probing && opened_at.elapsed() >= self.recovery_timeout => {
+        health.probing = true;
+        Route::Primary
+      }
+      Some(_) => Route::Backup,
+    }
+  }
+
+  /// 主域名返回了应答
+  fn primary_succeeded(&self) {
+    let recovered = {
+      let mut health = self.health.lock().unwrap();
+      let recovered = health.opened_at.is_some() && health.probing;
+      if health.opened_at.is_none() || recovered {
+        *health = Health::default();
+      }
+      recovered
+    };
+    if recovered {
+      tracing::info!("wechat pay primary domain recovered");
+      self.emit(FailoverEvent::Recovered);
+    }
+  }
+
+  /// 连接主域名失败
+  fn primary_failed(&self) {
+    let opened = {
+      let mut health = self.health.lock().unwrap();
+      health.consecutive_failures += 1;
+      let opened = health.probing
+        || (health.opened_at.is_none() && health.consecutive_failures >= self.failure_threshold);
+      if opened {
+        health.opened_at = Some(Instant::now());
+        health.probing = false;
+      }
+      opened.then_some(health.consecutive_failures)
+    };
+    if let Some(consecutive_failures) = opened {
+      tracing::warn!(consecutive_failures, "wechat pay failover circuit opened");
+      self.emit(FailoverEvent::CircuitOpened {
+        consecutive_failures,
+      });
+    }
+  }
+
+  /// 主域名请求失败但无法判断主域名是否可用，例如读取应答超时；探测请求遇到这种情况时重新计时
+  fn primary_inconclusive(&self) {
+    let mut health = self.health.lock().unwrap();
+    if health.probing {
+      health.opened_at = Some(Instant::now());
+      health.probing = false;
+    }
+  }
+
+  /// 将请求的域名替换为备用域名
+  fn rewrite(
+    &self,
+    primary_base_url: &str,
+    mut req: reqwest::Request,
+  ) -> Result<reqwest::Request, WeChatPayError> {
+    if let Some(rest) = req.url().as_str().strip_prefix(primary_base_url) {
+      *req.url_mut() = Url::parse(&format!("{}{}", self.backup_base_url, rest))?;
+    }
+    Ok(req)
+  }
+}
+
+impl Client {
+  /// 发送请求，启用了 [Failover] 时在连接失败后改发到备用域名
+  ///
+  /// 外层错误来自构造请求，内层为发送请求的网络错误
+  pub(crate) async fn dispatch<F, Fut>(
+    &self,
+    template: &str,
+    build: &mut F,
+  ) -> Result<Result<Response, reqwest::Error>, WeChatPayError>
+  where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<reqwest::Request, WeChatPayError>>,
+  {
+    let Some(failover) = &self.failover else {
+      return Ok(self.http_client.execute(build().await?).await);
+    };
+    let reason = match failover.route() {
+      Route::Backup => FailoverReason::CircuitOpen,
+      Route::Primary => match self.http_client.execute(build().await?).await {
+        Ok(res) => {
+          failover.primary_succeeded();
+          return Ok(Ok(res));
+        }
+        Err(err) if err.is_connect() => {
+          tracing::warn!(
+            error = %err,
+            "failed to connect to wechat pay, failing over to backup domain"
+          );
+          failover.primary_failed();
+          FailoverReason::ConnectError(err.to_string())
+        }
+        Err(err) => {
+          failover.primary_inconclusive();
+          return Ok(Err(err));
+        }
+      },
+    };
+    failover.emit(FailoverEvent::FailedOver {
+      path: template.to_string(),
+      reason,
+    });
+    let req = failover.rewrite(&self.base_url, build().await?)?;
+    Ok(self.http_client.execute(req).await)
+  }
+}
@@ -0,0 +1,294 @@
+mod builder;
+mod endpoint;
+mod failover;
+mod key;
+mod rate_limit;
+mod retry;
+
+use crate::signer::Signer;
+use crate::store::PlatformKeyStore;
+use crate::WeChatPayError;
+use aes_gcm::aead::{consts::U32, generic_array::GenericArray};
+pub use builder::{ClientBuilder, DEFAULT_USER_AGENT};
+use chrono::Utc;
+#[cfg(feature = "testing")]
+pub(crate) use endpoint::matches_template;
+pub(crate) use endpoint::{endpoint_template, is_idempotent};
+pub use failover::{CircuitState, Failover, FailoverEvent, FailoverReason, BACKUP_BASE_URL};
+pub use key::MerchantCertificate;
+pub use rate_limit::{RateLimit, RateLimitMode, RateLimiter};
+pub(crate) use retry::error_code;
+pub use retry::RetryPolicy;
+use rsa::{pkcs8::DecodePublicKey, RsaPrivateKey, RsaPublicKey};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, Instant};
+use url::Url;
+
+/// 微信支付 API 主域名
+pub const DEFAULT_BASE_URL: &str = "https://api.mch.weixin.qq.com";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PlatformPubKey {
+  pub serial_no: String,
+  pub expire_time: u64,
+  pub effective_time: u64,
+  pub key: String,
+}
+#[derive(Debug, Clone)]
+pub struct PlatformPubKeyInner {
+  pub serial_no: String,
+  pub expire_time: u64,
+  pub effective_time: u64,
+  pub key: RsaPublicKey,
+}
+impl TryFrom<PlatformPubKey> for PlatformPubKeyInner {
+  type Error = WeChatPayError;
+  fn try_from(value: PlatformPubKey) -> Result<Self, Self::Error> {
+    Ok(Self {
+      serial_no: value.serial_no,
+      expire_time: value.expire_time,
+      effective_time: value.effective_time,
+      key: RsaPublicKey::from_public_key_pem(&value.key)
+        .map_err(|_| WeChatPayError::Unknown("public key parse error".to_string()))?,
+    })
+  }
+}
+
+/// 微信支付公钥 ID 的前缀
+pub const PUB_KEY_ID_PREFIX: &str = "PUB_KEY_ID_";
+
+/// # 验签模式
+/// 微信支付正在将商户从平台证书迁移到固定的[微信支付公钥](https://pay.weixin.qq.com/doc/v3/merchant/4012153196)，
+/// 公钥 ID 以 `PUB_KEY_ID_` 开头。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum VerificationMode {
+  /// 只使用平台证书
+  #[default]
+  PlatformCertificate,
+  /// 只使用微信支付公钥
+  PublicKey,
+  /// 迁移期间同时接受平台证书和微信支付公钥，加密敏感信息时优先使用微信支付公钥
+  Hybrid,
+}
+
+pub struct Client {
+  pub merchant_id: String,
+  /// 商户 API 私钥，仅用于解密敏感信息；只配置了外部签名器时为空
+  pub(crate) private_key: Option<RsaPrivateKey>,
+  pub(crate) signer: Arc<dyn Signer>,
+  pub(crate) merchant_serial_number: String,
+  pub(crate) merchant_certificate: Option<MerchantCertificate>,
+  pub(crate) api_key: GenericArray<u8, U32>,
+  pub(crate) public_keys: RwLock<Vec<PlatformPubKeyInner>>,
+  pub(crate) verification_mode: VerificationMode,
+  /// 微信支付公钥，`serial_no` 为公钥 ID
+  pub(crate) wechatpay_public_key: Option<PlatformPubKeyInner>,
+  /// 遇到未知的 `Wechatpay-Serial` 时是否自动下载平台证书
+  pub(crate) auto_refresh_certificates: bool,
+  /// 上一次下载平台证书的时间，同时用于避免并发下载
+  pub(crate) certificates_refreshed_at: tokio::sync::Mutex<Option<Instant>>,
+  /// 请求超时时间，同时限制等待其他实例下载平台证书的时间
+  pub(crate) timeout: Option<Duration>,
+  /// 多实例共享的平台公钥存储
+  pub(crate) key_store: Option<Arc<dyn PlatformKeyStore>>,
+  pub(crate) base_url: String,
+  pub(crate) http_client: reqwest::Client,
+  pub(crate) user_agent: String,
+  pub(crate) retry_policy: RetryPolicy,
+  pub(crate) rate_limiter: Option<RateLimiter>,
+  pub(crate) failover: Option<Failover>,
+}
+
+/// 不输出 APIv3 密钥和商户私钥
+impl fmt::Debug for Client {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    f.debug_struct("Client")
+      .field("merchant_id", &self.merchant_id)
+      .field("merchant_serial_number", &self.merchant_serial_number)
+      .field("api_key", &"<redacted>")
+      .field(
+        "private_key",
+        &self.private_key.as_ref().map(|_| "<redacted>"),
+      )
+      .field("signer", &self.signer)
+      .field("merchant_certificate", &self.merchant_certificate)
+      .field("verification_mode", &self.verification_mode)
+      .field("wechatpay_public_key", &self.wechatpay_public_key)
+      .field("auto_refresh_certificates", &self.auto_refresh_certificates)
+      .field("timeout", &self.timeout)
+      .field("key_store", &self.key_store)
+      .field("base_url", &self.base_url)
+      .field("user_agent", &self.user_agent)
+      .field("retry_policy", &self.retry_policy)
+      .field("rate_limiter", &self.rate_limiter)
+      .field("failover", &self.failover)
+      .finish_non_exhaustive()
+  }
+}
+
+impl Client {
+  /// Create a new client.
+  ///
+  /// # Arguments
+  ///
+  /// * `merchant_id` - 商户号
+  /// * `private_key_path` - 商户 API 私钥路径（PEM 格式，PKCS#8 或 PKCS#1）
+  /// * `merchant_serial_number` - 商户 API 证书序列号
+  /// * `api_key` - 商户 APIv3 密钥
+  pub fn new(
+    merchant_id: &str,
+    private_key_path: &str,
+    merchant_serial_number: &str,
+    api_key: &str,
+    platform_pub_keys: Vec<PlatformPubKey>,
+  ) -> Result<Self, WeChatPayError> {
+    ClientBuilder::new(merchant_id, merchant_serial_number, api_key)
+      .private_key_path(private_key_path)
+      .platform_pub_keys(platform_pub_keys)
+      .build()
+  }
+  /// 使用 PEM 格式（PKCS#8 或 PKCS#1）的商户 API 私钥创建客户端
+  pub fn from_pem(
+    merchant_id: &str,
+    private_key_pem: &str,
+    merchant_serial_number: &str,
+    api_key: &str,
+  ) -> Result<Self, WeChatPayError> {
+    ClientBuilder::new(merchant_id, merchant_serial_number, api_key)
+      .private_key_pem(private_key_pem)
+      .build()
+  }
+  /// 使用 PKCS#12 证书包（`apiclient_cert.p12`）创建客户端
+  pub fn from_pkcs12(
+    merchant_id: &str,
+    pkcs12_der: &[u8],
+    password: &str,
+    merchant_serial_number: &str,
+    api_key: &str,
+  ) -> Result<Self, WeChatPayError> {
+    ClientBuilder::new(merchant_id, merchant_serial_number, api_key)
+      .pkcs12(pkcs12_der, password)
+      .build()
+  }
+  /// 从环境变量创建客户端，见 [ClientBuilder::from_env]
+  pub fn from_env() -> Result<Self, WeChatPayError> {
+    ClientBuilder::from_env()?.build()
+  }
+  /// 使用 [ClientBuilder] 创建客户端，可以配置超时、代理、连接池等参数
+  ///
+  /// # Arguments
+  ///
+  /// * `merchant_id` - 商户号
+  /// * `merchant_serial_number` - 商户 API 证书序列号
+  /// * `api_key` - 商户 APIv3 密钥
+  pub fn builder(merchant_id: &str, merchant_serial_number: &str, api_key: &str) -> ClientBuilder {
+    ClientBuilder::new(merchant_id, merchant_serial_number, api_key)
+  }
+  /// 替换 API 请求域名，默认为 [DEFAULT_BASE_URL]
+  ///
+  /// 例如在测试中指向本地的模拟服务器：`http://127.0.0.1:8080`
+  pub fn with_base_url(mut self, base_url: &str) -> Result<Self, WeChatPayError> {
+    Url::parse(base_url)?;
+    self.base_url = base_url.trim_end_matches('/').to_string();
+    Ok(self)
+  }
+  /// 替换发送请求所使用的 HTTP 客户端，所有接口都通过该客户端发送请求
+  ///
+  /// 可以用于自定义 TLS、代理等设置
+  pub fn with_http_client(mut self, http_client: reqwest::Client) -> Self {
+    self.http_client = http_client;
+    self
+  }
+  pub fn base_url(&self) -> &str {
+    &self.base_url
+  }
+  /// 备用域名容灾配置，可以用于查询熔断状态
+  pub fn failover(&self) -> Option<&Failover> {
+    self.failover.as_ref()
+  }
+  pub(crate) fn api_url(&self, path: &str) -> String {
+    format!("{}{}", self.base_url, path)
+  }
+  /// 商户 API 证书序列号
+  pub fn merchant_serial_number(&self) -> &str {
+    &self.merchant_serial_number
+  }
+  /// 商户 API 证书信息，只有构建时提供了商户证书（或 PKCS#12 证书包）才会返回
+  ///
+  /// 可以用于在证书过期前告警：
+  /// ```no_run
+  /// # fn check(client: &wechat_pay_sdk::Client) {
+  /// if let Some(cert) = client.merchant_certificate() {
+  ///   if cert.expires_in() < chrono::Duration::days(30) {
+  ///     eprintln!("merchant certificate expires at {}", cert.not_after);
+  ///   }
+  /// }
+  /// # }
+  /// ```
+  pub fn merchant_certificate(&self) -> Option<&MerchantCertificate> {
+    self.merchant_certificate.as_ref()
+  }
+  /// 签名器，见 [signer](crate::signer)
+  pub fn signer(&self) -> &Arc<dyn Signer> {
+    &self.signer
+  }
+  pub fn verification_mode(&self) -> VerificationMode {
+    self.verification_mode
+  }
+  /// 根据 `Wechatpay-Serial` 查找验签公钥，可能是平台证书序列号，也可能是微信支付公钥 ID
+  pub fn get_public_key(&self, serial_no: &str) -> Option<PlatformPubKeyInner> {
+    if self.verification_mode != VerificationMode::PlatformCertificate {
+      if let Some(key) = self
+        .wechatpay_public_key
+        .as_ref()
+        .filter(|key| key.serial_no == serial_no)
+      {
+        return Some(key.clone());
+      }
+    }
+    if self.verification_mode == VerificationMode::PublicKey {
+      return None;
+    }
+    self
+      .public_keys
+      .read()
+      .unwrap()
+      .iter()
+      .find(|x| x.serial_no == serial_no)
+      .cloned()
+  }
+  pub fn update_public_keys(&self, platform_pub_keys: Vec<PlatformPubKey>) {
+    let platform_pub_keys = platform_pub_keys
+      .into_iter()
+      .filter_map(|key| PlatformPubKeyInner::try_from(key).ok())
+      .collect::<Vec<_>>();
+    if !platform_pub_keys.is_empty() {
+      *self.public_keys.write().unwrap() = platform_pub_keys;
+    }
+  }
+  // select the latest public key
+  pub fn get_latest_public_key(&self) -> Option<PlatformPubKeyInner> {
+    let now = Utc::now().timestamp().try_into().unwrap();
+    self
+      .public_keys
+      .read()
+      .unwrap()
+      .iter()
+      .filter(|x| x.effective_time < now && x.expire_time > now)
+      .max_by_key(|x| x.effective_time)
+      .cloned()
+  }
+  /// 加密敏感信息时使用的公钥，其 `serial_no` 即请求头 `Wechatpay-Serial` 的值
+  pub fn get_encrypt_public_key(&self) -> Option<PlatformPubKeyInner> {
+    match self.verification_mode {
+      VerificationMode::PlatformCertificate => self.get_latest_public_key(),
+      VerificationMode::PublicKey | VerificationMode::Hybrid => self.wechatpay_public_key.clone(),
+    }
+  }
+  // check public keys is empty
+  pub fn is_public_keys_empty(&self) -> bool {
+    self.public_keys.read().unwrap().is_empty()
+  }
+}
@@ -0,0 +1,265 @@
+//! # 限流
+//! 超过接口调用频率时，微信支付会返回 `FREQUENCY_LIMITED`、`RATELIMIT_EXCEEDED` 或 `FREQUENCY_LIMIT_EXCEED`。
+//! [RateLimiter] 在客户端按令牌桶算法限制请求频率，并可以限制同时进行的请求数：
+//! - 全局限制作用于所有接口，接口限制以路径模板区分，例如 `/v3/refund/domestic/refunds`
+//! - 超过限制时按 [RateLimitMode] 等待或立即返回 [WeChatPayError::RateLimited]
+//! - [RateLimiter] 可以克隆并在多个任务、多个客户端之间共享
+//!
+//! # Example
+//! ```no_run
+//! use wechat_pay_sdk::{RateLimit, RateLimiter};
+//!
+//! let limiter = RateLimiter::new()
+//!   .global(RateLimit::per_second(500))
+//!   .endpoint("/v3/refund/domestic/refunds", RateLimit::per_second(150))
+//!   .max_concurrency(64);
+//! ```
+use crate::WeChatPayError;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+
+/// 令牌桶参数
+#[derive(Debug, Clone, Copy)]
+pub struct RateLimit {
+  /// 每秒补充的令牌数
+  pub rate: f64,
+  /// 令牌桶容量，即允许的突发请求数
+  pub burst: u32,
+}
+
+impl RateLimit {
+  /// 每秒最多 `n` 次请求，突发请求数为 `n`
+  pub fn per_second(n: u32) -> Self {
+    Self {
+      rate: n as f64,
+      burst: n.max(1),
+    }
+  }
+  /// 每分钟最多 `n` 次请求，突发请求数为 `n`
+  pub fn per_minute(n: u32) -> Self {
+    Self {
+      rate: n as f64 / 60.0,
+      burst: n.max(1),
+    }
+  }
+  pub fn burst(mut self, burst: u32) -> Self {
+    self.burst = burst.max(1);
+    self
+  }
+}
+
+/// 超过限制时的行为
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum RateLimitMode {
+  /// 等待直到获得令牌
+  #[default]
+  Wait,
+  /// 立即返回 [WeChatPayError::RateLimited]
+  Reject,
+}
+
+#[derive(Debug)]
+struct TokenBucket {
+  limit: RateLimit,
+  state: Mutex<(f64, Instant)>,
+}
+
+impl TokenBucket {
+  fn new(limit: RateLimit) -> Self {
+    assert!(
+      limit.rate.is_finite() && limit.rate > 0.0,
+      "RateLimit rate must be finite and greater than zero"
+    );
+    Self {
+      limit,
+      state: Mutex::new((limit.burst as f64, Instant::now())),
+    }
+  }
+
+  /// 获取一个令牌，令牌不足时返回需要等待的时间
+  fn try_acquire(&self) -> Result<(), Duration> {
+    let mut state = self.state.lock().unwrap();
+    let (tokens, last) = *state;
+    let now = Instant::now();
+    let tokens = (tokens + now.duration_since(last).as_secs_f64() * self.limit.rate)
+      .min(self.limit.burst as f64);
+    if tokens >= 1.0 {
+      *state = (tokens - 1.0, now);
+      Ok(())
+    } else {
+      *state = (tokens, now);
+      Err(Duration::try_from_secs_f64((1.0 - tokens) / self.limit.rate).unwrap_or(Duration::MAX))
+    }
+  }
+
+  /// 归还一个未使用的令牌
+  fn release(&self) {
+    let mut state = self.state.lock().unwrap();
+    state.0 = (state.0 + 1.0).min(self.limit.burst as f64);
+  }
+}
+
+#[derive(Debug, Default)]
+struct Inner {
+  mode: RateLimitMode,
+  global: Option<TokenBucket>,
+  endpoints: HashMap<String, TokenBucket>,
+  concurrency: Option<Arc<Semaphore>>,
+}
+
+/// # 客户端限流器
+/// 通过 [ClientBuilder::rate_limiter](crate::ClientBuilder::rate_limiter) 启用
+///
+/// 克隆后的限流器共享状态，因此需要在克隆前完成配置
+#[derive(Debug, Clone, Default)]
+pub struct RateLimiter {
+  inner: Arc<Inner>,
+}
+
+/// 请求完成前需要持有的许可
+#[derive(Debug)]
+pub(crate) struct RateLimitPermit {
+  _concurrency: Option<OwnedSemaphorePermit>,
+}
+
+impl RateLimiter {
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  fn inner_mut(&mut self) -> &mut Inner {
+    Arc::get_mut(&mut self.inner).expect("RateLimiter must be configured before it is shared")
+  }
+  /// 超过限制时的行为，默认等待
+  pub fn mode(mut self, mode: RateLimitMode) -> Self {
+    self.inner_mut().mode = mode;
+    self
+  }
+  /// 作用于所有接口的限制
+  ///
+  /// # Panics
+  ///
+  /// `limit.rate` 不是大于 0 的有限数时 panic
+  pub fn global(mut self, limit: RateLimit) -> Self {
+    self.inner_mut().global = Some(TokenBucket::new(limit));
+    self
+  }
+  /// 单个接口的限制，`template` 为不含域名和查询参数的路径模板
+  ///
+  /// # Panics
+  ///
+  /// `limit.rate` 不是大于 0 的有限数时 panic
+  pub fn endpoint(mut self, template: &str, limit: RateLimit) -> Self {
+    self
+      .inner_mut()
+      .endpoints
+      .insert(template.to_string(), TokenBucket::new(limit));
+    self
+  }
+  /// 同时进行的请求数上限
+  pub fn max_concurrency(mut self, max: usize) -> Self {
+    self.inner_mut().concurrency = Some(Arc::new(Semaphore::new(max.max(1))));
+    self
+  }
+
+  /// 获取发送一次请求的许可
+  ///
+  /// 任一限制拒绝时归还已取得的令牌，避免被拒绝的请求占用其他限制的额度
+  pub(crate) async fn acquire(&self, template: &str) -> Result<RateLimitPermit, WeChatPayError> {
+    let inner = &self.inner;
+    let buckets: Vec<&TokenBucket> = inner
+      .global
+      .iter()
+      .chain(inner.endpoints.get(template))
+      .collect();
+    let mut acquired: Vec<&TokenBucket> = Vec::with_capacity(buckets.len());
+    let result = async {
+      for bucket in buckets {
+        self.acquire_token(bucket, template).await?;
+        acquired.push(bucket);
+      }
+      match &inner.concurrency {
+        None => Ok(None),
+        Some(semaphore) => {
+          let permit = match inner.mode {
+            RateLimitMode::Wait => semaphore.clone().acquire_owned().await.ok(),
+            RateLimitMode::Reject => semaphore.clone().try_acquire_owned().ok(),
+          };
+          permit
+            .map(Some)
+            .ok_or_else(|| WeChatPayError::RateLimited(template.to_string()))
+        }
+      }
+    }
+    .await;
+    match result {
+      Ok(concurrency) => Ok(RateLimitPermit {
+        _concurrency: concurrency,
+      }),
+      Err(err) => {
+        acquired.iter().for_each(|bucket| bucket.release());
+        Err(err)
+      }
+    }
+  }
+
+  async fn acquire_token(
+    &self,
+    bucket: &TokenBucket,
+    template: &str,
+  ) -> Result<(), WeChatPayError> {
+    loop {
+      match bucket.try_acquire() {
+        Ok(()) => return Ok(()),
+        Err(wait) if self.inner.mode == RateLimitMode::Wait && wait != Duration::MAX => {
+          tokio::time::sleep(wait).await
+        }
+        Err(_) => return Err(WeChatPayError::RateLimited(template.to_string())),
+      }
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[tokio::test]
+  async fn rejected_request_keeps_global_token() {
+    let limiter = RateLimiter::new()
+      .mode(RateLimitMode::Reject)
+      .global(RateLimit::per_minute(2))
+      .endpoint("/v3/refund/domestic/refunds", RateLimit::per_minute(1));
+    assert!(limiter.acquire("/v3/refund/domestic/refunds").await.is_ok());
+    assert!(matches!(
+      limiter.acquire("/v3/refund/domestic/refunds").await,
+      Err(WeChatPayError::RateLimited(_))
+    ));
+    // 被接口限制拒绝的请求没有消耗全局令牌
+    assert!(limiter.acquire("/v3/pay/transactions/native").await.is_ok());
+    assert!(limiter
+      .acquire("/v3/pay/transactions/native")
+      .await
+      .is_err());
+  }
+
+  #[tokio::test]
+  async fn rejected_by_concurrency_refunds_tokens() {
+    let limiter = RateLimiter::new()
+      .mode(RateLimitMode::Reject)
+      .global(RateLimit::per_minute(2))
+      .max_concurrency(1);
+    let permit = limiter.acquire("/v3/certificates").await.unwrap();
+    assert!(limiter.acquire("/v3/certificates").await.is_err());
+    drop(permit);
+    assert!(limiter.acquire("/v3/certificates").await.is_ok());
+  }
+
+  #[test]
+  #[should_panic(expected = "finite and greater than zero")]
+  fn zero_rate_is_rejected() {
+    let _ = RateLimiter::new().global(RateLimit::per_second(0));
+  }
+}
@@ -0,0 +1,150 @@
+//! # 重试策略
+//! [错误码](https://pay.weixin.qq.com/wiki/doc/apiv3/Share/error_code.shtml)文档中，`SYSTEM_ERROR`、`BANK_ERROR`、`BIZ_ERR_NEED_RETRY`
+//! 等错误要求使用相同参数重新调用。[RetryPolicy] 让客户端自动完成重试：
+//! - 只重试幂等的接口，如下单、退款、转账等以商户单号去重的接口以及所有查询接口
+//! - 每次重试都会重新签名，使用新的随机串和时间戳
+//! - 重试间隔按指数增长，并可以加入随机抖动
+use rand::{thread_rng, Rng};
+use reqwest::StatusCode;
+use serde::Deserialize;
+use std::time::Duration;
+
+#[derive(Debug, Clone)]
+pub struct RetryPolicy {
+  /// 最多尝试次数（包含第一次请求），为 1 时不重试
+  pub max_attempts: u32,
+  /// 第一次重试前的等待时间
+  pub initial_backoff: Duration,
+  /// 最长等待时间
+  pub max_backoff: Duration,
+  /// 每次重试等待时间的增长倍数
+  pub multiplier: f64,
+  /// 是否在等待时间上加入随机抖动（在 0 到计算出的等待时间之间随机取值）
+  pub jitter: bool,
+  /// 需要重试的微信支付错误码
+  pub retry_codes: Vec<String>,
+  /// 需要重试的 HTTP 状态码
+  pub retry_statuses: Vec<u16>,
+  /// 是否重试连接失败（包括连接超时）的网络错误。连接建立后的超时等错误无法确定微信支付是否已处理请求，不会重试
+  pub retry_network_errors: bool,
+}
+
+impl Default for RetryPolicy {
+  /// 最多尝试 3 次，重试 `SYSTEM_ERROR`、`BANK_ERROR`、`BIZ_ERR_NEED_RETRY`、202 及 5xx 应答和连接失败
+  fn default() -> Self {
+    Self {
+      max_attempts: 3,
+      initial_backoff: Duration::from_millis(200),
+      max_backoff: Duration::from_secs(5),
+      multiplier: 2.0,
+      jitter: true,
+      retry_codes: vec![
+        "SYSTEM_ERROR".to_string(),
+        "BANK_ERROR".to_string(),
+        "BIZ_ERR_NEED_RETRY".to_string(),
+      ],
+      retry_statuses: vec![202, 500, 502, 503, 504],
+      retry_network_errors: true,
+    }
+  }
+}
+
+#[derive(Deserialize)]
+struct ErrorCode {
+  code: String,
+}
+
+impl RetryPolicy {
+  /// 不重试
+  pub fn none() -> Self {
+    Self {
+      max_attempts: 1,
+      ..Default::default()
+    }
+  }
+  pub fn max_attempts(mut self, max_attempts: u32) -> Self {
+    self.max_attempts = max_attempts.max(1);
+    self
+  }
+  pub fn backoff(mut self, initial: Duration, max: Duration) -> Self {
+    self.initial_backoff = initial;
+    self.max_backoff = max;
+    self
+  }
+  pub fn multiplier(mut self, multiplier: f64) -> Self {
+    self.multiplier = multiplier;
+    self
+  }
+  pub fn jitter(mut self, jitter: bool) -> Self {
+    self.jitter = jitter;
+    self
+  }
+  pub fn retry_codes(mut self, codes: &[&str]) -> Self {
+    self.retry_codes = codes.iter().map(|code| code.to_string()).collect();
+    self
+  }
+  pub fn retry_statuses(mut self, statuses: &[u16]) -> Self {
+    self.retry_statuses = statuses.to_vec();
+    self
+  }
+  pub fn retry_network_errors(mut self, retry: bool) -> Self {
+    self.retry_network_errors = retry;
+    self
+  }
+
+  /// 第 `attempt` 次请求失败后的等待时间，`attempt` 从 1 开始
+  pub fn backoff_for(&self, attempt: u32) -> Duration {
+    let exp = self
+      .multiplier
+      .powi(attempt.saturating_sub(1).min(i32::MAX as u32) as i32);
+    // 先以秒为单位截断到上限再构造 Duration，避免指数增长溢出
+    let secs =
+      (self.initial_backoff.as_secs_f64() * exp.max(0.0)).min(self.max_backoff.as_secs_f64());
+    let backoff = Duration::try_from_secs_f64(secs).unwrap_or(self.max_backoff);
+    if self.jitter && !backoff.is_zero() {
+      backoff.mul_f64(thread_rng().gen_range(0.0..=1.0))
+    } else {
+      backoff
+    }
+  }
+
+  pub(crate) fn should_retry_network(&self, err: &reqwest::Error) -> bool {
+    self.retry_network_errors && err.is_connect()
+  }
+
+  pub(crate) fn should_retry_status(&self, status: StatusCode) -> bool {
+    self.retry_statuses.contains(&status.as_u16())
+  }
+
+  pub(crate) fn should_retry_body(&self, status: StatusCode, body: &str) -> bool {
+    if status.is_success() || self.retry_codes.is_empty() {
+      return false;
+    }
+    error_code(body).is_some_and(|code| self.retry_codes.contains(&code))
+  }
+}
+
+/// 从错误应答中取出错误码
+pub(crate) fn error_code(body: &str) -> Option<String> {
+  serde_json::from_str::<ErrorCode>(body)
+    .ok()
+    .map(|err| err.code)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn backoff_is_capped_for_large_attempts() {
+    let policy = RetryPolicy::default()
+      .backoff(Duration::from_millis(100), Duration::from_secs(5))
+      .multiplier(2.0)
+      .jitter(false);
+    assert_eq!(policy.backoff_for(1), Duration::from_millis(100));
+    assert_eq!(policy.backoff_for(2), Duration::from_millis(200));
+    for attempt in [64, 1024, u32::MAX] {
+      assert_eq!(policy.backoff_for(attempt), Duration::from_secs(5));
+    }
+  }
+}
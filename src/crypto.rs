@@ -1,3 +1,9 @@
+use crate::client::{
+  endpoint_template, error_code, is_idempotent, PlatformPubKeyInner, VerificationMode,
+};
+use crate::error::RawResponse;
+use crate::sensitive::with_encrypt_key;
+use crate::signer::RsaSigner;
 use crate::{Client, WeChatPayError};
 use aes_gcm::aead::Payload;
 use aes_gcm::{
@@ -6,23 +12,26 @@ use aes_gcm::{
 };
 use base64::{engine::general_purpose, Engine};
 use rand::{distributions::Alphanumeric, thread_rng, Rng};
-use reqwest::{header, Method, Response, StatusCode, Url};
-use std::time::{SystemTime, UNIX_EPOCH};
+use reqwest::{header, header::HeaderMap, Method, Response, StatusCode, Url};
+use std::future::Future;
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+use tracing::{field, Instrument, Span};
 
 use rsa::sha2::{Digest, Sha256};
-use rsa::{Pkcs1v15Sign, RsaPrivateKey};
+use rsa::{Pkcs1v15Sign, RsaPrivateKey, RsaPublicKey};
 impl Client {
-  pub fn sha256_with_rsa(
+  /// SHA256withRSA 签名，返回 Base64 编码的签名
+  ///
+  /// 未指定 `private_key` 时使用客户端的 [Signer](crate::signer::Signer)
+  pub async fn sha256_with_rsa(
     &self,
     content: &[u8],
     private_key: Option<RsaPrivateKey>,
   ) -> Result<String, WeChatPayError> {
-    let private_key = private_key.unwrap_or(self.private_key.clone());
-    // let mut hasher = Sha256::new();
-    let mut hasher: Sha256 = Digest::new();
-    hasher.update(content);
-    let hex = hasher.finalize();
-    let signature = private_key.sign(Pkcs1v15Sign::new::<Sha256>(), &hex)?;
+    let signature = match private_key {
+      Some(private_key) => RsaSigner::new(private_key).sign_sync(content)?,
+      None => self.signer.sign(content).await?,
+    };
     Ok(general_purpose::STANDARD.encode(signature))
   }
 
@@ -70,19 +79,14 @@ impl Client {
     Ok(plaintext)
   }
 
-  pub fn request_authorization(
+  pub async fn request_authorization(
     &self,
     method: &Method,
     path: &str,
     content: &str,
   ) -> Result<String, WeChatPayError> {
     let timestamp = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
-    // TODO: don't create ThreadRng every time
-    let nonce = thread_rng()
-      .sample_iter(Alphanumeric)
-      .take(32)
-      .map(char::from)
-      .collect::<String>();
+    let nonce = nonce_str();
     let content = format!(
       "{}\n{}\n{}\n{}\n{}\n",
       method.as_str(),
@@ -91,7 +95,7 @@ impl Client {
       nonce,
       content
     );
-    let signature = self.sha256_with_rsa(content.as_bytes(), None)?;
+    let signature = self.sha256_with_rsa(content.as_bytes(), None).await?;
     Ok(format!(
       "WECHATPAY2-SHA256-RSA2048 mchid=\"{}\",nonce_str=\"{}\",signature=\"{}\",timestamp=\"{}\",serial_no=\"{}\"",
       self.merchant_id,
@@ -102,126 +106,320 @@ impl Client {
     ))
   }
 
-  /// # 发送请求
-  /// 包含
-  /// [签名生成](https://pay.weixin.qq.com/wiki/doc/apiv3/wechatpay/wechatpay4_0.shtml)
-  /// [签名验证](https://pay.weixin.qq.com/wiki/doc/apiv3/wechatpay/wechatpay4_1.shtml)
-  /// 逻辑，返回结果参考
-  /// [parse_response](Self::parse_response)
-  pub async fn send_request<Request, Response>(
+  /// 构造带签名的请求，不发送
+  pub(crate) async fn build_request<Request>(
     &self,
     method: Method,
     url: &str,
     query: Option<&[(&str, &str)]>,
     body: Option<&Request>,
-  ) -> Result<Option<Response>, WeChatPayError>
+  ) -> Result<reqwest::Request, WeChatPayError>
   where
     Request: serde::Serialize,
-    Response: serde::de::DeserializeOwned + Send + 'static,
   {
-    let verify = url != "/v3/certificates";
-    let api = format!("https://api.mch.weixin.qq.com{}", url);
-    let (url, path) = if let Some(query) = query {
+    let (url, path) = self.request_url(url, query)?;
+    let pub_key = self.encrypt_public_key_for(body.is_some()).await;
+    let content = self.serialize_body(body, pub_key.as_ref())?;
+    self
+      .sign_request(method, url, &path, content, pub_key.as_ref())
+      .await
+  }
+
+  /// 请求加密敏感字段使用的公钥
+  ///
+  /// 有请求体且本地还没有平台证书时先下载平台证书，下载失败时仍然发送请求，
+  /// 请求体中有敏感字段时由 [serialize_body](Self::serialize_body) 返回错误
+  async fn encrypt_public_key_for(&self, has_body: bool) -> Option<PlatformPubKeyInner> {
+    let pub_key = self.get_encrypt_public_key();
+    if pub_key.is_some()
+      || !has_body
+      || self.verification_mode != VerificationMode::PlatformCertificate
+    {
+      return pub_key;
+    }
+    // 下载平台证书本身也会构造请求，这里需要装箱以打断异步递归
+    if let Err(err) = Box::pin(self.refresh_certificates_if_stale()).await {
+      tracing::warn!(error = %err, "failed to download platform certificates for encryption");
+    }
+    self.get_encrypt_public_key()
+  }
+
+  /// 序列化请求体，其中标注为[敏感信息](crate::sensitive)的字段使用 `pub_key` 加密
+  fn serialize_body<Request>(
+    &self,
+    body: Option<&Request>,
+    pub_key: Option<&PlatformPubKeyInner>,
+  ) -> Result<Option<String>, WeChatPayError>
+  where
+    Request: serde::Serialize,
+  {
+    with_encrypt_key(pub_key.map(|key| &key.key), || {
+      body.map(serde_json::to_string).transpose()
+    })
+  }
+
+  /// 返回完整的请求 URL 以及参与签名的路径（含查询参数）
+  fn request_url(
+    &self,
+    url: &str,
+    query: Option<&[(&str, &str)]>,
+  ) -> Result<(Url, String), WeChatPayError> {
+    let api = self.api_url(url);
+    if let Some(query) = query {
       let u = Url::parse_with_params(&api, query)?;
       let query = u.query().unwrap_or("").to_string();
-      (u, format!("{}?{}", url, query))
+      Ok((u, format!("{}?{}", url, query)))
     } else {
-      (Url::parse(&api)?, url.to_string())
-    };
+      Ok((Url::parse(&api)?, url.to_string()))
+    }
+  }
+
+  /// 为请求签名，每次调用都会使用新的随机串和时间戳
+  async fn sign_request(
+    &self,
+    method: Method,
+    url: Url,
+    path: &str,
+    content: Option<String>,
+    pub_key: Option<&PlatformPubKeyInner>,
+  ) -> Result<reqwest::Request, WeChatPayError> {
     let mut req = reqwest::Request::new(method.clone(), url);
-    let content = if let Some(body) = body {
+    let signature = self
+      .request_authorization(&method, path, content.as_deref().unwrap_or(""))
+      .await?;
+    if let Some(content) = content {
       req.headers_mut().insert(
         header::CONTENT_TYPE,
         header::HeaderValue::from_static("application/json"),
       );
-      *req.body_mut() = Some(serde_json::to_vec(body)?.into());
-      serde_json::to_string(body)?
-    } else {
-      "".to_string()
-    };
-    let signature = self.request_authorization(&method, &path, &content)?;
+      *req.body_mut() = Some(content.into());
+    }
     req.headers_mut().insert(
       header::ACCEPT,
       header::HeaderValue::from_str("application/json")?,
     );
     req.headers_mut().insert(
       header::USER_AGENT,
-      header::HeaderValue::from_str("wechat-pay-sdk-rs")?,
+      header::HeaderValue::from_str(&self.user_agent)?,
     );
     req.headers_mut().insert(
       header::AUTHORIZATION,
       header::HeaderValue::from_str(&signature)?,
     );
-    req.headers_mut().insert(
-      "Wechatpay-Serial",
-      header::HeaderValue::from_str(&self.merchant_serial_number)?,
+    // Wechatpay-Serial 为加密敏感信息所用的平台证书序列号或微信支付公钥 ID
+    if let Some(pub_key) = pub_key {
+      req.headers_mut().insert(
+        "Wechatpay-Serial",
+        header::HeaderValue::from_str(&pub_key.serial_no)?,
+      );
+    }
+    Ok(req)
+  }
+
+  /// # 发送请求
+  /// 包含
+  /// [签名生成](https://pay.weixin.qq.com/wiki/doc/apiv3/wechatpay/wechatpay4_0.shtml)
+  /// [签名验证](https://pay.weixin.qq.com/wiki/doc/apiv3/wechatpay/wechatpay4_1.shtml)
+  /// 逻辑，返回结果参考
+  /// [parse_response](Self::parse_response)
+  ///
+  /// 对于幂等的接口，失败时按照 [RetryPolicy](crate::RetryPolicy) 重试
+  pub async fn send_request<Request, Response>(
+    &self,
+    method: Method,
+    url: &str,
+    query: Option<&[(&str, &str)]>,
+    body: Option<&Request>,
+  ) -> Result<Option<Response>, WeChatPayError>
+  where
+    Request: serde::Serialize,
+    Response: serde::de::DeserializeOwned + Send + 'static,
+  {
+    let (request_url, path) = self.request_url(url, query)?;
+    // 同一个请求的多次尝试使用相同的密文和 Wechatpay-Serial
+    let pub_key = self.encrypt_public_key_for(body.is_some()).await;
+    let content = self.serialize_body(body, pub_key.as_ref())?;
+    let response = self
+      .execute(&method, url, || {
+        self.sign_request(
+          method.clone(),
+          request_url.clone(),
+          &path,
+          content.clone(),
+          pub_key.as_ref(),
+        )
+      })
+      .await?;
+    Self::parse_response(response).await
+  }
+
+  /// 发送请求并验证应答签名
+  ///
+  /// `build` 在每次尝试前调用，需要返回重新签名的请求；`path` 为不含查询参数的请求路径
+  ///
+  /// 整个过程（包括重试）记录在 `wechat_pay.request` span 中，只包含接口模板、状态码、错误码等，不记录请求头和请求体
+  pub(crate) async fn execute<F, Fut>(
+    &self,
+    method: &Method,
+    path: &str,
+    build: F,
+  ) -> Result<RawResponse, WeChatPayError>
+  where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<reqwest::Request, WeChatPayError>>,
+  {
+    let template = endpoint_template(method, path);
+    let span = tracing::info_span!(
+      "wechat_pay.request",
+      http.method = %method,
+      path = template,
+      mchid = %self.merchant_id,
+      http.status = field::Empty,
+      request_id = field::Empty,
+      error_code = field::Empty,
+      attempt = field::Empty,
+      duration_ms = field::Empty,
     );
+    let started = Instant::now();
+    let result = self
+      .execute_attempts(method, path, template, build)
+      .instrument(span.clone())
+      .await;
+    span.record("duration_ms", started.elapsed().as_millis() as u64);
+    if let Err(err) = &result {
+      tracing::warn!(parent: &span, error = %err, "wechat pay request failed");
+    }
+    result
+  }
 
-    let client = reqwest::Client::new();
-    let res = client.execute(req).await?;
-    let (status, text) = if verify {
-      self.verify_signatrue(res).await?
+  async fn execute_attempts<F, Fut>(
+    &self,
+    method: &Method,
+    path: &str,
+    template: &str,
+    mut build: F,
+  ) -> Result<RawResponse, WeChatPayError>
+  where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<reqwest::Request, WeChatPayError>>,
+  {
+    let span = Span::current();
+    let max_attempts = if is_idempotent(method, path) {
+      self.retry_policy.max_attempts.max(1)
     } else {
-      (res.status(), res.text().await?)
+      1
     };
-    Self::parse_response(status, text).await
+    let mut attempt = 1;
+    loop {
+      span.record("attempt", attempt);
+      let retryable = attempt < max_attempts;
+      let permit = match &self.rate_limiter {
+        Some(rate_limiter) => Some(rate_limiter.acquire(template).await?),
+        None => None,
+      };
+      let res = match self.dispatch(template, &mut build).await? {
+        Ok(res) => res,
+        Err(err) if retryable && self.retry_policy.should_retry_network(&err) => {
+          let backoff = self.retry_policy.backoff_for(attempt);
+          tracing::info!(
+            attempt,
+            error = %err,
+            backoff_ms = backoff.as_millis() as u64,
+            "retrying after network error"
+          );
+          drop(permit);
+          tokio::time::sleep(backoff).await;
+          attempt += 1;
+          continue;
+        }
+        Err(err) => return Err(err.into()),
+      };
+      span.record("http.status", res.status().as_u16());
+      let request_id = RawResponse::request_id(res.headers());
+      if let Some(request_id) = &request_id {
+        span.record("request_id", request_id.as_str());
+      }
+      if retryable && self.retry_policy.should_retry_status(res.status()) {
+        let backoff = self.retry_policy.backoff_for(attempt);
+        tracing::info!(
+          attempt,
+          status = res.status().as_u16(),
+          backoff_ms = backoff.as_millis() as u64,
+          "retrying after status"
+        );
+        drop(permit);
+        tokio::time::sleep(backoff).await;
+        attempt += 1;
+        continue;
+      }
+      let (status, text) = self.verify_signatrue(res).await?;
+      if !status.is_success() {
+        if let Some(code) = error_code(&text) {
+          span.record("error_code", code.as_str());
+        }
+      }
+      if retryable && self.retry_policy.should_retry_body(status, &text) {
+        let backoff = self.retry_policy.backoff_for(attempt);
+        tracing::info!(
+          attempt,
+          backoff_ms = backoff.as_millis() as u64,
+          "retrying after error code"
+        );
+        drop(permit);
+        tokio::time::sleep(backoff).await;
+        attempt += 1;
+        continue;
+      }
+      return Ok(RawResponse {
+        status,
+        request_id,
+        body: text,
+      });
+    }
   }
 
+  /// 验证应答签名，找不到 `Wechatpay-Serial` 对应的平台公钥时会尝试重新下载平台证书
+  #[tracing::instrument(
+    name = "wechat_pay.verify_signature",
+    skip_all,
+    fields(http.status = response.status().as_u16(), serial = field::Empty),
+    err(Display)
+  )]
   pub async fn verify_signatrue(
     &self,
     response: Response,
   ) -> Result<(StatusCode, String), WeChatPayError> {
-    let timestamp = Self::get_header(&response, "Wechatpay-Timestamp")?;
-    Self::verify_timestamp(timestamp.as_str())?;
-
-    let serial_no = Self::get_header(&response, "Wechatpay-Serial")?;
-    let signature = Self::get_header(&response, "Wechatpay-Signature")?;
-    let nonce = Self::get_header(&response, "Wechatpay-Nonce")?;
+    let signature = SignatureHeaders::from_headers(response.headers())?;
+    Span::current().record("serial", signature.serial.as_str());
+    Self::verify_timestamp(signature.timestamp.as_str())?;
 
     let status = response.status();
     let body = response.text().await?;
 
-    let message = format!(
-      "{}\n{}\n{}\n",
-      timestamp.as_str(),
-      nonce.as_str(),
-      body.as_str()
-    );
-    let pub_key =
-      self
-        .get_public_key(serial_no.as_ref())
-        .ok_or(WeChatPayError::VerifySignatureFail(
-          "No public key found".to_string(),
-        ))?;
-
-    // let pub_key = RsaPublicKey::from_public_key_pem(&pub_key.key).map_err(|e| {
-    //   WeChatPayError::VerifySignatureFail(format!("public key parser error: {}", e))
-    // })?;
-    let pub_key = &pub_key.key;
-    let mut hasher: Sha256 = Digest::new();
-    hasher.update(message);
-    let hex = hasher.finalize();
-    let signatrue = general_purpose::STANDARD
-      .decode(signature.as_str())
-      .map_err(|e| WeChatPayError::VerifySignatureFail(format!("signature decode error: {}", e)))?;
-    let scheme = Pkcs1v15Sign::new::<Sha256>();
-    pub_key
-      .verify(scheme, &hex, signatrue.as_slice())
-      .map(|_| (status, body))
-      .map_err(|e| WeChatPayError::VerifySignatureFail(e.to_string()))
+    let pub_key = self
+      .find_public_key(signature.serial.as_str())
+      .await
+      .ok_or(WeChatPayError::VerifySignatureFail(
+        "No public key found".to_string(),
+      ))?;
+    signature.verify(&pub_key.key, body.as_str())?;
+    Ok((status, body))
   }
 
-  fn get_header(response: &Response, key: &str) -> Result<String, WeChatPayError> {
-    Ok(
-      response
-        .headers()
-        .get(key)
-        .ok_or_else(|| WeChatPayError::VerifySignatureFail(format!("Missing {}", key)))?
-        .to_str()?
-        .to_string(),
-    )
+  /// 查找平台公钥，必要时下载平台证书
+  pub(crate) async fn find_public_key(&self, serial_no: &str) -> Option<PlatformPubKeyInner> {
+    if let Some(pub_key) = self.get_public_key(serial_no) {
+      return Some(pub_key);
+    }
+    if self.verification_mode == VerificationMode::PublicKey {
+      return None;
+    }
+    self.reload_public_keys_if_stale(serial_no).await.ok()?;
+    self.get_public_key(serial_no)
   }
-  fn verify_timestamp(timestamp: &str) -> Result<(), WeChatPayError> {
+
+  /// 签名中的时间戳与当前时间相差不能超过 5 分钟
+  pub(crate) fn verify_timestamp(timestamp: &str) -> Result<(), WeChatPayError> {
     let timestamp = timestamp
       .parse::<u64>()
       .map_err(|_| WeChatPayError::VerifySignatureFail("Failed to parse timestamp".to_string()))?;
@@ -234,3 +432,62 @@ impl Client {
     Ok(())
   }
 }
+
+/// 32 位随机串，用于签名
+pub(crate) fn nonce_str() -> String {
+  // TODO: don't create ThreadRng every time
+  thread_rng()
+    .sample_iter(Alphanumeric)
+    .take(32)
+    .map(char::from)
+    .collect()
+}
+
+/// 应答（或回调）中与签名相关的 HTTP 头
+#[derive(Debug)]
+pub(crate) struct SignatureHeaders {
+  pub timestamp: String,
+  pub nonce: String,
+  pub serial: String,
+  pub signature: String,
+}
+
+impl SignatureHeaders {
+  pub fn from_headers(headers: &HeaderMap) -> Result<Self, WeChatPayError> {
+    let get_header = |key: &str| -> Result<String, WeChatPayError> {
+      Ok(
+        headers
+          .get(key)
+          .ok_or_else(|| WeChatPayError::VerifySignatureFail(format!("Missing {}", key)))?
+          .to_str()?
+          .to_string(),
+      )
+    };
+    Ok(Self {
+      timestamp: get_header("Wechatpay-Timestamp")?,
+      nonce: get_header("Wechatpay-Nonce")?,
+      serial: get_header("Wechatpay-Serial")?,
+      signature: get_header("Wechatpay-Signature")?,
+    })
+  }
+
+  /// 使用平台公钥验证 `body` 的签名
+  pub fn verify(&self, pub_key: &RsaPublicKey, body: &str) -> Result<(), WeChatPayError> {
+    let message = format!(
+      "{}\n{}\n{}\n",
+      self.timestamp.as_str(),
+      self.nonce.as_str(),
+      body
+    );
+    let mut hasher: Sha256 = Digest::new();
+    hasher.update(message);
+    let hex = hasher.finalize();
+    let signatrue = general_purpose::STANDARD
+      .decode(self.signature.as_str())
+      .map_err(|e| WeChatPayError::VerifySignatureFail(format!("signature decode error: {}", e)))?;
+    let scheme = Pkcs1v15Sign::new::<Sha256>();
+    pub_key
+      .verify(scheme, &hex, signatrue.as_slice())
+      .map_err(|e| WeChatPayError::VerifySignatureFail(e.to_string()))
+  }
+}
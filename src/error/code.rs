@@ -1,16 +1,28 @@
 use serde::Deserialize;
 
 /// 微信支付接口错误码
-#[derive(Debug, Deserialize)]
+///
+/// 未列出的错误码解析为 [Other](Self::Other)，微信支付新增错误码不会导致解析失败
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
 #[serde(untagged)]
 pub enum WeChatPayApiErrorCode {
   Common(Common),
   Order(Order),
   Refund(Refund),
+  /// 未知错误码
+  Other(String),
+}
+
+impl WeChatPayApiErrorCode {
+  /// 解析错误码，从不失败
+  pub fn parse(code: &str) -> Self {
+    serde_json::from_value(serde_json::Value::String(code.to_string()))
+      .unwrap_or_else(|_| Self::Other(code.to_string()))
+  }
 }
 
 /// [公共错误码](https://pay.weixin.qq.com/wiki/doc/apiv3/Share/error_code.shtml)
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
 #[serde(rename_all = "SCREAMING_SNAKE_CASE")]
 pub enum Common {
   /// 商户号与 appid 不匹配
@@ -198,7 +210,7 @@ pub enum Common {
 /// 下单错误：
 /// - [JSApi 下单](https://pay.weixin.qq.com/wiki/doc/apiv3/apis/chapter3_1_1.shtml)
 /// - [App 下单](https://pay.weixin.qq.com/wiki/doc/apiv3/apis/chapter3_2_1.shtml)
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
 #[serde(rename_all = "SCREAMING_SNAKE_CASE")]
 pub enum Order {
   /// openid和appid不匹配
@@ -213,7 +225,7 @@ pub enum Order {
 
 /// 退款错误
 /// - [申请退款](https://pay.weixin.qq.com/wiki/doc/apiv3/apis/chapter3_1_9.shtml)
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
 #[serde(rename_all = "SCREAMING_SNAKE_CASE")]
 pub enum Refund {
   /// 余额不足
@@ -1,6 +1,8 @@
 use super::WeChatPayError;
 use aes_gcm::Error as AesGcmError;
 use base64::DecodeError;
+use openssl::error::ErrorStack as OpensslError;
+use redis::RedisError;
 use reqwest::header::InvalidHeaderValue;
 use reqwest::header::ToStrError as HttpHeaderToStrError;
 use reqwest::Error as ReqwestError;
@@ -10,26 +12,29 @@ use std::{io::Error as IOError, str::Utf8Error, time::SystemTimeError};
 use url::ParseError;
 
 impl From<AesGcmError> for WeChatPayError {
-  fn from(_: AesGcmError) -> Self {
-    Self::CryptoError("AEAD_AES_256_GCM error".to_string())
+  fn from(err: AesGcmError) -> Self {
+    Self::Crypto {
+      message: "AEAD_AES_256_GCM error".to_string(),
+      source: Box::new(err),
+    }
   }
 }
 
 impl From<DecodeError> for WeChatPayError {
-  fn from(_: DecodeError) -> Self {
-    Self::Unknown("Base64 Decode Error".to_string())
+  fn from(err: DecodeError) -> Self {
+    Self::DecodeError(Box::new(err))
   }
 }
 
 impl From<InvalidHeaderValue> for WeChatPayError {
   fn from(err: InvalidHeaderValue) -> Self {
-    Self::Unknown(err.to_string())
+    Self::HeaderError(Box::new(err))
   }
 }
 
 impl From<HttpHeaderToStrError> for WeChatPayError {
   fn from(err: HttpHeaderToStrError) -> Self {
-    Self::Unknown(err.to_string())
+    Self::HeaderError(Box::new(err))
   }
 }
 
@@ -39,33 +44,54 @@ impl From<ReqwestError> for WeChatPayError {
   }
 }
 
+impl From<RedisError> for WeChatPayError {
+  fn from(err: RedisError) -> Self {
+    Self::RedisError(err)
+  }
+}
+
 impl From<SerdeError> for WeChatPayError {
   fn from(err: SerdeError) -> Self {
-    Self::Unknown(err.to_string())
+    Self::JsonError(err)
   }
 }
 
 impl From<RsaError> for WeChatPayError {
   fn from(err: RsaError) -> Self {
-    Self::CryptoError(err.to_string())
+    Self::Crypto {
+      message: "RSA error".to_string(),
+      source: Box::new(err),
+    }
   }
 }
 
 impl From<Pkcs8DecodeError> for WeChatPayError {
   fn from(err: Pkcs8DecodeError) -> Self {
-    Self::CryptoError(err.to_string())
+    Self::Crypto {
+      message: "PKCS#8 decode error".to_string(),
+      source: Box::new(err),
+    }
+  }
+}
+
+impl From<OpensslError> for WeChatPayError {
+  fn from(err: OpensslError) -> Self {
+    Self::Crypto {
+      message: "OpenSSL error".to_string(),
+      source: Box::new(err),
+    }
   }
 }
 
 impl From<IOError> for WeChatPayError {
   fn from(err: IOError) -> Self {
-    Self::InternalServerError(err.to_string())
+    Self::IoError(err)
   }
 }
 
 impl From<Utf8Error> for WeChatPayError {
-  fn from(_: Utf8Error) -> Self {
-    Self::Unknown("UTF-8 Decode Error".to_string())
+  fn from(err: Utf8Error) -> Self {
+    Self::DecodeError(Box::new(err))
   }
 }
 
@@ -77,6 +103,6 @@ impl From<SystemTimeError> for WeChatPayError {
 
 impl From<ParseError> for WeChatPayError {
   fn from(err: ParseError) -> Self {
-    Self::Unknown(err.to_string())
+    Self::UrlError(err)
   }
 }
@@ -1,10 +1,10 @@
 //! # 错误处理
-mod code;
+pub mod code;
 mod external;
 
 use crate::Client;
 pub use code::WeChatPayApiErrorCode;
-use reqwest::StatusCode;
+use reqwest::{header::HeaderMap, StatusCode};
 use serde::{de::DeserializeOwned, Deserialize};
 
 #[derive(Debug, Deserialize)]
@@ -40,8 +40,14 @@ pub struct WeChatPayApiErrorDetail {
 ///   }
 /// }
 /// ```
-#[derive(Debug, Deserialize)]
+#[derive(Debug)]
 pub struct WeChatPayApiError {
+  /// HTTP 状态码
+  pub status: StatusCode,
+  /// 应答头 `Request-ID`，联系微信支付技术支持时需要提供
+  pub request_id: Option<String>,
+  /// 应答中的原始错误码
+  pub raw_code: String,
   /// 详细错误码
   pub code: WeChatPayApiErrorCode,
   /// 错误描述，使用易理解的文字表示错误的原因。
@@ -49,21 +55,143 @@ pub struct WeChatPayApiError {
   pub detail: Option<WeChatPayApiErrorDetail>,
 }
 
+/// 错误应答的消息体
+#[derive(Deserialize)]
+struct ApiErrorBody {
+  code: String,
+  message: Option<String>,
+  detail: Option<WeChatPayApiErrorDetail>,
+}
+
+impl std::fmt::Display for WeChatPayApiError {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    write!(f, "{} {}", self.status.as_u16(), self.raw_code)?;
+    if let Some(message) = &self.message {
+      write!(f, ": {}", message)?;
+    }
+    if let Some(request_id) = &self.request_id {
+      write!(f, " (Request-ID: {})", request_id)?;
+    }
+    Ok(())
+  }
+}
+
+/// 已验证签名的应答
+#[derive(Debug)]
+pub(crate) struct RawResponse {
+  pub status: StatusCode,
+  /// 应答头 `Request-ID`
+  pub request_id: Option<String>,
+  pub body: String,
+}
+
+impl RawResponse {
+  pub fn request_id(headers: &HeaderMap) -> Option<String> {
+    headers
+      .get("Request-ID")
+      .and_then(|v| v.to_str().ok())
+      .map(str::to_string)
+  }
+}
+
+/// 错误分类，用于按类别处理错误而不必匹配具体的变体或错误信息
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum ErrorKind {
+  /// 配置错误，如缺少或无效的密钥、证书、环境变量、请求域名、请求头
+  Configuration,
+  /// 加密、解密或签名失败
+  Crypto,
+  /// 网络或存储访问失败
+  Transport,
+  /// 应答或回调签名验证失败
+  Signature,
+  /// 微信支付返回的业务错误，包括 202 处理中
+  Api,
+  /// 报文解码或反序列化失败
+  Deserialization,
+  /// 超过客户端限流
+  RateLimited,
+  /// 其他错误
+  Other,
+}
+
 #[derive(Debug)]
 pub enum WeChatPayError {
   NetworkError(reqwest::Error),
   RedisError(redis::RedisError),
-  /// 加密错误，通常是由于配置错误
-  CryptoError(String),
+  /// 配置错误，如缺少或无效的密钥、证书、环境变量
+  ConfigError(String),
+  /// 加密、解密或签名失败，保留底层错误
+  Crypto {
+    message: String,
+    source: Box<dyn std::error::Error + Send + Sync>,
+  },
   WeChatApiError(Box<WeChatPayApiError>),
   /// 服务器已接受请求，但尚未处理，一般的解决方案是使用原参数重复请求一遍
   Accepted,
+  /// 应答无法解析
+  InvalidResponse {
+    status: StatusCode,
+    request_id: Option<String>,
+    body: String,
+    source: serde_json::Error,
+  },
+  /// JSON 序列化或反序列化失败
+  JsonError(serde_json::Error),
+  /// 报文解码失败，如 Base64、UTF-8
+  DecodeError(Box<dyn std::error::Error + Send + Sync>),
+  /// 无效的 URL
+  UrlError(url::ParseError),
+  /// 无效的 HTTP 头
+  HeaderError(Box<dyn std::error::Error + Send + Sync>),
+  /// 读取文件等 I/O 错误
+  IoError(std::io::Error),
   /// 通常应该是 sdk 实现有不当之处
   Unknown(String),
   /// sdk 认为是服务器自身错误，但也有可能是 sdk 的实现错误
   InternalServerError(String),
   /// 签名验证失败
   VerifySignatureFail(String),
+  /// 收到微信支付的签名探测通知（`Wechatpay-Signature` 以 `WECHATPAY/SIGNTEST/` 开头），应当按验签失败应答
+  SignatureProbe,
+  /// 超过客户端限流，参数为接口路径模板
+  RateLimited(String),
+  /// 生成二维码失败，如内容过长
+  QrCodeError(String),
+}
+
+impl WeChatPayError {
+  /// 错误分类
+  pub fn kind(&self) -> ErrorKind {
+    match self {
+      WeChatPayError::ConfigError(_)
+      | WeChatPayError::UrlError(_)
+      | WeChatPayError::HeaderError(_)
+      | WeChatPayError::IoError(_) => ErrorKind::Configuration,
+      WeChatPayError::Crypto { .. } => ErrorKind::Crypto,
+      WeChatPayError::NetworkError(_) | WeChatPayError::RedisError(_) => ErrorKind::Transport,
+      WeChatPayError::VerifySignatureFail(_) | WeChatPayError::SignatureProbe => {
+        ErrorKind::Signature
+      }
+      WeChatPayError::WeChatApiError(_) | WeChatPayError::Accepted => ErrorKind::Api,
+      WeChatPayError::InvalidResponse { .. }
+      | WeChatPayError::JsonError(_)
+      | WeChatPayError::DecodeError(_) => ErrorKind::Deserialization,
+      WeChatPayError::RateLimited(_) => ErrorKind::RateLimited,
+      WeChatPayError::Unknown(_)
+      | WeChatPayError::InternalServerError(_)
+      | WeChatPayError::QrCodeError(_) => ErrorKind::Other,
+    }
+  }
+
+  /// 微信支付返回的业务错误
+  pub fn api_error(&self) -> Option<&WeChatPayApiError> {
+    match self {
+      WeChatPayError::WeChatApiError(err) => Some(err),
+      _ => None,
+    }
+  }
 }
 
 // implement display trait for WeChatPayError
@@ -72,52 +200,144 @@ impl std::fmt::Display for WeChatPayError {
     match self {
       WeChatPayError::NetworkError(err) => write!(f, "NetworkError: {}", err),
       WeChatPayError::RedisError(err) => write!(f, "RedisError: {}", err),
-      WeChatPayError::CryptoError(err) => write!(f, "CryptoError: {}", err),
-      WeChatPayError::WeChatApiError(err) => write!(f, "WeChatApiError: {:?}", err),
+      WeChatPayError::ConfigError(err) => write!(f, "ConfigError: {}", err),
+      WeChatPayError::Crypto { message, source } => {
+        write!(f, "CryptoError: {}: {}", message, source)
+      }
+      WeChatPayError::WeChatApiError(err) => write!(f, "WeChatApiError: {}", err),
       WeChatPayError::Accepted => write!(f, "Accepted"),
+      WeChatPayError::InvalidResponse {
+        status,
+        request_id,
+        source,
+        ..
+      } => write!(
+        f,
+        "InvalidResponse: status {}, Request-ID {}: {}",
+        status.as_u16(),
+        request_id.as_deref().unwrap_or("-"),
+        source
+      ),
+      WeChatPayError::JsonError(err) => write!(f, "JsonError: {}", err),
+      WeChatPayError::DecodeError(err) => write!(f, "DecodeError: {}", err),
+      WeChatPayError::UrlError(err) => write!(f, "UrlError: {}", err),
+      WeChatPayError::HeaderError(err) => write!(f, "HeaderError: {}", err),
+      WeChatPayError::IoError(err) => write!(f, "IoError: {}", err),
       WeChatPayError::Unknown(err) => write!(f, "Unknown: {}", err),
       WeChatPayError::InternalServerError(err) => write!(f, "InternalServerError: {}", err),
       WeChatPayError::VerifySignatureFail(err) => write!(f, "VerifySignatureError: {}", err),
+      WeChatPayError::SignatureProbe => write!(f, "SignatureProbe: WECHATPAY/SIGNTEST/"),
+      WeChatPayError::RateLimited(endpoint) => write!(f, "RateLimited: {}", endpoint),
+      WeChatPayError::QrCodeError(err) => write!(f, "QrCodeError: {}", err),
+    }
+  }
+}
+
+impl std::error::Error for WeChatPayError {
+  fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+    match self {
+      WeChatPayError::NetworkError(err) => Some(err),
+      WeChatPayError::RedisError(err) => Some(err),
+      WeChatPayError::Crypto { source, .. } => Some(source.as_ref()),
+      WeChatPayError::WeChatApiError(err) => Some(err.as_ref()),
+      WeChatPayError::InvalidResponse { source, .. } => Some(source),
+      WeChatPayError::JsonError(err) => Some(err),
+      WeChatPayError::DecodeError(err) => Some(err.as_ref()),
+      WeChatPayError::UrlError(err) => Some(err),
+      WeChatPayError::HeaderError(err) => Some(err.as_ref()),
+      WeChatPayError::IoError(err) => Some(err),
+      _ => None,
     }
   }
 }
 
+impl std::error::Error for WeChatPayApiError {}
+
 impl Client {
   #[inline]
-  fn parse_json<Response>(text: &str) -> Result<Response, WeChatPayError>
+  fn parse_json<Response>(response: &RawResponse) -> Result<Response, WeChatPayError>
   where
     Response: DeserializeOwned + Send + 'static,
   {
-    serde_json::from_str::<Response>(text).map_err(|_| {
-      serde_json::from_str::<WeChatPayApiError>(text)
-        .map(|err| WeChatPayError::WeChatApiError(Box::new(err)))
-        .unwrap_or_else(|_| WeChatPayError::Unknown(format!("Failed to parse response: {}", text)))
+    serde_json::from_str::<Response>(&response.body).map_err(|err| {
+      match serde_json::from_str::<ApiErrorBody>(&response.body) {
+        Ok(body) => Self::api_error(response, body),
+        Err(_) => Self::invalid_response(response, err),
+      }
     })
   }
 
+  /// 解析错误应答，消息体不是错误格式时返回 [WeChatPayError::InvalidResponse]
+  fn parse_error(response: &RawResponse) -> WeChatPayError {
+    match serde_json::from_str::<ApiErrorBody>(&response.body) {
+      Ok(body) => Self::api_error(response, body),
+      Err(err) => Self::invalid_response(response, err),
+    }
+  }
+
+  fn api_error(response: &RawResponse, body: ApiErrorBody) -> WeChatPayError {
+    WeChatPayError::WeChatApiError(Box::new(WeChatPayApiError {
+      status: response.status,
+      request_id: response.request_id.clone(),
+      code: WeChatPayApiErrorCode::parse(&body.code),
+      raw_code: body.code,
+      message: body.message,
+      detail: body.detail,
+    }))
+  }
+
+  fn invalid_response(response: &RawResponse, source: serde_json::Error) -> WeChatPayError {
+    WeChatPayError::InvalidResponse {
+      status: response.status,
+      request_id: response.request_id.clone(),
+      body: response.body.clone(),
+      source,
+    }
+  }
+
   /// # [错误信息](https://pay.weixin.qq.com/wiki/doc/apiv3/wechatpay/wechatpay2_0.shtml#part-6)
   /// 微信支付 API v3 使用 HTTP 状态码来表示请求处理的结果。
   /// - 处理成功的请求，如果有应答的消息体将返回 200，若没有应答的消息体将返回 204。
   /// - 已经被成功接受待处理的请求，将返回 202。
   /// - 请求处理失败时，如缺少必要的入参、支付时余额不足，将会返回 4xx 范围内的错误码。
   /// - 请求处理时发生了微信支付侧的服务系统错误，将返回 500 / 501 / 503 的状态码。这种情况比较少见。
+  ///
+  /// 网关返回的 502 / 504 等其他 4xx、5xx 同样按错误应答解析，消息体不是错误格式时返回 [WeChatPayError::InvalidResponse]
   pub(crate) async fn parse_response<Response>(
-    status: StatusCode,
-    text: String,
+    response: RawResponse,
   ) -> Result<Option<Response>, WeChatPayError>
   where
     Response: DeserializeOwned + Send + 'static,
   {
-    match status.as_u16() {
-      200 => Ok(Some(Self::parse_json::<Response>(&text)?)),
+    match response.status.as_u16() {
+      200 => Ok(Some(Self::parse_json::<Response>(&response)?)),
       204 => Ok(None),
       202 => Err(WeChatPayError::Accepted),
-      400..=501 | 503 => Err(WeChatPayError::WeChatApiError(Box::new(
-        Self::parse_json::<WeChatPayApiError>(&text)?,
+      400..=599 => Err(Self::parse_error(&response)),
+      status => Err(WeChatPayError::Unknown(format!(
+        "Unknown response status code {} (Request-ID {})",
+        status,
+        response.request_id.as_deref().unwrap_or("-")
       ))),
-      _ => Err(WeChatPayError::Unknown(
-        "Unknown response status code".to_string(),
-      )),
     }
   }
 }
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use reqwest::header::HeaderValue;
+
+  #[test]
+  fn invalid_header_is_configuration_error() {
+    let err: WeChatPayError = HeaderValue::from_str("\n").unwrap_err().into();
+    assert_eq!(err.kind(), ErrorKind::Configuration);
+  }
+
+  #[test]
+  fn crypto_errors_keep_source() {
+    let err: WeChatPayError = rsa::errors::Error::Decryption.into();
+    assert_eq!(err.kind(), ErrorKind::Crypto);
+    assert!(std::error::Error::source(&err).is_some());
+  }
+}
@@ -1,8 +1,23 @@
+#[cfg(feature = "blocking")]
+pub mod blocking;
 mod client;
 mod crypto;
 mod error;
 pub mod sdk;
+pub mod sensitive;
+pub mod signer;
+pub mod store;
+#[cfg(feature = "testing")]
+pub mod testing;
 pub mod webhook;
 
-pub use client::{Client, PlatformPubKey};
-pub use error::{WeChatPayApiError, WeChatPayApiErrorDetail, WeChatPayError};
+pub use client::{
+  CircuitState, Client, ClientBuilder, Failover, FailoverEvent, FailoverReason,
+  MerchantCertificate, PlatformPubKey, PlatformPubKeyInner, RateLimit, RateLimitMode, RateLimiter,
+  RetryPolicy, VerificationMode, BACKUP_BASE_URL, DEFAULT_BASE_URL, DEFAULT_USER_AGENT,
+  PUB_KEY_ID_PREFIX,
+};
+pub use error::code as error_code;
+pub use error::{
+  ErrorKind, WeChatPayApiError, WeChatPayApiErrorCode, WeChatPayApiErrorDetail, WeChatPayError,
+};
@@ -0,0 +1,104 @@
+//! # [APP 调起支付](https://pay.weixin.qq.com/wiki/doc/apiv3/apis/chapter3_2_4.shtml)
+//! 最新更新时间：2022.09.05
+//!
+//! 通过 [APP 下单](super::order) 取得 `prepay_id` 后，使用商户 API 私钥对调起支付参数签名，
+//! APP 将 [AppPayParams] 中的字段依次填入 OpenSDK 的 `PayReq`（`appId`、`partnerId`、`prepayId`、`packageValue`、`nonceStr`、`timeStamp`、`sign`）后调起支付。
+//!
+//! 签名串（每行以 `\n` 结尾）：
+//! ```text
+//! 应用ID
+//! 时间戳
+//! 随机字符串
+//! 预支付交易会话ID
+//! ```
+//!
+//! # Example
+//! ```no_run
+//! # async fn example(client: &wechat_pay_sdk::Client) -> Result<(), wechat_pay_sdk::WeChatPayError> {
+//! let params = client
+//!   .app_pay_params("wx8888888888888888", "WX1217752501201407033233368018")
+//!   .await?;
+//! assert_eq!(params.package, "Sign=WXPay");
+//! # Ok(())
+//! # }
+//! ```
+use crate::crypto::nonce_str;
+use crate::{Client, WeChatPayError};
+use serde::{Deserialize, Serialize};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// # [APP 调起支付](self) 参数
+/// # Example
+/// ```json
+/// {
+///   "appid": "wx8888888888888888",
+///   "partnerid": "1900000109",
+///   "prepayid": "WX1217752501201407033233368018",
+///   "package": "Sign=WXPay",
+///   "noncestr": "5K8264ILTKCH16CQ2502SI8ZNMTM67VS",
+///   "timestamp": "1412000000",
+///   "sign": "oR9d8PuhnIc+YZ8cBHFCwfgpaK9gd7vaRvkYD7rthRAZ/X+QBhcCYL21N7cHCTUxbQ+EAt6Uy+lwSN22f5YZvI45MLko8Pfso0jm46v5hqcVwrk6uddkGuT+Cdvu4WBqDzaDjnNa5UK3GfE1Wfl2gHxIIY5lLdUgWFts17D4WuolLLkiFZV+JSHMvH7eaLdT9N5GBovBwu5yYKUR7skR8Fu+LozcSqQixnlEZUfyE55feLOQTUYzLmR9pNtPbPsu6WVhbNHMS3Ss2+AehHvz+n64GDmXxbX++IOBvm2olHu3PsOUGRwhudhVf7UcGcunXt8cqNjKNqZLhLw4jq/xDg=="
+/// }
+/// ```
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AppPayParams {
+  /// 应用ID
+  ///
+  /// 下单时使用的移动应用 appid
+  pub appid: String,
+  /// 商户号
+  ///
+  /// 请求客户端的商户号
+  pub partnerid: String,
+  /// 预支付交易会话ID
+  ///
+  /// APP 下单接口返回的 prepay_id
+  pub prepayid: String,
+  /// 订单详情扩展字符串
+  ///
+  /// 固定为 `Sign=WXPay`
+  pub package: String,
+  /// 随机字符串
+  ///
+  /// 不长于 32 位
+  pub noncestr: String,
+  /// 时间戳
+  ///
+  /// 当前的时间，单位为秒
+  pub timestamp: String,
+  /// 签名
+  ///
+  /// 使用商户 API 私钥对签名串做 SHA256withRSA 签名后的 Base64 值
+  pub sign: String,
+}
+
+impl Client {
+  /// 生成 APP 调起支付参数
+  ///
+  /// # Arguments
+  ///
+  /// * `appid` - 下单时使用的移动应用 appid
+  /// * `prepay_id` - [APP 下单](super::order)返回的预支付交易会话标识
+  pub async fn app_pay_params(
+    &self,
+    appid: &str,
+    prepay_id: &str,
+  ) -> Result<AppPayParams, WeChatPayError> {
+    let timestamp = SystemTime::now()
+      .duration_since(UNIX_EPOCH)?
+      .as_secs()
+      .to_string();
+    let noncestr = nonce_str();
+    let message = format!("{}\n{}\n{}\n{}\n", appid, timestamp, noncestr, prepay_id);
+    let sign = self.sha256_with_rsa(message.as_bytes(), None).await?;
+    Ok(AppPayParams {
+      appid: appid.to_string(),
+      partnerid: self.merchant_id.clone(),
+      prepayid: prepay_id.to_string(),
+      package: "Sign=WXPay".to_string(),
+      noncestr,
+      timestamp,
+      sign,
+    })
+  }
+}
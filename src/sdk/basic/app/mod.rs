@@ -1,2 +1,4 @@
 //! # APP 支付
 //! 商户通过在移动端应用APP中集成开放SDK调起微信支付模块来完成支付。
+pub mod invoke;
+pub mod order;
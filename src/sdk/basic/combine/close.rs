@@ -0,0 +1,69 @@
+//! # [合单关闭订单](https://pay.weixin.qq.com/wiki/doc/apiv3/apis/chapter5_1_12.shtml)
+//! 最新更新时间：2022.09.05
+//!
+//! 合单支付订单只能使用此合单关单 API 完成关单。
+//! ## 接口说明
+//! 适用对象：直连商户
+//!
+//! 请求 URL：<https://api.mch.weixin.qq.com/v3/combine-transactions/out-trade-no/{combine_out_trade_no}/close>
+//!
+//! 请求方式：POST
+use crate::{Client, WeChatPayError};
+use reqwest::Method;
+use serde::Serialize;
+
+/// # [合单关闭订单](self) 请求
+/// # Example
+/// ```json
+/// {
+///   "combine_appid": "wxd678efh567hg6787",
+///   "sub_orders": [
+///     {
+///       "mchid": "1900000109",
+///       "out_trade_no": "20150806125346"
+///     }
+///   ]
+/// }
+/// ```
+#[derive(Serialize)]
+pub struct CombineCloseRequest {
+  /// 合单商户 appid
+  ///
+  /// 示例值：wxd678efh567hg6787
+  pub combine_appid: String,
+  /// 子单信息
+  ///
+  /// 最多支持子单条数：50
+  pub sub_orders: Vec<CombineCloseSubOrder>,
+}
+
+/// 需要关闭的子单
+#[derive(Serialize)]
+pub struct CombineCloseSubOrder {
+  /// 子单商户号
+  ///
+  /// 示例值：1900000109
+  pub mchid: String,
+  /// 子单商户订单号
+  ///
+  /// 示例值：20150806125346
+  pub out_trade_no: String,
+}
+
+impl Client {
+  /// 关闭成功时微信支付返回 204，没有应答体
+  pub async fn combine_close(
+    &self,
+    combine_out_trade_no: &str,
+    req: &CombineCloseRequest,
+  ) -> Result<(), WeChatPayError> {
+    let url = format!(
+      "/v3/combine-transactions/out-trade-no/{}/close",
+      combine_out_trade_no
+    );
+    self
+      .send_request::<_, serde_json::Value>(Method::POST, &url, None, Some(req))
+      .await?;
+    Ok(())
+  }
+}
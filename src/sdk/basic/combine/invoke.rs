@@ -0,0 +1,39 @@
+//! # 合单调起支付
+//! 合单下单返回的 `prepay_id` 与基础支付的调起方式相同，签名时的应用ID使用 `combine_appid`：
+//! - APP：[APP 调起支付](crate::sdk::basic::app::invoke)，`partnerid` 为合单商户号，即客户端的商户号
+//! - JSAPI、小程序：[JSAPI 调起支付](crate::sdk::basic::jsapi::invoke)
+//!
+//! H5 和 Native 分别直接使用应答中的 `h5_url` 和 `code_url`。
+use crate::sdk::basic::app::invoke::AppPayParams;
+use crate::sdk::basic::jsapi::invoke::JSApiPayParams;
+use crate::{Client, WeChatPayError};
+
+impl Client {
+  /// 生成合单 APP 调起支付参数
+  ///
+  /// # Arguments
+  ///
+  /// * `combine_appid` - 合单下单时使用的 `combine_appid`
+  /// * `prepay_id` - [合单 APP 下单](Client::combine_app_order)返回的预支付交易会话标识
+  pub async fn combine_app_pay_params(
+    &self,
+    combine_appid: &str,
+    prepay_id: &str,
+  ) -> Result<AppPayParams, WeChatPayError> {
+    self.app_pay_params(combine_appid, prepay_id).await
+  }
+
+  /// 生成合单 JSAPI、小程序调起支付参数
+  ///
+  /// # Arguments
+  ///
+  /// * `combine_appid` - 合单下单时使用的 `combine_appid`
+  /// * `prepay_id` - [合单 JSAPI 下单](Client::combine_jsapi_order)返回的预支付交易会话标识
+  pub async fn combine_jsapi_pay_params(
+    &self,
+    combine_appid: &str,
+    prepay_id: &str,
+  ) -> Result<JSApiPayParams, WeChatPayError> {
+    self.jsapi_pay_params(combine_appid, prepay_id).await
+  }
+}
@@ -1,2 +1,11 @@
 //! # 合单支付
 //! 一次支付行为可以同时进行票证、保险的付款，且两笔付款分别对应两个不同的商户。
+//! - [合单下单](order)：APP、JSAPI、小程序、H5、Native
+//! - [合单调起支付](invoke)
+//! - [合单查询订单](query)
+//! - [合单关闭订单](close)
+//! - [合单支付通知](crate::webhook::combine)
+pub mod close;
+pub mod invoke;
+pub mod order;
+pub mod query;
@@ -0,0 +1,312 @@
+//! # [合单下单](https://pay.weixin.qq.com/wiki/doc/apiv3/apis/chapter5_1_1.shtml)
+//! 最新更新时间：2022.09.05
+//!
+//! 使用合单支付接口，用户只输入一次密码，即可完成多个订单的支付。目前最多一次可支持 50 笔订单进行合单支付。
+//!
+//! 不同支付场景使用不同的请求 URL，请求和应答参数与对应的基础支付下单接口基本一致：
+//!
+//! | 场景 | 方法 | 请求 URL | 应答 |
+//! | --- | --- | --- | --- |
+//! | APP | [combine_app_order](Client::combine_app_order) | <https://api.mch.weixin.qq.com/v3/combine-transactions/app> | `prepay_id` |
+//! | JSAPI、小程序 | [combine_jsapi_order](Client::combine_jsapi_order) | <https://api.mch.weixin.qq.com/v3/combine-transactions/jsapi> | `prepay_id` |
+//! | H5 | [combine_h5_order](Client::combine_h5_order) | <https://api.mch.weixin.qq.com/v3/combine-transactions/h5> | `h5_url` |
+//! | Native | [combine_native_order](Client::combine_native_order) | <https://api.mch.weixin.qq.com/v3/combine-transactions/native> | `code_url` |
+//!
+//! 取得 `prepay_id` 后通过 [调起支付](super::invoke) 生成客户端参数。
+//! ## 接口说明
+//! 适用对象：直连商户
+//!
+//! 请求方式：POST
+use crate::sdk::basic::app::order::AppOrderResponse;
+use crate::sdk::basic::h5::order::H5OrderResponse;
+use crate::sdk::basic::jsapi::order::JSApiOrderResponse;
+use crate::sdk::basic::native::order::NativeOrderResponse;
+use crate::sdk::common::Payer;
+use crate::{Client, WeChatPayError};
+use reqwest::Method;
+use serde::Serialize;
+
+/// # [合单下单](self) 请求
+/// # Example
+/// ```json
+/// {
+///   "combine_appid": "wxd678efh567hg6787",
+///   "combine_mchid": "1900000109",
+///   "combine_out_trade_no": "P20150806125346",
+///   "scene_info": {
+///     "device_id": "POS1:1",
+///     "payer_client_ip": "14.17.22.32"
+///   },
+///   "sub_orders": [
+///     {
+///       "mchid": "1900000109",
+///       "attach": "深圳分店",
+///       "amount": {
+///         "total_amount": 10,
+///         "currency": "CNY"
+///       },
+///       "out_trade_no": "20150806125346",
+///       "description": "腾讯充值中心-QQ会员充值",
+///       "settle_info": {
+///         "profit_sharing": false
+///       }
+///     }
+///   ],
+///   "combine_payer_info": {
+///     "openid": "oUpF8uMuAJO_M2pxb1Q9zNjWeS6o"
+///   },
+///   "time_expire": "2018-06-08T10:34:56+08:00",
+///   "notify_url": "https://yourapp.com/notify"
+/// }
+/// ```
+#[derive(Serialize)]
+pub struct CombineOrderRequest {
+  /// 合单商户 appid
+  ///
+  /// 合单发起方的 appid，需要与调起支付时使用的 appid 一致
+  ///
+  /// 示例值：wxd678efh567hg6787
+  pub combine_appid: String,
+  /// 合单商户号
+  ///
+  /// 合单发起方商户号
+  ///
+  /// 示例值：1900000109
+  pub combine_mchid: String,
+  /// 合单商户订单号
+  ///
+  /// 合单支付总订单号，要求 32 个字符内，只能是数字、大小写字母_-|*@ ，且在同一个商户号下唯一
+  ///
+  /// 示例值：P20150806125346
+  pub combine_out_trade_no: String,
+  /// 场景信息
+  ///
+  /// H5 下单时必填，且需要包含 `h5_info`
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub scene_info: Option<CombineScene>,
+  /// 子单信息
+  ///
+  /// 最多支持子单条数：50
+  pub sub_orders: Vec<CombineSubOrder>,
+  /// 支付者
+  ///
+  /// JSAPI、小程序下单时必填
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub combine_payer_info: Option<Payer>,
+  /// 交易起始时间
+  ///
+  /// 订单生成时间，遵循 rfc3339 标准格式
+  ///
+  /// 示例值：2019-12-31T15:59:60+08:00
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub time_start: Option<String>,
+  /// 交易结束时间
+  ///
+  /// 订单失效时间，遵循 rfc3339 标准格式
+  ///
+  /// 示例值：2019-12-31T15:59:60+08:00
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub time_expire: Option<String>,
+  /// 通知地址
+  ///
+  /// 接收微信支付异步通知回调地址，通知 url 必须为直接可访问的 URL，不能携带参数。
+  ///
+  /// 示例值：https://yourapp.com/notify
+  pub notify_url: String,
+}
+
+/// 子单信息
+#[derive(Serialize)]
+pub struct CombineSubOrder {
+  /// 子单商户号
+  ///
+  /// 子单发起方商户号，必须与发起方 appid 有绑定关系
+  ///
+  /// 示例值：1900000109
+  pub mchid: String,
+  /// 附加数据
+  ///
+  /// 附加数据，在查询 API 和支付通知中原样返回，可作为自定义参数使用
+  ///
+  /// 示例值：深圳分店
+  pub attach: String,
+  /// 订单金额
+  pub amount: CombineAmount,
+  /// 子单商户订单号
+  ///
+  /// 商户系统内部订单号，要求 32 个字符内，只能是数字、大小写字母_-|*@ ，且在同一个商户号下唯一
+  ///
+  /// 示例值：20150806125346
+  pub out_trade_no: String,
+  /// 订单优惠标记
+  ///
+  /// 示例值：WXG
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub goods_tag: Option<String>,
+  /// 商品描述
+  ///
+  /// 示例值：腾讯充值中心-QQ会员充值
+  pub description: String,
+  /// 结算信息
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub settle_info: Option<CombineSettle>,
+}
+
+/// 子单金额
+#[derive(Serialize)]
+pub struct CombineAmount {
+  /// 标价金额
+  ///
+  /// 子单金额，单位为分
+  ///
+  /// 示例值：10
+  pub total_amount: i32,
+  /// 标价币种
+  ///
+  /// 符合 ISO 4217 标准的三位字母代码，人民币：CNY
+  ///
+  /// 示例值：CNY
+  pub currency: String,
+}
+
+/// 子单结算信息
+#[derive(Serialize)]
+pub struct CombineSettle {
+  /// 是否指定分账
+  ///
+  /// 示例值：false
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub profit_sharing: Option<bool>,
+  /// 补差金额
+  ///
+  /// SettleInfo.profit_sharing 为 true 时，该金额才生效。单位为分
+  ///
+  /// 示例值：10
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub subsidy_amount: Option<i64>,
+}
+
+/// 合单场景信息
+#[derive(Serialize)]
+pub struct CombineScene {
+  /// 商户端设备号
+  ///
+  /// 示例值：POS1:1
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub device_id: Option<String>,
+  /// 用户终端 IP
+  ///
+  /// 示例值：14.17.22.32
+  pub payer_client_ip: String,
+  /// H5 场景信息，H5 下单时必填
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub h5_info: Option<H5Info>,
+}
+
+/// H5 场景信息
+#[derive(Serialize)]
+pub struct H5Info {
+  /// 场景类型
+  ///
+  /// iOS、Android、Wap
+  ///
+  /// 示例值：iOS
+  #[serde(rename = "type")]
+  pub type_: String,
+  /// 应用名称
+  ///
+  /// 示例值：王者荣耀
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub app_name: Option<String>,
+  /// 网站 URL
+  ///
+  /// 示例值：https://pay.qq.com
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub app_url: Option<String>,
+  /// iOS 平台 BundleID
+  ///
+  /// 示例值：com.tencent.wzryiOS
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub bundle_id: Option<String>,
+  /// Android 平台 PackageName
+  ///
+  /// 示例值：com.tencent.tmgp.sgame
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub package_name: Option<String>,
+}
+
+/// 合单 APP 下单应答，与 [APP 下单](crate::sdk::basic::app::order) 相同
+pub type CombineAppOrderResponse = AppOrderResponse;
+/// 合单 JSAPI、小程序下单应答，与 [JSAPI 下单](crate::sdk::basic::jsapi::order) 相同
+pub type CombineJSApiOrderResponse = JSApiOrderResponse;
+/// 合单 H5 下单应答，与 [H5 下单](crate::sdk::basic::h5::order) 相同
+pub type CombineH5OrderResponse = H5OrderResponse;
+/// 合单 Native 下单应答，与 [Native 下单](crate::sdk::basic::native::order) 相同
+pub type CombineNativeOrderResponse = NativeOrderResponse;
+
+impl Client {
+  pub async fn combine_app_order(
+    &self,
+    req: &CombineOrderRequest,
+  ) -> Result<CombineAppOrderResponse, WeChatPayError> {
+    Ok(
+      self
+        .send_request(
+          Method::POST,
+          "/v3/combine-transactions/app",
+          None,
+          Some(req),
+        )
+        .await?
+        .unwrap(),
+    )
+  }
+
+  /// JSAPI、小程序合单下单，`combine_payer_info` 必填
+  pub async fn combine_jsapi_order(
+    &self,
+    req: &CombineOrderRequest,
+  ) -> Result<CombineJSApiOrderResponse, WeChatPayError> {
+    Ok(
+      self
+        .send_request(
+          Method::POST,
+          "/v3/combine-transactions/jsapi",
+          None,
+          Some(req),
+        )
+        .await?
+        .unwrap(),
+    )
+  }
+
+  /// H5 合单下单，`scene_info.h5_info` 必填
+  pub async fn combine_h5_order(
+    &self,
+    req: &CombineOrderRequest,
+  ) -> Result<CombineH5OrderResponse, WeChatPayError> {
+    Ok(
+      self
+        .send_request(Method::POST, "/v3/combine-transactions/h5", None, Some(req))
+        .await?
+        .unwrap(),
+    )
+  }
+
+  pub async fn combine_native_order(
+    &self,
+    req: &CombineOrderRequest,
+  ) -> Result<CombineNativeOrderResponse, WeChatPayError> {
+    Ok(
+      self
+        .send_request(
+          Method::POST,
+          "/v3/combine-transactions/native",
+          None,
+          Some(req),
+        )
+        .await?
+        .unwrap(),
+    )
+  }
+}
@@ -0,0 +1,151 @@
+//! # [合单查询订单](https://pay.weixin.qq.com/wiki/doc/apiv3/apis/chapter5_1_11.shtml)
+//! 最新更新时间：2022.09.05
+//!
+//! 电商平台通过合单查询订单 API 查询订单状态，完成下一步的业务逻辑。
+//! ## 接口说明
+//! 适用对象：直连商户
+//!
+//! 请求 URL：<https://api.mch.weixin.qq.com/v3/combine-transactions/out-trade-no/{combine_out_trade_no}>
+//!
+//! 请求方式：GET
+use crate::sdk::common::{EmptyRequest, Payer, TransactionPromotion, TransactionScene};
+use crate::webhook::transaction::{TradeStatus, TradeType};
+use crate::{Client, WeChatPayError};
+use reqwest::Method;
+use serde::Deserialize;
+
+/// # 合单订单
+/// [合单查询订单](self) 的应答，也是[合单支付通知](crate::webhook::combine)的通知数据
+/// # Example
+/// ```json
+/// {
+///   "combine_appid": "wxd678efh567hg6787",
+///   "combine_mchid": "1900000109",
+///   "combine_out_trade_no": "P20150806125346",
+///   "scene_info": {
+///     "device_id": "POS1:1"
+///   },
+///   "sub_orders": [
+///     {
+///       "mchid": "1900000109",
+///       "trade_type": "JSAPI",
+///       "trade_state": "SUCCESS",
+///       "bank_type": "CMC",
+///       "attach": "深圳分店",
+///       "success_time": "2015-05-20T13:29:35.120+08:00",
+///       "transaction_id": "1009660380201506130728806387",
+///       "out_trade_no": "20150806125346",
+///       "amount": {
+///         "total_amount": 10,
+///         "payer_amount": 10,
+///         "currency": "CNY",
+///         "payer_currency": "CNY"
+///       }
+///     }
+///   ],
+///   "combine_payer_info": {
+///     "openid": "oUpF8uMuAJO_M2pxb1Q9zNjWeS6o"
+///   }
+/// }
+/// ```
+#[derive(Deserialize, Debug)]
+pub struct CombineTransaction {
+  /// 合单商户 appid
+  ///
+  /// 示例值：wxd678efh567hg6787
+  pub combine_appid: String,
+  /// 合单商户号
+  ///
+  /// 示例值：1900000109
+  pub combine_mchid: String,
+  /// 合单商户订单号
+  ///
+  /// 示例值：P20150806125346
+  pub combine_out_trade_no: String,
+  /// 场景信息
+  pub scene_info: Option<TransactionScene>,
+  /// 子单信息
+  pub sub_orders: Vec<CombineSubOrderTransaction>,
+  /// 支付者
+  pub combine_payer_info: Option<Payer>,
+}
+
+/// 子单信息
+#[derive(Deserialize, Debug)]
+pub struct CombineSubOrderTransaction {
+  /// 子单商户号
+  ///
+  /// 示例值：1900000109
+  pub mchid: String,
+  /// 交易类型
+  ///
+  /// 示例值：JSAPI
+  pub trade_type: TradeType,
+  /// 交易状态
+  ///
+  /// 示例值：SUCCESS
+  pub trade_state: TradeStatus,
+  /// 付款银行
+  ///
+  /// 示例值：CMC
+  pub bank_type: Option<String>,
+  /// 附加数据
+  ///
+  /// 示例值：深圳分店
+  pub attach: Option<String>,
+  /// 支付完成时间
+  ///
+  /// 示例值：2015-05-20T13:29:35.120+08:00
+  pub success_time: Option<String>,
+  /// 微信支付订单号
+  ///
+  /// 示例值：1009660380201506130728806387
+  pub transaction_id: Option<String>,
+  /// 子单商户订单号
+  ///
+  /// 示例值：20150806125346
+  pub out_trade_no: String,
+  /// 订单金额
+  pub amount: CombineTransactionAmount,
+  /// 优惠功能，享受优惠时返回该字段
+  pub promotion_detail: Option<Vec<TransactionPromotion>>,
+}
+
+/// 子单金额
+#[derive(Deserialize, Debug)]
+pub struct CombineTransactionAmount {
+  /// 标价金额，单位为分
+  ///
+  /// 示例值：10
+  pub total_amount: i32,
+  /// 现金支付金额，单位为分，支付后返回
+  ///
+  /// 示例值：10
+  pub payer_amount: Option<i32>,
+  /// 标价币种
+  ///
+  /// 示例值：CNY
+  pub currency: String,
+  /// 现金支付币种
+  ///
+  /// 示例值：CNY
+  pub payer_currency: Option<String>,
+}
+
+impl Client {
+  pub async fn combine_query(
+    &self,
+    combine_out_trade_no: &str,
+  ) -> Result<CombineTransaction, WeChatPayError> {
+    let url = format!(
+      "/v3/combine-transactions/out-trade-no/{}",
+      combine_out_trade_no
+    );
+    Ok(
+      self
+        .send_request::<EmptyRequest, _>(Method::GET, &url, None, None)
+        .await?
+        .unwrap(),
+    )
+  }
+}
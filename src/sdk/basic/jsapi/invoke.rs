@@ -0,0 +1,122 @@
+//! # [JSAPI 调起支付](https://pay.weixin.qq.com/wiki/doc/apiv3/apis/chapter3_1_4.shtml)
+//! 最新更新时间：2022.09.05
+//!
+//! 通过 [JSAPI 下单](super::order) 取得 `prepay_id` 后，使用商户 API 私钥对调起支付参数签名，
+//! 前端将 [JSApiPayParams] 原样传给 `WeixinJSBridge.invoke('getBrandWCPayRequest', ...)` 或 `wx.requestPayment(...)` 即可调起支付。
+//!
+//! 签名串（每行以 `\n` 结尾）：
+//! ```text
+//! 应用ID
+//! 时间戳
+//! 随机字符串
+//! 订单详情扩展字符串
+//! ```
+//!
+//! # Example
+//! ```no_run
+//! # async fn example(client: &wechat_pay_sdk::Client) -> Result<(), wechat_pay_sdk::WeChatPayError> {
+//! let params = client
+//!   .jsapi_pay_params("wxd678efh567hg6787", "wx201410272009395522657a690389285100")
+//!   .await?;
+//! // {"appId":"wxd678efh567hg6787","timeStamp":"...","nonceStr":"...","package":"prepay_id=wx201410272009395522657a690389285100","signType":"RSA","paySign":"..."}
+//! let json = serde_json::to_string(&params)?;
+//! # Ok(())
+//! # }
+//! ```
+use crate::crypto::nonce_str;
+use crate::{Client, WeChatPayError};
+use serde::{Deserialize, Serialize};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// # [JSAPI 调起支付](self) 参数
+/// # Example
+/// ```json
+/// {
+///   "appId": "wx2421b1c4370ec43b",
+///   "timeStamp": "1395712654",
+///   "nonceStr": "e61463f8efa94090b1f366cccfbbb444",
+///   "package": "prepay_id=up_wx21201855730335ac86f8c43d1889123400",
+///   "signType": "RSA",
+///   "paySign": "oR9d8PuhnIc+YZ8cBHFCwfgpaK9gd7vaRvkYD7rthRAZ/X+QBhcCYL21N7cHCTUxbQ+EAt6Uy+lwSN22f5YZvI45MLko8Pfso0jm46v5hqcVwrk6uddkGuT+Cdvu4WBqDzaDjnNa5UK3GfE1Wfl2gHxIIY5lLdUgWFts17D4WuolLLkiFZV+JSHMvH7eaLdT9N5GBovBwu5yYKUR7skR8Fu+LozcSqQixnlEZUfyE55feLOQTUYzLmR9pNtPbPsu6WVhbNHMS3Ss2+AehHvz+n64GDmXxbX++IOBvm2olHu3PsOUGRwhudhVf7UcGcunXt8cqNjKNqZLhLw4jq/xDg=="
+/// }
+/// ```
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct JSApiPayParams {
+  /// 应用ID
+  ///
+  /// 下单时使用的 appid，服务商模式下见 [partner_jsapi_pay_params](Client::partner_jsapi_pay_params)
+  pub app_id: String,
+  /// 时间戳
+  ///
+  /// 当前的时间，单位为秒
+  pub time_stamp: String,
+  /// 随机字符串
+  ///
+  /// 不长于 32 位
+  pub nonce_str: String,
+  /// 订单详情扩展字符串
+  ///
+  /// JSAPI 下单接口返回的 prepay_id 参数值，提交格式如：`prepay_id=***`
+  pub package: String,
+  /// 签名方式
+  ///
+  /// 固定为 `RSA`
+  pub sign_type: String,
+  /// 签名
+  ///
+  /// 使用商户 API 私钥对签名串做 SHA256withRSA 签名后的 Base64 值
+  pub pay_sign: String,
+}
+
+impl Client {
+  /// 生成 JSAPI、小程序调起支付参数
+  ///
+  /// # Arguments
+  ///
+  /// * `appid` - 下单时使用的 appid
+  /// * `prepay_id` - [JSAPI 下单](super::order)返回的预支付交易会话标识
+  pub async fn jsapi_pay_params(
+    &self,
+    appid: &str,
+    prepay_id: &str,
+  ) -> Result<JSApiPayParams, WeChatPayError> {
+    let time_stamp = SystemTime::now()
+      .duration_since(UNIX_EPOCH)?
+      .as_secs()
+      .to_string();
+    let nonce_str = nonce_str();
+    let package = format!("prepay_id={}", prepay_id);
+    let message = format!("{}\n{}\n{}\n{}\n", appid, time_stamp, nonce_str, package);
+    let pay_sign = self.sha256_with_rsa(message.as_bytes(), None).await?;
+    Ok(JSApiPayParams {
+      app_id: appid.to_string(),
+      time_stamp,
+      nonce_str,
+      package,
+      sign_type: "RSA".to_string(),
+      pay_sign,
+    })
+  }
+
+  /// 服务商模式下生成 JSAPI、小程序调起支付参数
+  ///
+  /// 下单时传入了 `sub_appid`（如使用 `sub_openid` 下单）时，`appId` 必须为 `sub_appid`，否则为 `sp_appid`。
+  /// 签名使用服务商的商户 API 私钥。
+  ///
+  /// # Arguments
+  ///
+  /// * `sp_appid` - 服务商应用ID
+  /// * `sub_appid` - 子商户应用ID，下单时未传入时为 `None`
+  /// * `prepay_id` - 下单返回的预支付交易会话标识
+  pub async fn partner_jsapi_pay_params(
+    &self,
+    sp_appid: &str,
+    sub_appid: Option<&str>,
+    prepay_id: &str,
+  ) -> Result<JSApiPayParams, WeChatPayError> {
+    self
+      .jsapi_pay_params(sub_appid.unwrap_or(sp_appid), prepay_id)
+      .await
+  }
+}
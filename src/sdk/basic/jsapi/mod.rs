@@ -1,4 +1,5 @@
 //! # JSAPI 支付
 //! 商户通过调用微信支付提供的JSAPI接口，在支付场景中调起微信支付模块完成收款。
+pub mod invoke;
 pub mod order;
 pub mod refund;
@@ -6,4 +6,8 @@
 //! - [申请退款](https://pay.weixin.qq.com/wiki/doc/apiv3/apis/chapter3_2_9.shtml)
 //!
 //!   该接口与 [JSAPI 支付 > 申请退款](refund) 完全一致，因此只是重复导出。
-pub use super::jsapi::{order, refund};
+//! - [小程序调起支付](https://pay.weixin.qq.com/wiki/doc/apiv3/apis/chapter3_5_4.shtml)
+//!
+//!   签名方式与 [JSAPI 支付 > JSAPI 调起支付](invoke) 完全一致，因此只是重复导出。
+//!   [JSApiPayParams](invoke::JSApiPayParams) 可以直接传给 `wx.requestPayment`，其中的 `appId` 会被忽略。
+pub use super::jsapi::{invoke, order, refund};
@@ -1,2 +1,7 @@
 //! # Native 支付
 //! 商户系统按微信支付协议生成支付二维码，用户再用微信“扫一扫”完成支付的模式。
+//!
+//! 启用 `qrcode` feature 后，可以通过 [qrcode] 模块将 `code_url` 渲染为二维码图片。
+pub mod order;
+#[cfg(feature = "qrcode")]
+pub mod qrcode;
@@ -0,0 +1,151 @@
+//! # 支付二维码
+//! 启用 `qrcode` feature 后可用，将 [Native 下单](super::order) 返回的 `code_url` 渲染为 PNG 或 SVG 图片。
+//!
+//! 二维码的每个模块占整数个像素以保证清晰可扫，因此图片边长是不小于 [size](QrCodeOptions::size) 的最小整数倍，
+//! 可能略大于设置的值；需要固定尺寸时，在页面中按设置的尺寸显示即可。
+//!
+//! # Example
+//! ```no_run
+//! use wechat_pay_sdk::sdk::basic::native::qrcode::{EcLevel, QrCodeOptions};
+//!
+//! # fn example(res: &wechat_pay_sdk::sdk::basic::native::order::NativeOrderResponse) -> Result<(), wechat_pay_sdk::WeChatPayError> {
+//! let options = QrCodeOptions::new().size(400).ec_level(EcLevel::H);
+//! let png = res.qr_code_png(&options)?;
+//! let svg = res.qr_code_svg(&options)?;
+//! # Ok(())
+//! # }
+//! ```
+use super::order::NativeOrderResponse;
+use crate::WeChatPayError;
+use image::{DynamicImage, ImageFormat, Luma};
+use qrcode::render::svg;
+use qrcode::QrCode;
+use std::io::Cursor;
+
+pub use qrcode::EcLevel;
+
+/// # 二维码渲染选项
+/// - `size`：图片的最小边长（像素），默认 256，实际边长可能略大
+/// - `ec_level`：纠错等级，默认 [EcLevel::M]
+/// - `quiet_zone`：是否在四周保留 4 个模块宽的空白，默认保留
+#[derive(Debug, Clone, Copy)]
+pub struct QrCodeOptions {
+  size: u32,
+  ec_level: EcLevel,
+  quiet_zone: bool,
+}
+
+impl Default for QrCodeOptions {
+  fn default() -> Self {
+    Self {
+      size: 256,
+      ec_level: EcLevel::M,
+      quiet_zone: true,
+    }
+  }
+}
+
+impl QrCodeOptions {
+  /// 默认 256 像素，纠错等级 M，保留四周空白
+  pub fn new() -> Self {
+    Self::default()
+  }
+  /// 图片的最小边长（像素）
+  ///
+  /// 实际边长为模块数（含空白）的整数倍，取不小于 `size` 的最小值；内容较长、`size` 较小时每个模块至少 1 像素，
+  /// 图片可能明显大于 `size`
+  pub fn size(mut self, size: u32) -> Self {
+    self.size = size;
+    self
+  }
+  /// 纠错等级，中间需要覆盖 logo 时可以使用 [EcLevel::H]
+  pub fn ec_level(mut self, ec_level: EcLevel) -> Self {
+    self.ec_level = ec_level;
+    self
+  }
+  /// 是否保留四周空白，默认保留
+  pub fn quiet_zone(mut self, quiet_zone: bool) -> Self {
+    self.quiet_zone = quiet_zone;
+    self
+  }
+
+  fn encode(&self, content: &str) -> Result<QrCode, WeChatPayError> {
+    QrCode::with_error_correction_level(content, self.ec_level)
+      .map_err(|e| WeChatPayError::QrCodeError(e.to_string()))
+  }
+
+  /// 将 `content` 渲染为 8 位灰度 PNG 图片，边长见 [size](Self::size)
+  pub fn render_png(&self, content: &str) -> Result<Vec<u8>, WeChatPayError> {
+    let image = self
+      .encode(content)?
+      .render::<Luma<u8>>()
+      .min_dimensions(self.size, self.size)
+      .quiet_zone(self.quiet_zone)
+      .build();
+    let mut png = Vec::new();
+    DynamicImage::ImageLuma8(image)
+      .write_to(&mut Cursor::new(&mut png), ImageFormat::Png)
+      .map_err(|e| WeChatPayError::QrCodeError(e.to_string()))?;
+    Ok(png)
+  }
+
+  /// 将 `content` 渲染为 SVG 图片，`width`、`height` 属性的取值见 [size](Self::size)
+  pub fn render_svg(&self, content: &str) -> Result<String, WeChatPayError> {
+    Ok(
+      self
+        .encode(content)?
+        .render::<svg::Color<'_>>()
+        .min_dimensions(self.size, self.size)
+        .quiet_zone(self.quiet_zone)
+        .build(),
+    )
+  }
+}
+
+impl NativeOrderResponse {
+  /// 将 `code_url` 渲染为 PNG 二维码，返回 PNG 文件内容
+  ///
+  /// 图片边长不小于 `options` 中的 `size`，见 [QrCodeOptions::size]
+  pub fn qr_code_png(&self, options: &QrCodeOptions) -> Result<Vec<u8>, WeChatPayError> {
+    options.render_png(&self.code_url)
+  }
+  /// 将 `code_url` 渲染为 SVG 二维码，返回 SVG 文档
+  ///
+  /// 图片边长不小于 `options` 中的 `size`，见 [QrCodeOptions::size]
+  pub fn qr_code_svg(&self, options: &QrCodeOptions) -> Result<String, WeChatPayError> {
+    options.render_svg(&self.code_url)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  const CODE_URL: &str = "weixin://wxpay/bizpayurl/up?pr=NwY5Mz9&groupid=00";
+
+  #[test]
+  fn png_is_at_least_requested_size() {
+    for size in [1, 100, 256, 300] {
+      let png = QrCodeOptions::new()
+        .size(size)
+        .render_png(CODE_URL)
+        .unwrap();
+      let image = image::load_from_memory(&png).unwrap();
+      assert_eq!(image.width(), image.height());
+      assert!(image.width() >= size);
+    }
+  }
+
+  #[test]
+  fn quiet_zone_changes_module_count() {
+    let with = QrCodeOptions::new().size(1).render_png(CODE_URL).unwrap();
+    let without = QrCodeOptions::new()
+      .size(1)
+      .quiet_zone(false)
+      .render_png(CODE_URL)
+      .unwrap();
+    let with = image::load_from_memory(&with).unwrap().width();
+    let without = image::load_from_memory(&without).unwrap().width();
+    assert_eq!(with, without + 8);
+  }
+}
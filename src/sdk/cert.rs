@@ -4,10 +4,35 @@
 //! 请求 URL: <https://api.mch.weixin.qq.com/v3/certificates>
 //!
 //! 请求方式: GET
+//!
+//! ## 证书管理
+//! 平台证书会定期更换，[Client] 提供了以下几种方式保持平台公钥为最新：
+//! - [refresh_certificates](Client::refresh_certificates)：立即下载并解密平台证书，替换当前的平台公钥
+//! - [spawn_certificate_refresher](Client::spawn_certificate_refresher)：在后台定期下载平台证书
+//! - 验证签名时遇到未知的 `Wechatpay-Serial`，自动下载平台证书（见 [ClientBuilder::auto_refresh_certificates](crate::ClientBuilder::auto_refresh_certificates)）
+//!
+//! 多个实例可以通过[共享存储](crate::store)共享平台公钥。
+use crate::client::PlatformPubKey;
+use crate::crypto::SignatureHeaders;
+use crate::error::RawResponse;
 use crate::sdk::common::EmptyRequest;
+use crate::store::StoredPlatformKeys;
 use crate::{Client, WeChatPayError};
+use base64::{engine::general_purpose, Engine};
+use chrono::DateTime;
+use openssl::x509::X509;
 use reqwest::Method;
 use serde::Deserialize;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::task::JoinHandle;
+
+/// 两次按需下载平台证书（或因未知序列号读取共享存储）之间的最短间隔
+const REFRESH_COOLDOWN: Duration = Duration::from_secs(60);
+/// 共享存储中刷新锁的有效期，也是等待其他实例下载的最长时间（配置了更短的请求超时时间时以请求超时时间为准）
+const STORE_LOCK_TTL: Duration = Duration::from_secs(30);
+/// 等待其他实例下载时读取存储的间隔
+const STORE_POLL_INTERVAL: Duration = Duration::from_millis(500);
 
 #[derive(Debug, Deserialize)]
 pub struct EncryptCertificate {
@@ -25,6 +50,35 @@ pub struct CertificateData {
   pub encrypt_certificate: EncryptCertificate,
 }
 
+impl CertificateData {
+  /// 使用 APIv3 密钥解密平台证书，并提取其中的公钥
+  pub fn decrypt(&self, cli: &Client) -> Result<PlatformPubKey, WeChatPayError> {
+    let cert = &self.encrypt_certificate;
+    let plaintext = cli.aead_aes_256_gcm_decrypt(
+      cert.nonce.as_bytes(),
+      general_purpose::STANDARD
+        .decode(&cert.ciphertext)?
+        .as_slice(),
+      Some(cert.associated_data.as_bytes()),
+    )?;
+    let x509 = X509::from_pem(plaintext.as_slice())?;
+    let key = String::from_utf8(x509.public_key()?.public_key_to_pem()?)
+      .map_err(|e| WeChatPayError::DecodeError(Box::new(e)))?;
+    Ok(PlatformPubKey {
+      serial_no: self.serial_no.clone(),
+      effective_time: parse_time(&self.effective_time)?,
+      expire_time: parse_time(&self.expire_time)?,
+      key,
+    })
+  }
+}
+
+fn parse_time(time: &str) -> Result<u64, WeChatPayError> {
+  let time = DateTime::parse_from_rfc3339(time)
+    .map_err(|e| WeChatPayError::Unknown(format!("Invalid certificate time {}: {}", time, e)))?;
+  Ok(time.timestamp().max(0) as u64)
+}
+
 /// # [获取平台证书](self) 响应
 #[derive(Debug, Deserialize)]
 pub struct GetCertificatesResponse {
@@ -32,12 +86,214 @@ pub struct GetCertificatesResponse {
 }
 
 impl Client {
+  /// 获取平台证书列表，返回值未经解密
+  ///
+  /// 平台证书下载接口的应答无法用本地尚未拥有的证书验证，这里只返回原始数据，
+  /// 如需验证并解密请使用 [download_certificates](Self::download_certificates)
   pub async fn get_certificates(&self) -> Result<GetCertificatesResponse, WeChatPayError> {
-    Ok(
-      self
-        .send_request::<EmptyRequest, _>(Method::GET, "/v3/certificates", None, None)
-        .await?
-        .unwrap(),
-    )
+    Ok(self.fetch_certificates().await?.1)
+  }
+
+  async fn fetch_certificates(
+    &self,
+  ) -> Result<(SignatureHeaders, GetCertificatesResponse, String), WeChatPayError> {
+    let res = self
+      .dispatch("/v3/certificates", &mut || {
+        self.build_request::<EmptyRequest>(Method::GET, "/v3/certificates", None, None)
+      })
+      .await??;
+    let signature = SignatureHeaders::from_headers(res.headers())?;
+    let status = res.status();
+    let request_id = RawResponse::request_id(res.headers());
+    let text = res.text().await?;
+    let response = Self::parse_response::<GetCertificatesResponse>(RawResponse {
+      status,
+      request_id,
+      body: text.clone(),
+    })
+    .await?
+    .ok_or_else(|| WeChatPayError::Unknown("Empty certificates response".to_string()))?;
+    Ok((signature, response, text))
+  }
+
+  /// 下载并解密平台证书
+  ///
+  /// 应答签名使用下载得到的证书（或本地已有的平台公钥）验证，验证通过后才返回。
+  pub async fn download_certificates(&self) -> Result<Vec<PlatformPubKey>, WeChatPayError> {
+    let (signature, response, text) = self.fetch_certificates().await?;
+    let keys = response
+      .data
+      .iter()
+      .map(|cert| cert.decrypt(self))
+      .collect::<Result<Vec<_>, _>>()?;
+    let pub_key = match keys.iter().find(|key| key.serial_no == signature.serial) {
+      Some(key) => key.clone().try_into()?,
+      None => self
+        .get_public_key(&signature.serial)
+        .ok_or(WeChatPayError::VerifySignatureFail(
+          "No public key found".to_string(),
+        ))?,
+    };
+    signature.verify(&pub_key.key, &text)?;
+    Ok(keys)
+  }
+
+  /// 下载平台证书并替换当前的平台公钥
+  ///
+  /// 配置了[共享存储](crate::store)时，只有获得刷新锁的实例会下载，其余实例等待并从存储中读取，
+  /// 等待超时后在本地下载
+  pub async fn refresh_certificates(&self) -> Result<(), WeChatPayError> {
+    let mut refreshed_at = self.certificates_refreshed_at.lock().await;
+    self.refresh_certificates_locked().await?;
+    *refreshed_at = Some(Instant::now());
+    Ok(())
+  }
+
+  /// 从共享存储同步平台公钥，存储中的公钥不存在或早于 `max_age` 时重新下载
+  ///
+  /// 未配置共享存储时等同于 [refresh_certificates](Self::refresh_certificates)
+  pub async fn sync_certificates(&self, max_age: Duration) -> Result<(), WeChatPayError> {
+    if let Some(store) = &self.key_store {
+      if let Some(stored) = store.load().await? {
+        if stored.age() < max_age {
+          self.update_public_keys(stored.keys);
+          return Ok(());
+        }
+      }
+    }
+    self.refresh_certificates().await
+  }
+
+  /// 按需下载平台证书，距离上一次下载不足 [REFRESH_COOLDOWN] 时跳过
+  ///
+  /// 并发调用时只有一个会真正发起下载，其余等待其完成
+  pub(crate) async fn refresh_certificates_if_stale(&self) -> Result<(), WeChatPayError> {
+    let mut refreshed_at = self.certificates_refreshed_at.lock().await;
+    if let Some(at) = *refreshed_at {
+      if at.elapsed() < REFRESH_COOLDOWN {
+        return Ok(());
+      }
+    }
+    let result = self.refresh_certificates_locked().await;
+    // 无论成功与否都记录时间，避免伪造的 Wechatpay-Serial 触发频繁下载
+    *refreshed_at = Some(Instant::now());
+    result
+  }
+
+  /// 找不到 `serial_no` 对应的平台公钥时，先从共享存储读取，仍然没有再下载平台证书
+  ///
+  /// `Wechatpay-Serial` 可以被伪造，读取存储和下载共用 [REFRESH_COOLDOWN]，避免每个请求都访问存储
+  pub(crate) async fn reload_public_keys_if_stale(
+    &self,
+    serial_no: &str,
+  ) -> Result<(), WeChatPayError> {
+    let mut refreshed_at = self.certificates_refreshed_at.lock().await;
+    if let Some(at) = *refreshed_at {
+      if at.elapsed() < REFRESH_COOLDOWN {
+        return Ok(());
+      }
+    }
+    // 等待锁期间其他任务可能已经取得了公钥
+    if self.get_public_key(serial_no).is_some() {
+      return Ok(());
+    }
+    let result = async {
+      // 其他实例可能已经下载了新的平台证书
+      if self.load_certificates_from_store().await.unwrap_or(false)
+        && self.get_public_key(serial_no).is_some()
+      {
+        return Ok(());
+      }
+      if !self.auto_refresh_certificates {
+        return Ok(());
+      }
+      self.refresh_certificates_locked().await
+    }
+    .await;
+    *refreshed_at = Some(Instant::now());
+    result
+  }
+
+  /// 从共享存储读取平台公钥，返回存储中是否有公钥
+  pub(crate) async fn load_certificates_from_store(&self) -> Result<bool, WeChatPayError> {
+    let Some(store) = &self.key_store else {
+      return Ok(false);
+    };
+    match store.load().await? {
+      Some(stored) => {
+        self.update_public_keys(stored.keys);
+        Ok(true)
+      }
+      None => Ok(false),
+    }
+  }
+
+  async fn refresh_certificates_locked(&self) -> Result<(), WeChatPayError> {
+    let Some(store) = &self.key_store else {
+      let keys = self.download_certificates().await?;
+      self.update_public_keys(keys);
+      return Ok(());
+    };
+    let since = StoredPlatformKeys::new(Vec::new()).updated_at;
+    match store.try_lock(STORE_LOCK_TTL).await? {
+      Some(token) => {
+        let result = async {
+          let keys = self.download_certificates().await?;
+          store.save(&StoredPlatformKeys::new(keys.clone())).await?;
+          Ok::<_, WeChatPayError>(keys)
+        }
+        .await;
+        if let Ok(keys) = &result {
+          self.update_public_keys(keys.clone());
+        }
+        // 锁会在有效期后自动释放，解锁失败不影响已下载的证书
+        if let Err(err) = store.unlock(&token).await {
+          tracing::warn!(error = %err, "failed to release platform key store lock");
+        }
+        result.map(|_| ())
+      }
+      None => {
+        // 其他实例正在下载，等待其写入存储；超时后在本地下载，避免被一个缓慢的实例拖住所有请求
+        let wait = self
+          .timeout
+          .map_or(STORE_LOCK_TTL, |t| t.min(STORE_LOCK_TTL));
+        let deadline = Instant::now() + wait;
+        while Instant::now() < deadline {
+          tokio::time::sleep(STORE_POLL_INTERVAL.min(wait)).await;
+          if let Some(stored) = store.load().await? {
+            if stored.updated_at >= since {
+              self.update_public_keys(stored.keys);
+              return Ok(());
+            }
+          }
+        }
+        tracing::warn!(
+          wait_ms = wait.as_millis() as u64,
+          "timed out waiting for platform keys from key store, downloading locally"
+        );
+        let keys = self.download_certificates().await?;
+        self.update_public_keys(keys);
+        Ok(())
+      }
+    }
+  }
+
+  /// 在后台定期同步平台证书，`Client` 被释放后任务自动结束
+  ///
+  /// 配置了[共享存储](crate::store)时，存储中的公钥未超过 `interval` 则直接使用，否则重新下载
+  pub fn spawn_certificate_refresher(self: &Arc<Self>, interval: Duration) -> JoinHandle<()> {
+    let client = Arc::downgrade(self);
+    tokio::spawn(async move {
+      let mut ticker = tokio::time::interval(interval);
+      loop {
+        ticker.tick().await;
+        let Some(client) = client.upgrade() else {
+          break;
+        };
+        if let Err(err) = client.sync_certificates(interval).await {
+          tracing::warn!(error = %err, "failed to sync wechat pay platform certificates");
+        }
+      }
+    })
   }
 }
@@ -34,7 +34,12 @@ pub struct TransferDetailInput {
   /// 同一批次转账明细中的姓名字段传入规则需保持一致，也即全部填写、或全部不填写
   ///
   /// 若商户传入收款用户姓名，微信支付会校验用户 openID 与姓名是否一致，并提供电子回单
-  #[serde(skip_serializing_if = "Option::is_none")]
+  ///
+  /// 该字段为[敏感信息](crate::sensitive)，发送时自动加密
+  #[serde(
+    serialize_with = "crate::sensitive::encrypt",
+    skip_serializing_if = "Option::is_none"
+  )]
   pub user_name: Option<String>,
 }
 
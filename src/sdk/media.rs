@@ -35,20 +35,26 @@ impl Client {
         "filename": filename,
         "sha256": hash
     });
-    let signature = self.request_authorization(&Method::POST, api, &meta.to_string())?;
-    let headers = self.build_header(signature)?;
-    let form = build_form(&meta, image, filename)?;
-    let url = format!("https://api.mch.weixin.qq.com{}", api);
-    let client = reqwest::Client::new();
-    let response = client
-      .post(&url)
-      .headers(headers)
-      .multipart(form)
-      .send()
+    let (meta, image) = (&meta, &image);
+    let response = self
+      .execute(&Method::POST, api, move || async move {
+        let signature = self
+          .request_authorization(&Method::POST, api, &meta.to_string())
+          .await?;
+        let headers = self.build_header(signature)?;
+        let form = build_form(meta, image.clone(), filename)?;
+        Ok(
+          self
+            .http_client
+            .post(self.api_url(api))
+            .headers(headers)
+            .multipart(form)
+            .build()?,
+        )
+      })
       .await?;
-    let (status, text) = self.verify_signatrue(response).await?;
 
-    let response = Self::parse_response::<UploadImageResponse>(status, text)
+    let response = Self::parse_response::<UploadImageResponse>(response)
       .await?
       .unwrap();
     Ok(response)
@@ -64,20 +70,20 @@ impl Client {
       header::ACCEPT,
       header::HeaderValue::from_str("application/json")?,
     );
-
-    let chrome_agent = "Mozilla/5.0 (Linux; Android 10; Redmi K30 Pro) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/86.0.4240.198 Mobile Safari/537.36";
     headers.insert(
       header::USER_AGENT,
-      header::HeaderValue::from_str(chrome_agent)?,
+      header::HeaderValue::from_str(&self.user_agent)?,
     );
     headers.insert(
       header::AUTHORIZATION,
       header::HeaderValue::from_str(&signature)?,
     );
-    headers.insert(
-      "Wechatpay-Serial",
-      header::HeaderValue::from_str(&self.merchant_serial_number)?,
-    );
+    if let Some(pub_key) = self.get_encrypt_public_key() {
+      headers.insert(
+        "Wechatpay-Serial",
+        header::HeaderValue::from_str(&pub_key.serial_no)?,
+      );
+    }
     Ok(headers)
   }
 }
@@ -97,7 +103,7 @@ fn build_form(
   // according to the file extension, get the mime
   let ext = filename
     .split('.')
-    .last()
+    .next_back()
     .ok_or_else(|| WeChatPayError::Unknown("Invalid filename, no extension found".to_string()))?;
   let mime = match ext {
     "jpg" | "jpeg" => Ok("image/jpeg"),
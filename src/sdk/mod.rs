@@ -8,7 +8,7 @@
 //! - 风险合规
 //! - 其他能力
 pub mod basic;
-pub(crate) mod cert;
+pub mod cert;
 pub mod common;
 pub mod fund;
 pub mod media;
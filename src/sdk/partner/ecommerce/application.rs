@@ -1,4 +1,5 @@
 // 参考文档: https://pay.weixin.qq.com/docs/partner/apis/ecommerce-merchant-application/applyment/submit-applyment.html
+// 身份证件、银行账户、联系人等字段为敏感信息，发送时自动加密，见 crate::sensitive
 
 use crate::{Client, WeChatPayError};
 use reqwest::Method;
@@ -149,8 +150,14 @@ pub enum IdDocType {
 pub struct IdCardInfo {
   id_card_copy: String,
   id_card_national: String,
+  #[serde(serialize_with = "crate::sensitive::encrypt")]
   id_card_name: String,
+  #[serde(serialize_with = "crate::sensitive::encrypt")]
   id_card_number: String,
+  #[serde(
+    serialize_with = "crate::sensitive::encrypt",
+    skip_serializing_if = "Option::is_none"
+  )]
   id_card_address: Option<String>,
   id_card_valid_time_begin: String,
   id_card_valid_time: String,
@@ -158,10 +165,16 @@ pub struct IdCardInfo {
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct IdDocInfo {
+  #[serde(serialize_with = "crate::sensitive::encrypt")]
   id_doc_name: String,
+  #[serde(serialize_with = "crate::sensitive::encrypt")]
   id_doc_number: String,
   id_doc_copy: String,
   id_doc_copy_back: Option<String>,
+  #[serde(
+    serialize_with = "crate::sensitive::encrypt",
+    skip_serializing_if = "Option::is_none"
+  )]
   id_doc_address: Option<String>,
   doc_period_begin: String,
   doc_period_end: String,
@@ -199,8 +212,20 @@ pub struct UboInfo {
   ubo_id_doc_type: Option<UboIdDocType>,
   ubo_id_doc_copy: Option<String>,
   ubo_id_doc_copy_back: Option<String>,
+  #[serde(
+    serialize_with = "crate::sensitive::encrypt",
+    skip_serializing_if = "Option::is_none"
+  )]
   ubo_id_doc_name: Option<String>,
+  #[serde(
+    serialize_with = "crate::sensitive::encrypt",
+    skip_serializing_if = "Option::is_none"
+  )]
   ubo_id_doc_number: Option<String>,
+  #[serde(
+    serialize_with = "crate::sensitive::encrypt",
+    skip_serializing_if = "Option::is_none"
+  )]
   ubo_id_doc_address: Option<String>,
   ubo_id_doc_period_begin: Option<String>,
   ubo_id_doc_period_end: Option<String>,
@@ -210,25 +235,37 @@ pub struct UboInfo {
 pub struct AccountInfo {
   account_type: String,
   account_bank: String,
+  #[serde(serialize_with = "crate::sensitive::encrypt")]
   account_name: String,
   bank_address_code: String,
   bank_branch_id: Option<String>,
   bank_name: Option<String>,
+  #[serde(serialize_with = "crate::sensitive::encrypt")]
   account_number: String,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct ContactInfo {
   contact_type: String,
+  #[serde(serialize_with = "crate::sensitive::encrypt")]
   contact_name: String,
   contact_id_doc_type: Option<IdDocType>,
+  #[serde(
+    serialize_with = "crate::sensitive::encrypt",
+    skip_serializing_if = "Option::is_none"
+  )]
   contact_id_card_number: Option<String>,
   contact_id_doc_copy: Option<String>,
   contact_id_doc_copy_back: Option<String>,
   contact_id_doc_period_begin: Option<String>,
   contact_id_doc_period_end: Option<String>,
   business_authorization_letter: Option<String>,
+  #[serde(serialize_with = "crate::sensitive::encrypt")]
   mobile_phone: String,
+  #[serde(
+    serialize_with = "crate::sensitive::encrypt",
+    skip_serializing_if = "Option::is_none"
+  )]
   contact_email: Option<String>,
 }
 
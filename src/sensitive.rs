@@ -0,0 +1,202 @@
+//! # [敏感信息加解密](https://pay.weixin.qq.com/wiki/doc/apiv3/wechatpay/wechatpay4_3.shtml)
+//! 为了保证通信过程中敏感信息字段（如用户的住址、银行卡号、手机号码等）的机密性，微信支付 API v3 要求：
+//! - 商户对上送的敏感信息字段使用微信支付平台证书（或微信支付公钥）中的公钥加密，并在请求头 `Wechatpay-Serial` 中携带对应的序列号（或公钥 ID）
+//! - 微信支付对下行的敏感信息字段使用商户证书中的公钥加密，商户使用 API 私钥解密
+//!
+//! 加密算法为 RSAES-OAEP（SHA-1），密文使用 Base64 编码。
+//!
+//! ## 自动加密
+//! 请求模型中的敏感字段使用 [encrypt] 标注，[send_request](crate::Client::send_request)
+//! 序列化请求时会使用当前有效的公钥加密这些字段，并设置 `Wechatpay-Serial`。
+//! 使用平台证书且本地还没有证书时，会先下载平台证书；仍然没有可用公钥时返回 [WeChatPayError::ConfigError]：
+//! ```no_run
+//! use serde::Serialize;
+//!
+//! #[derive(Serialize)]
+//! pub struct Receiver {
+//!   pub account: String,
+//!   #[serde(serialize_with = "wechat_pay_sdk::sensitive::encrypt")]
+//!   pub name: String,
+//!   #[serde(
+//!     serialize_with = "wechat_pay_sdk::sensitive::encrypt",
+//!     skip_serializing_if = "Option::is_none"
+//!   )]
+//!   pub phone: Option<String>,
+//! }
+//! ```
+//! 在客户端之外序列化这些模型会返回错误，以免明文被发送出去。
+use crate::{Client, WeChatPayError};
+use base64::{engine::general_purpose, Engine};
+use rand::thread_rng;
+use rsa::{Oaep, RsaPublicKey};
+use serde::{ser::Error, Serialize, Serializer};
+use sha1::Sha1;
+use std::cell::{Cell, RefCell};
+
+thread_local! {
+  static ENCRYPT_KEY: RefCell<Option<RsaPublicKey>> = const { RefCell::new(None) };
+  /// 序列化过程中是否遇到了没有公钥可用的敏感字段
+  static KEY_MISSING: Cell<bool> = const { Cell::new(false) };
+}
+
+/// 在 `f` 执行期间，使用 `key` 加密 [encrypt] 标注的字段
+///
+/// 没有公钥而请求体中有敏感字段时返回 [WeChatPayError::ConfigError]
+pub(crate) fn with_encrypt_key<T>(
+  key: Option<&RsaPublicKey>,
+  f: impl FnOnce() -> Result<T, serde_json::Error>,
+) -> Result<T, WeChatPayError> {
+  let previous = ENCRYPT_KEY.with(|cell| cell.replace(key.cloned()));
+  let previous_missing = KEY_MISSING.with(|cell| cell.replace(false));
+  let result = f();
+  ENCRYPT_KEY.with(|cell| cell.replace(previous));
+  let missing = KEY_MISSING.with(|cell| cell.replace(previous_missing));
+  result.map_err(|err| {
+    if missing {
+      no_encrypt_key()
+    } else {
+      err.into()
+    }
+  })
+}
+
+/// 没有可用于加密的公钥
+fn no_encrypt_key() -> WeChatPayError {
+  WeChatPayError::ConfigError("No platform public key for encrypting sensitive fields".to_string())
+}
+
+/// 使用公钥加密敏感信息，返回 Base64 编码的密文
+pub fn encrypt_with_key(key: &RsaPublicKey, plaintext: &str) -> Result<String, WeChatPayError> {
+  let ciphertext = key.encrypt(&mut thread_rng(), Oaep::new::<Sha1>(), plaintext.as_bytes())?;
+  Ok(general_purpose::STANDARD.encode(ciphertext))
+}
+
+/// 可以被加密的字段类型
+pub trait SensitiveValue {
+  /// 没有值需要加密，如 `None`
+  fn is_absent(&self) -> bool {
+    false
+  }
+
+  fn serialize_encrypted<S: Serializer>(
+    &self,
+    key: &RsaPublicKey,
+    serializer: S,
+  ) -> Result<S::Ok, S::Error>;
+}
+
+impl SensitiveValue for String {
+  fn serialize_encrypted<S: Serializer>(
+    &self,
+    key: &RsaPublicKey,
+    serializer: S,
+  ) -> Result<S::Ok, S::Error> {
+    self.as_str().serialize_encrypted(key, serializer)
+  }
+}
+
+impl SensitiveValue for str {
+  fn serialize_encrypted<S: Serializer>(
+    &self,
+    key: &RsaPublicKey,
+    serializer: S,
+  ) -> Result<S::Ok, S::Error> {
+    encrypt_with_key(key, self)
+      .map_err(S::Error::custom)?
+      .serialize(serializer)
+  }
+}
+
+impl<T: SensitiveValue> SensitiveValue for Option<T> {
+  fn is_absent(&self) -> bool {
+    self.is_none()
+  }
+
+  fn serialize_encrypted<S: Serializer>(
+    &self,
+    key: &RsaPublicKey,
+    serializer: S,
+  ) -> Result<S::Ok, S::Error> {
+    match self {
+      Some(value) => value.serialize_encrypted(key, serializer),
+      None => serializer.serialize_none(),
+    }
+  }
+}
+
+/// 用于 `#[serde(serialize_with = "...")]`，加密敏感字段
+pub fn encrypt<T, S>(value: &T, serializer: S) -> Result<S::Ok, S::Error>
+where
+  T: SensitiveValue + ?Sized,
+  S: Serializer,
+{
+  // 没有值时无需公钥，字段仍应同时标注 `skip_serializing_if = "Option::is_none"`
+  if value.is_absent() {
+    return serializer.serialize_none();
+  }
+  ENCRYPT_KEY.with(|cell| match cell.borrow().as_ref() {
+    Some(key) => value.serialize_encrypted(key, serializer),
+    None => {
+      KEY_MISSING.with(|cell| cell.set(true));
+      Err(S::Error::custom(
+        "sensitive field must be serialized by Client with a platform public key",
+      ))
+    }
+  })
+}
+
+impl Client {
+  /// 使用当前有效的平台公钥（或微信支付公钥）加密敏感信息
+  ///
+  /// 返回公钥的序列号（即请求头 `Wechatpay-Serial` 的值）和 Base64 编码的密文
+  pub fn encrypt_sensitive(&self, plaintext: &str) -> Result<(String, String), WeChatPayError> {
+    let pub_key = self.get_encrypt_public_key().ok_or_else(no_encrypt_key)?;
+    Ok((
+      pub_key.serial_no,
+      encrypt_with_key(&pub_key.key, plaintext)?,
+    ))
+  }
+
+  /// 使用商户 API 私钥解密应答中的敏感信息
+  ///
+  /// 只配置了外部 [Signer](crate::signer::Signer) 而没有商户私钥时返回错误
+  pub fn decrypt_sensitive(&self, ciphertext: &str) -> Result<String, WeChatPayError> {
+    let ciphertext = general_purpose::STANDARD.decode(ciphertext)?;
+    let private_key = self.private_key.as_ref().ok_or_else(|| {
+      WeChatPayError::ConfigError(
+        "Decrypting requires the merchant private key, a custom signer alone cannot decrypt"
+          .to_string(),
+      )
+    })?;
+    let plaintext = private_key.decrypt(Oaep::new::<Sha1>(), ciphertext.as_slice())?;
+    String::from_utf8(plaintext).map_err(|e| WeChatPayError::DecodeError(Box::new(e)))
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[derive(Serialize)]
+  struct Receiver {
+    #[serde(serialize_with = "encrypt")]
+    name: Option<String>,
+  }
+
+  #[test]
+  fn none_needs_no_key() {
+    let json = with_encrypt_key(None, || serde_json::to_string(&Receiver { name: None })).unwrap();
+    assert_eq!(json, r#"{"name":null}"#);
+  }
+
+  #[test]
+  fn missing_key_is_config_error() {
+    let err = with_encrypt_key(None, || {
+      serde_json::to_string(&Receiver {
+        name: Some("张三".to_string()),
+      })
+    })
+    .unwrap_err();
+    assert!(matches!(err, WeChatPayError::ConfigError(_)));
+  }
+}
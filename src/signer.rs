@@ -0,0 +1,92 @@
+//! # 请求签名
+//! 所有需要商户 API 私钥签名的地方（[请求签名](crate::Client::request_authorization)、调起支付参数等）都通过 [Signer] 完成。
+//!
+//! 默认使用进程内的 [RsaSigner]。商户私钥不允许出现在应用内存中时，可以实现 [Signer] 调用远程签名服务（如 KMS、HSM），
+//! 并通过 [ClientBuilder::signer](crate::ClientBuilder::signer) 启用：
+//! ```no_run
+//! use async_trait::async_trait;
+//! use std::sync::Arc;
+//! use wechat_pay_sdk::{signer::Signer, Client, WeChatPayError};
+//!
+//! #[derive(Debug)]
+//! struct RemoteSigner {
+//!   http: reqwest::Client,
+//! }
+//!
+//! #[async_trait]
+//! impl Signer for RemoteSigner {
+//!   async fn sign(&self, message: &[u8]) -> Result<Vec<u8>, WeChatPayError> {
+//!     let res = self
+//!       .http
+//!       .post("http://signer.internal/sign")
+//!       .body(message.to_vec())
+//!       .send()
+//!       .await?;
+//!     Ok(res.bytes().await?.to_vec())
+//!   }
+//! }
+//!
+//! # fn main() -> Result<(), WeChatPayError> {
+//! let signer = RemoteSigner { http: reqwest::Client::new() };
+//! let client = Client::builder("1900000100", "5157F09EFDC096DE15EBE81A47057A72", "0123456789abcdef0123456789abcdef")
+//!   .signer(Arc::new(signer))
+//!   .build()?;
+//! # Ok(())
+//! # }
+//! ```
+use crate::WeChatPayError;
+use async_trait::async_trait;
+use rsa::sha2::{Digest, Sha256};
+use rsa::{Pkcs1v15Sign, RsaPrivateKey, RsaPublicKey};
+
+/// 商户签名器
+#[async_trait]
+pub trait Signer: Send + Sync + std::fmt::Debug {
+  /// 使用商户 API 私钥对 `message` 做 SHA256withRSA（PKCS#1 v1.5）签名，返回未编码的签名
+  async fn sign(&self, message: &[u8]) -> Result<Vec<u8>, WeChatPayError>;
+  /// 签名私钥对应的公钥，用于构建客户端时检查与商户证书是否匹配；无法获取时返回 `None`
+  fn public_key(&self) -> Option<RsaPublicKey> {
+    None
+  }
+}
+
+/// 使用进程内 RSA 私钥签名
+#[derive(Clone)]
+pub struct RsaSigner {
+  private_key: RsaPrivateKey,
+}
+
+impl RsaSigner {
+  pub fn new(private_key: RsaPrivateKey) -> Self {
+    Self { private_key }
+  }
+
+  /// 同步签名，不经过异步运行时
+  pub fn sign_sync(&self, message: &[u8]) -> Result<Vec<u8>, WeChatPayError> {
+    let mut hasher: Sha256 = Digest::new();
+    hasher.update(message);
+    let hash = hasher.finalize();
+    Ok(
+      self
+        .private_key
+        .sign(Pkcs1v15Sign::new::<Sha256>(), &hash)?,
+    )
+  }
+}
+
+impl std::fmt::Debug for RsaSigner {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    f.debug_struct("RsaSigner").finish_non_exhaustive()
+  }
+}
+
+#[async_trait]
+impl Signer for RsaSigner {
+  async fn sign(&self, message: &[u8]) -> Result<Vec<u8>, WeChatPayError> {
+    self.sign_sync(message)
+  }
+
+  fn public_key(&self) -> Option<RsaPublicKey> {
+    Some(RsaPublicKey::from(&self.private_key))
+  }
+}
@@ -0,0 +1,52 @@
+//! # 平台公钥共享存储
+//! 多个实例各自下载平台证书既浪费又容易产生竞争。通过 [PlatformKeyStore]，多个实例可以共享解密后的平台公钥：
+//! - 需要下载平台证书时，先获取分布式锁，只有持有锁的实例会真正下载，并将结果写入存储
+//! - 其余实例从存储中读取，平台证书轮换后无需重启即可获得新的公钥
+//!
+//! 通过 [ClientBuilder::key_store](crate::ClientBuilder::key_store) 启用，内置 [RedisKeyStore] 实现。
+mod redis;
+
+use crate::{PlatformPubKey, WeChatPayError};
+use async_trait::async_trait;
+pub use redis::RedisKeyStore;
+use serde::{Deserialize, Serialize};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// 存储中的平台公钥
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StoredPlatformKeys {
+  /// 下载时间，Unix 时间戳（秒）
+  pub updated_at: u64,
+  pub keys: Vec<PlatformPubKey>,
+}
+
+impl StoredPlatformKeys {
+  pub fn new(keys: Vec<PlatformPubKey>) -> Self {
+    let updated_at = SystemTime::now()
+      .duration_since(UNIX_EPOCH)
+      .map(|d| d.as_secs())
+      .unwrap_or_default();
+    Self { updated_at, keys }
+  }
+  /// 距离下载的时间
+  pub fn age(&self) -> Duration {
+    let now = SystemTime::now()
+      .duration_since(UNIX_EPOCH)
+      .map(|d| d.as_secs())
+      .unwrap_or_default();
+    Duration::from_secs(now.saturating_sub(self.updated_at))
+  }
+}
+
+/// 平台公钥存储
+#[async_trait]
+pub trait PlatformKeyStore: Send + Sync + std::fmt::Debug {
+  /// 读取平台公钥，不存在时返回 `None`
+  async fn load(&self) -> Result<Option<StoredPlatformKeys>, WeChatPayError>;
+  /// 写入平台公钥
+  async fn save(&self, keys: &StoredPlatformKeys) -> Result<(), WeChatPayError>;
+  /// 尝试获取刷新锁，成功时返回用于释放锁的令牌；锁在 `ttl` 后自动过期
+  async fn try_lock(&self, ttl: Duration) -> Result<Option<String>, WeChatPayError>;
+  /// 释放刷新锁，只有令牌匹配时才会释放
+  async fn unlock(&self, token: &str) -> Result<(), WeChatPayError>;
+}
@@ -0,0 +1,163 @@
+use super::{PlatformKeyStore, StoredPlatformKeys};
+use crate::crypto::nonce_str;
+use crate::WeChatPayError;
+use async_trait::async_trait;
+use redis::aio::MultiplexedConnection;
+use redis::Script;
+use std::time::Duration;
+
+/// 仅当令牌匹配时删除锁
+const UNLOCK_SCRIPT: &str = r#"
+if redis.call("GET", KEYS[1]) == ARGV[1] then
+  return redis.call("DEL", KEYS[1])
+else
+  return 0
+end
+"#;
+
+/// # 基于 Redis 的平台公钥存储
+/// 平台公钥以 JSON 格式保存在 `{prefix}:platform_keys:{merchant_id}`，刷新锁为 `{prefix}:platform_keys:{merchant_id}:lock`
+#[derive(Clone)]
+pub struct RedisKeyStore {
+  connection: MultiplexedConnection,
+  key: String,
+  lock_key: String,
+}
+
+impl std::fmt::Debug for RedisKeyStore {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    f.debug_struct("RedisKeyStore")
+      .field("key", &self.key)
+      .field("lock_key", &self.lock_key)
+      .finish()
+  }
+}
+
+impl RedisKeyStore {
+  /// # Arguments
+  ///
+  /// * `connection` - Redis 连接
+  /// * `prefix` - 键前缀，例如 `wechat-pay`
+  /// * `merchant_id` - 商户号，不同商户的平台证书不同
+  pub fn new(connection: MultiplexedConnection, prefix: &str, merchant_id: &str) -> Self {
+    let (key, lock_key) = key_names(prefix, merchant_id);
+    Self {
+      connection,
+      key,
+      lock_key,
+    }
+  }
+}
+
+/// 平台公钥和刷新锁的键名
+fn key_names(prefix: &str, merchant_id: &str) -> (String, String) {
+  let key = format!("{}:platform_keys:{}", prefix, merchant_id);
+  let lock_key = format!("{}:lock", key);
+  (key, lock_key)
+}
+
+fn encode(keys: &StoredPlatformKeys) -> Result<String, WeChatPayError> {
+  Ok(serde_json::to_string(keys)?)
+}
+
+fn decode(value: Option<String>) -> Result<Option<StoredPlatformKeys>, WeChatPayError> {
+  value
+    .map(|value| serde_json::from_str(&value))
+    .transpose()
+    .map_err(Into::into)
+}
+
+#[async_trait]
+impl PlatformKeyStore for RedisKeyStore {
+  async fn load(&self) -> Result<Option<StoredPlatformKeys>, WeChatPayError> {
+    let mut connection = self.connection.clone();
+    let value: Option<String> = redis::cmd("GET")
+      .arg(&self.key)
+      .query_async(&mut connection)
+      .await?;
+    decode(value)
+  }
+
+  async fn save(&self, keys: &StoredPlatformKeys) -> Result<(), WeChatPayError> {
+    let mut connection = self.connection.clone();
+    redis::cmd("SET")
+      .arg(&self.key)
+      .arg(encode(keys)?)
+      .query_async::<_, ()>(&mut connection)
+      .await?;
+    Ok(())
+  }
+
+  async fn try_lock(&self, ttl: Duration) -> Result<Option<String>, WeChatPayError> {
+    let mut connection = self.connection.clone();
+    let token = nonce_str();
+    let locked: Option<String> = redis::cmd("SET")
+      .arg(&self.lock_key)
+      .arg(&token)
+      .arg("NX")
+      .arg("PX")
+      .arg(ttl.as_millis() as u64)
+      .query_async(&mut connection)
+      .await?;
+    Ok(locked.map(|_| token))
+  }
+
+  async fn unlock(&self, token: &str) -> Result<(), WeChatPayError> {
+    let mut connection = self.connection.clone();
+    Script::new(UNLOCK_SCRIPT)
+      .key(&self.lock_key)
+      .arg(token)
+      .invoke_async::<_, i32>(&mut connection)
+      .await?;
+    Ok(())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::PlatformPubKey;
+
+  #[test]
+  fn key_names_include_prefix_and_merchant() {
+    let (key, lock_key) = key_names("wechat-pay", "1900000109");
+    assert_eq!(key, "wechat-pay:platform_keys:1900000109");
+    assert_eq!(lock_key, "wechat-pay:platform_keys:1900000109:lock");
+  }
+
+  #[test]
+  fn stored_keys_round_trip() {
+    let stored = StoredPlatformKeys::new(vec![PlatformPubKey {
+      serial_no: "5157F09EFDC096DE15EBE81A47057A72".to_string(),
+      expire_time: 1893456000,
+      effective_time: 1577836800,
+      key: "-----BEGIN PUBLIC KEY-----\nMIIB\n-----END PUBLIC KEY-----\n".to_string(),
+    }]);
+    let decoded = decode(Some(encode(&stored).unwrap())).unwrap().unwrap();
+    assert_eq!(decoded.updated_at, stored.updated_at);
+    assert_eq!(decoded.keys.len(), 1);
+    assert_eq!(decoded.keys[0].serial_no, stored.keys[0].serial_no);
+    assert_eq!(decoded.keys[0].expire_time, stored.keys[0].expire_time);
+    assert_eq!(
+      decoded.keys[0].effective_time,
+      stored.keys[0].effective_time
+    );
+    assert_eq!(decoded.keys[0].key, stored.keys[0].key);
+  }
+
+  #[test]
+  fn missing_or_corrupt_value() {
+    assert!(decode(None).unwrap().is_none());
+    let err = decode(Some("not json".to_string())).unwrap_err();
+    assert!(matches!(err, WeChatPayError::JsonError(_)));
+  }
+
+  #[test]
+  fn lock_tokens_are_unique() {
+    let tokens: std::collections::HashSet<_> = (0..100).map(|_| nonce_str()).collect();
+    assert_eq!(tokens.len(), 100);
+    assert!(tokens
+      .iter()
+      .all(|token| token.len() == 32 && token.chars().all(|c| c.is_ascii_alphanumeric())));
+  }
+}
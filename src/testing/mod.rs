@@ -0,0 +1,650 @@
+//! # 模拟微信支付服务器
+//! 启用 `testing` feature 后可用。[MockServer] 在本地启动一个 HTTP 服务，模拟 SDK 封装的 v3 接口，
+//! 使基于 [Client] 的代码可以在离线环境中完成端到端测试：
+//! - 下单（JSAPI、APP、H5、Native）、查询订单、关闭订单
+//! - 合单下单、合单查询、合单关单
+//! - 申请退款、查询退款
+//! - 下载平台证书
+//! - 批量转账、上传图片、电商进件
+//!
+//! 服务器验证商户请求签名，使用自动生成的平台私钥为应答签名，并在内存中保存订单状态。
+//! 通过 [inject_error](MockServer::inject_error) 可以让指定接口返回错误码，
+//! 通过 [notification](MockServer::notification) 等方法可以生成签名并加密的回调通知。
+//!
+//! # Example
+//! ```
+//! use wechat_pay_sdk::sdk::common::{Amount, OrderRequest, Payer};
+//! use wechat_pay_sdk::testing::{InjectedError, MockServer};
+//! use wechat_pay_sdk::webhook::transaction::TransactionSuccess;
+//! use reqwest::Method;
+//!
+//! # #[tokio::main]
+//! # async fn main() -> Result<(), wechat_pay_sdk::WeChatPayError> {
+//! let server = MockServer::start().await?;
+//! let client = server.client();
+//! let order = OrderRequest {
+//!   appid: "wxd678efh567hg6787".to_string(),
+//!   mchid: server.merchant_id().to_string(),
+//!   description: "Image形象店-深圳腾大-QQ公仔".to_string(),
+//!   out_trade_no: "1217752501201407033233368018".to_string(),
+//!   time_expire: None,
+//!   attach: None,
+//!   notify_url: "https://www.weixin.qq.com/wxpay/pay.php".to_string(),
+//!   goods_tag: None,
+//!   support_fapiao: None,
+//!   amount: Amount { total: 100, currency: None },
+//!   payer: Payer { openid: "oUpF8uMuAJO_M2pxb1Q9zNjWeS6o".to_string() },
+//!   detail: None,
+//!   scene_info: None,
+//!   settle_info: None,
+//! };
+//! client.jsapi_order(&order).await?;
+//! server.pay_order("1217752501201407033233368018");
+//!
+//! let notification = server.order_notification("1217752501201407033233368018").unwrap();
+//! let (_, transaction) = client
+//!   .parse_webhook::<TransactionSuccess>(&notification.headers, notification.body.as_bytes())
+//!   .await?;
+//! assert_eq!(transaction.out_trade_no, "1217752501201407033233368018");
+//!
+//! server.inject_error(
+//!   Method::POST,
+//!   "/v3/pay/transactions/jsapi",
+//!   InjectedError::new(500, "SYSTEM_ERROR"),
+//! );
+//! assert!(client.jsapi_order(&order).await.is_err());
+//! # Ok(())
+//! # }
+//! ```
+mod keys;
+mod server;
+
+use crate::webhook::SIGNTEST_PREFIX;
+use crate::{Client, ClientBuilder, PlatformPubKey, VerificationMode, WeChatPayError};
+use base64::{engine::general_purpose, Engine};
+use keys::TestKey;
+use reqwest::header::{HeaderMap, HeaderValue, CONTENT_TYPE};
+use reqwest::Method;
+use rsa::{Oaep, RsaPublicKey};
+use serde_json::{json, Value};
+use server::{rfc3339, Shared, State};
+use sha1::Sha1;
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+use tokio::net::TcpListener;
+use tokio::task::JoinHandle;
+
+/// 模拟服务器使用的商户号
+pub const MOCK_MERCHANT_ID: &str = "1900000100";
+/// 模拟服务器使用的 APIv3 密钥
+pub const MOCK_API_V3_KEY: &str = "mockapiv3key0123456789abcdefghij";
+
+/// # 模拟微信支付服务器
+/// 服务器在被释放时停止
+pub struct MockServer {
+  addr: SocketAddr,
+  merchant: TestKey,
+  shared: Arc<Shared>,
+  platform_public_key_pem: String,
+  task: JoinHandle<()>,
+}
+
+impl MockServer {
+  /// 在 `127.0.0.1` 的随机端口上启动服务器，并生成商户和平台密钥
+  pub async fn start() -> Result<Self, WeChatPayError> {
+    let merchant = TestKey::generate("mock merchant")?;
+    let platform = TestKey::generate("mock wechatpay platform")?;
+    let listener = TcpListener::bind("127.0.0.1:0").await?;
+    let addr = listener.local_addr()?;
+    let shared = Arc::new(Shared {
+      merchant_id: MOCK_MERCHANT_ID.to_string(),
+      merchant_serial_no: merchant.serial_no.clone(),
+      merchant_public_key: RsaPublicKey::from(&merchant.private_key),
+      api_key: MOCK_API_V3_KEY.to_string(),
+      platform_private_key: platform.private_key.clone(),
+      platform_signer: crate::signer::RsaSigner::new(platform.private_key),
+      platform_serial_no: platform.serial_no,
+      platform_certificate_pem: platform.certificate_pem,
+      state: Mutex::new(State::default()),
+    });
+    let task = tokio::spawn(server::serve(listener, shared.clone()));
+    Ok(Self {
+      addr,
+      merchant,
+      shared,
+      platform_public_key_pem: platform.public_key_pem,
+      task,
+    })
+  }
+
+  /// 服务器地址，如 `http://127.0.0.1:12345`
+  pub fn base_url(&self) -> String {
+    format!("http://{}", self.addr)
+  }
+
+  pub fn merchant_id(&self) -> &str {
+    &self.shared.merchant_id
+  }
+
+  /// 商户 API 证书序列号
+  pub fn merchant_serial_no(&self) -> &str {
+    &self.merchant.serial_no
+  }
+
+  /// 商户 API 私钥（PEM 格式）
+  pub fn merchant_private_key_pem(&self) -> &str {
+    &self.merchant.private_key_pem
+  }
+
+  /// 商户 API 证书（PEM 格式）
+  pub fn merchant_certificate_pem(&self) -> &str {
+    &self.merchant.certificate_pem
+  }
+
+  /// 平台证书序列号，应答的 `Wechatpay-Serial`
+  pub fn platform_serial_no(&self) -> &str {
+    &self.shared.platform_serial_no
+  }
+
+  /// 平台公钥
+  pub fn platform_public_key(&self) -> PlatformPubKey {
+    let now = chrono::Utc::now().timestamp() as u64;
+    PlatformPubKey {
+      serial_no: self.shared.platform_serial_no.clone(),
+      effective_time: now - 86400,
+      expire_time: now + 365 * 86400,
+      key: self.platform_public_key_pem.clone(),
+    }
+  }
+
+  /// 使用平台私钥解密请求中的[敏感信息](crate::sensitive)，无法解密时返回 `None`
+  pub fn decrypt_sensitive(&self, ciphertext: &str) -> Option<String> {
+    let ciphertext = general_purpose::STANDARD.decode(ciphertext).ok()?;
+    let plaintext = self
+      .shared
+      .platform_private_key
+      .decrypt(Oaep::new::<Sha1>(), &ciphertext)
+      .ok()?;
+    String::from_utf8(plaintext).ok()
+  }
+
+  /// 使用商户公钥加密应答中的敏感信息，与微信支付下发敏感信息的方式相同
+  pub fn encrypt_sensitive(&self, plaintext: &str) -> String {
+    crate::sensitive::encrypt_with_key(&self.shared.merchant_public_key, plaintext)
+      .expect("merchant public key can encrypt short plaintext")
+  }
+
+  /// 指向该服务器的 [ClientBuilder]，已配置商户号、密钥和平台公钥，可以继续调整其他配置
+  pub fn client_builder(&self) -> ClientBuilder {
+    Client::builder(MOCK_MERCHANT_ID, "", MOCK_API_V3_KEY)
+      .private_key_pem(&self.merchant.private_key_pem)
+      .merchant_certificate_pem(&self.merchant.certificate_pem)
+      .platform_pub_keys(vec![self.platform_public_key()])
+      .verification_mode(VerificationMode::PlatformCertificate)
+      .base_url(&self.base_url())
+  }
+
+  /// 指向该服务器的 [Client]
+  pub fn client(&self) -> Client {
+    self
+      .client_builder()
+      .build()
+      .expect("mock client configuration is valid")
+  }
+
+  /// 让 `method` 和 `path` 匹配的请求返回错误，`path` 可以使用 `{}` 匹配任意一段路径，
+  /// 如 `/v3/pay/transactions/out-trade-no/{}`
+  ///
+  /// 签名验证之后、业务处理之前检查，注入的错误不会改变订单状态
+  pub fn inject_error(&self, method: Method, path: &str, error: InjectedError) {
+    self.state().errors.push((method, path.to_string(), error));
+  }
+
+  /// 清除所有注入的错误
+  pub fn clear_errors(&self) {
+    self.state().errors.clear();
+  }
+
+  /// 查询订单
+  pub fn order(&self, out_trade_no: &str) -> Option<MockOrder> {
+    self.state().orders.get(out_trade_no).cloned()
+  }
+
+  /// 模拟用户完成支付，返回支付后的订单；订单不存在或不是待支付状态时返回 `None`
+  pub fn pay_order(&self, out_trade_no: &str) -> Option<MockOrder> {
+    let mut state = self.state();
+    let id = state.next_id();
+    let order = state.orders.get_mut(out_trade_no)?;
+    if order.trade_state != "NOTPAY" {
+      return None;
+    }
+    order.trade_state = "SUCCESS".to_string();
+    order.transaction_id = Some(format!("4200{:024}", id));
+    order.success_time = Some(rfc3339(chrono::Utc::now()));
+    Some(order.clone())
+  }
+
+  /// 查询合单
+  pub fn combine_order(&self, combine_out_trade_no: &str) -> Option<MockCombineOrder> {
+    self.state().combines.get(combine_out_trade_no).cloned()
+  }
+
+  /// 模拟用户完成合单支付，所有子单同时支付成功；合单不存在或子单不是待支付状态时返回 `None`
+  pub fn pay_combine_order(&self, combine_out_trade_no: &str) -> Option<MockCombineOrder> {
+    let combine = self.combine_order(combine_out_trade_no)?;
+    let mut state = self.state();
+    let unpaid = combine.sub_orders.iter().all(|out_trade_no| {
+      state
+        .orders
+        .get(out_trade_no)
+        .is_some_and(|order| order.trade_state == "NOTPAY")
+    });
+    if !unpaid {
+      return None;
+    }
+    let success_time = rfc3339(chrono::Utc::now());
+    for out_trade_no in &combine.sub_orders {
+      let id = state.next_id();
+      let order = state.orders.get_mut(out_trade_no)?;
+      order.trade_state = "SUCCESS".to_string();
+      order.transaction_id = Some(format!("4200{:024}", id));
+      order.success_time = Some(success_time.clone());
+    }
+    Some(combine)
+  }
+
+  /// 查询退款
+  pub fn refund(&self, out_refund_no: &str) -> Option<MockRefund> {
+    self.state().refunds.get(out_refund_no).cloned()
+  }
+
+  /// 模拟退款到账
+  pub fn complete_refund(&self, out_refund_no: &str) -> Option<MockRefund> {
+    let mut state = self.state();
+    let refund = state.refunds.get_mut(out_refund_no)?;
+    refund.status = "SUCCESS".to_string();
+    refund.success_time = Some(rfc3339(chrono::Utc::now()));
+    Some(refund.clone())
+  }
+
+  /// 服务器收到的所有请求，按接收顺序排列
+  pub fn requests(&self) -> Vec<RecordedRequest> {
+    self.state().requests.clone()
+  }
+
+  /// 生成一条使用平台私钥签名、APIv3 密钥加密的回调通知
+  ///
+  /// # Arguments
+  ///
+  /// * `event_type` - 通知类型，如 `TRANSACTION.SUCCESS`
+  /// * `original_type` - 加密前的对象类型，如 `transaction`
+  /// * `summary` - 回调摘要
+  /// * `resource` - 通知数据明文
+  pub fn notification(
+    &self,
+    event_type: &str,
+    original_type: &str,
+    summary: &str,
+    resource: &Value,
+  ) -> MockNotification {
+    let id = self.state().next_id();
+    let (nonce, ciphertext) = self
+      .shared
+      .encrypt(resource.to_string().as_bytes(), original_type);
+    let body = json!({
+      "id": format!("EV-{:020}", id),
+      "create_time": rfc3339(chrono::Utc::now()),
+      "resource_type": "encrypt-resource",
+      "event_type": event_type,
+      "summary": summary,
+      "resource": {
+        "original_type": original_type,
+        "algorithm": "AEAD_AES_256_GCM",
+        "ciphertext": ciphertext,
+        "associated_data": original_type,
+        "nonce": nonce,
+      },
+    })
+    .to_string();
+    let (timestamp, nonce, signature) = self.shared.sign(&body);
+    let mut headers = HeaderMap::new();
+    let mut insert = |name: &'static str, value: &str| {
+      headers.insert(name, HeaderValue::from_str(value).unwrap());
+    };
+    insert("Wechatpay-Timestamp", &timestamp);
+    insert("Wechatpay-Nonce", &nonce);
+    insert("Wechatpay-Serial", &self.shared.platform_serial_no);
+    insert("Wechatpay-Signature", &signature);
+    insert("Wechatpay-Signature-Type", "WECHATPAY2-SHA256-RSA2048");
+    headers.insert(CONTENT_TYPE, HeaderValue::from_static("application/json"));
+    MockNotification { headers, body }
+  }
+
+  /// 订单的支付成功通知，订单不存在或尚未支付时返回 `None`
+  pub fn order_notification(&self, out_trade_no: &str) -> Option<MockNotification> {
+    let order = self
+      .order(out_trade_no)
+      .filter(|order| order.transaction_id.is_some())?;
+    Some(self.notification(
+      "TRANSACTION.SUCCESS",
+      "transaction",
+      "支付成功",
+      &order.to_json(),
+    ))
+  }
+
+  /// 合单支付成功通知，合单不存在或尚未支付时返回 `None`
+  pub fn combine_notification(&self, combine_out_trade_no: &str) -> Option<MockNotification> {
+    let resource = {
+      let state = self.state();
+      let combine = state.combines.get(combine_out_trade_no)?;
+      let paid = combine.sub_orders.iter().all(|out_trade_no| {
+        state
+          .orders
+          .get(out_trade_no)
+          .is_some_and(|order| order.transaction_id.is_some())
+      });
+      if !paid {
+        return None;
+      }
+      combine.to_json(&state.orders)
+    };
+    Some(self.notification("TRANSACTION.SUCCESS", "transaction", "支付成功", &resource))
+  }
+
+  /// 退款成功通知，退款不存在或尚未到账时返回 `None`
+  pub fn refund_notification(&self, out_refund_no: &str) -> Option<MockNotification> {
+    let refund = self
+      .refund(out_refund_no)
+      .filter(|refund| refund.status == "SUCCESS")?;
+    Some(self.notification(
+      "REFUND.SUCCESS",
+      "refund",
+      "退款成功",
+      &refund.notification_json(self.merchant_id()),
+    ))
+  }
+
+  fn state(&self) -> std::sync::MutexGuard<'_, State> {
+    self.shared.state.lock().unwrap()
+  }
+}
+
+impl Drop for MockServer {
+  fn drop(&mut self) {
+    self.task.abort();
+  }
+}
+
+impl std::fmt::Debug for MockServer {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    f.debug_struct("MockServer")
+      .field("addr", &self.addr)
+      .finish_non_exhaustive()
+  }
+}
+
+/// 注入的错误应答
+#[derive(Debug, Clone)]
+pub struct InjectedError {
+  /// HTTP 状态码
+  pub status: u16,
+  /// 错误码
+  pub code: String,
+  pub message: String,
+  /// 剩余次数，`None` 表示一直生效
+  pub times: Option<usize>,
+}
+
+impl InjectedError {
+  /// 只生效一次的错误
+  pub fn new(status: u16, code: &str) -> Self {
+    Self {
+      status,
+      code: code.to_string(),
+      message: code.to_string(),
+      times: Some(1),
+    }
+  }
+  pub fn message(mut self, message: &str) -> Self {
+    self.message = message.to_string();
+    self
+  }
+  /// 生效 `times` 次
+  pub fn times(mut self, times: usize) -> Self {
+    self.times = Some(times.max(1));
+    self
+  }
+  /// 一直生效，直到 [clear_errors](MockServer::clear_errors)
+  pub fn always(mut self) -> Self {
+    self.times = None;
+    self
+  }
+}
+
+/// 模拟服务器中的订单
+#[derive(Debug, Clone)]
+pub struct MockOrder {
+  pub appid: String,
+  pub mchid: String,
+  pub out_trade_no: String,
+  /// 支付后生成
+  pub transaction_id: Option<String>,
+  pub prepay_id: String,
+  /// JSAPI、APP、MWEB、NATIVE
+  pub trade_type: String,
+  /// NOTPAY、SUCCESS、REFUND、CLOSED
+  pub trade_state: String,
+  pub description: String,
+  pub attach: Option<String>,
+  /// 订单金额，单位为分
+  pub total: i32,
+  /// 已退款金额
+  pub refunded: i32,
+  pub openid: Option<String>,
+  pub success_time: Option<String>,
+}
+
+/// 下单应答，普通下单和合单下单的格式相同
+fn prepay_json(trade_type: &str, prepay_id: &str) -> Value {
+  match trade_type {
+    "MWEB" => json!({
+      "h5_url": format!(
+        "https://wx.tenpay.com/cgi-bin/mmpayweb-bin/checkmweb?prepay_id={}&package=2150917749",
+        prepay_id
+      )
+    }),
+    "NATIVE" => json!({ "code_url": format!("weixin://wxpay/bizpayurl?pr={}", prepay_id) }),
+    _ => json!({ "prepay_id": prepay_id }),
+  }
+}
+
+impl MockOrder {
+  fn prepay_json(&self) -> Value {
+    prepay_json(&self.trade_type, &self.prepay_id)
+  }
+
+  fn to_json(&self) -> Value {
+    let paid = self.transaction_id.is_some();
+    json!({
+      "appid": self.appid,
+      "mchid": self.mchid,
+      "out_trade_no": self.out_trade_no,
+      "transaction_id": self.transaction_id,
+      "trade_type": self.trade_type,
+      "trade_state": self.trade_state,
+      "trade_state_desc": match self.trade_state.as_str() {
+        "SUCCESS" => "支付成功",
+        "REFUND" => "转入退款",
+        "CLOSED" => "已关闭",
+        _ => "未支付",
+      },
+      "bank_type": if paid { Some("OTHERS") } else { None },
+      "attach": self.attach,
+      "success_time": self.success_time,
+      "payer": { "openid": self.openid },
+      "amount": {
+        "total": self.total,
+        "payer_total": if paid { Some(self.total) } else { None },
+        "currency": "CNY",
+        "payer_currency": "CNY",
+      },
+    })
+  }
+}
+
+/// 模拟服务器中的退款
+#[derive(Debug, Clone)]
+pub struct MockRefund {
+  pub out_refund_no: String,
+  pub refund_id: String,
+  pub out_trade_no: String,
+  pub transaction_id: String,
+  /// 退款金额，单位为分
+  pub refund: i32,
+  /// 订单金额
+  pub total: i32,
+  /// PROCESSING、SUCCESS
+  pub status: String,
+  pub create_time: String,
+  pub success_time: Option<String>,
+}
+
+impl MockRefund {
+  fn notification_json(&self, mchid: &str) -> Value {
+    json!({
+      "mchid": mchid,
+      "out_trade_no": self.out_trade_no,
+      "transaction_id": self.transaction_id,
+      "out_refund_no": self.out_refund_no,
+      "refund_id": self.refund_id,
+      "refund_status": self.status,
+      "success_time": self.success_time,
+      "user_received_account": "支付用户零钱",
+      "amount": {
+        "total": self.total,
+        "refund": self.refund,
+        "payer_total": self.total,
+        "payer_refund": self.refund,
+      },
+    })
+  }
+
+  fn to_json(&self) -> Value {
+    json!({
+      "refund_id": self.refund_id,
+      "out_refund_no": self.out_refund_no,
+      "transaction_id": self.transaction_id,
+      "out_trade_no": self.out_trade_no,
+      "channel": "ORIGINAL",
+      "user_received_account": "支付用户零钱",
+      "success_time": self.success_time,
+      "create_time": self.create_time,
+      "status": self.status,
+      "funds_account": "AVAILABLE",
+      "amount": {
+        "total": self.total,
+        "refund": self.refund,
+        "from": [],
+        "payer_total": self.total,
+        "payer_refund": self.refund,
+        "settlement_refund": self.refund,
+        "settlement_total": self.total,
+        "discount_refund": 0,
+        "currency": "CNY",
+      },
+    })
+  }
+}
+
+/// 服务器收到的请求
+#[derive(Debug, Clone)]
+pub struct RecordedRequest {
+  pub method: String,
+  /// 包含查询参数的路径
+  pub path: String,
+  /// 参与签名的请求体，上传接口为 `meta` 部分
+  pub body: String,
+  /// 请求头，如 `Authorization`、`Wechatpay-Serial`
+  pub headers: HeaderMap,
+}
+
+/// 模拟的回调通知
+#[derive(Debug, Clone)]
+pub struct MockNotification {
+  /// `Wechatpay-Timestamp`、`Wechatpay-Nonce`、`Wechatpay-Serial`、`Wechatpay-Signature` 等 HTTP 头
+  pub headers: HeaderMap,
+  /// 原始报文
+  pub body: String,
+}
+
+impl MockNotification {
+  /// 改为签名探测通知，`Wechatpay-Signature` 以 `WECHATPAY/SIGNTEST/` 开头
+  pub fn signtest(mut self) -> Self {
+    let signature = format!(
+      "{}{}",
+      SIGNTEST_PREFIX,
+      self.headers["Wechatpay-Signature"].to_str().unwrap()
+    );
+    self.headers.insert(
+      "Wechatpay-Signature",
+      HeaderValue::from_str(&signature).unwrap(),
+    );
+    self
+  }
+}
+
+/// 模拟服务器中的合单，子单保存在订单中
+#[derive(Debug, Clone)]
+pub struct MockCombineOrder {
+  pub combine_appid: String,
+  pub combine_mchid: String,
+  pub combine_out_trade_no: String,
+  pub prepay_id: String,
+  /// JSAPI、APP、MWEB、NATIVE
+  pub trade_type: String,
+  pub openid: Option<String>,
+  /// 子单商户订单号，可以通过 [MockServer::order] 查询
+  pub sub_orders: Vec<String>,
+}
+
+impl MockCombineOrder {
+  fn prepay_json(&self) -> Value {
+    prepay_json(&self.trade_type, &self.prepay_id)
+  }
+
+  fn to_json(&self, orders: &HashMap<String, MockOrder>) -> Value {
+    let sub_orders = self
+      .sub_orders
+      .iter()
+      .filter_map(|out_trade_no| orders.get(out_trade_no))
+      .map(|order| {
+        let paid = order.transaction_id.is_some();
+        json!({
+          "mchid": order.mchid,
+          "trade_type": order.trade_type,
+          "trade_state": order.trade_state,
+          "bank_type": if paid { Some("OTHERS") } else { None },
+          "attach": order.attach,
+          "success_time": order.success_time,
+          "transaction_id": order.transaction_id,
+          "out_trade_no": order.out_trade_no,
+          "amount": {
+            "total_amount": order.total,
+            "payer_amount": if paid { Some(order.total) } else { None },
+            "currency": "CNY",
+            "payer_currency": "CNY",
+          },
+        })
+      })
+      .collect::<Vec<_>>();
+    json!({
+      "combine_appid": self.combine_appid,
+      "combine_mchid": self.combine_mchid,
+      "combine_out_trade_no": self.combine_out_trade_no,
+      "sub_orders": sub_orders,
+      "combine_payer_info": self.openid.as_ref().map(|openid| json!({ "openid": openid })),
+    })
+  }
+}
@@ -0,0 +1,670 @@
+//! 模拟服务器的 HTTP 处理
+use super::{InjectedError, MockCombineOrder, MockOrder, MockRefund, RecordedRequest};
+use crate::client::matches_template;
+use crate::signer::RsaSigner;
+use aes_gcm::aead::{Aead, KeyInit, Payload};
+use aes_gcm::{Aes256Gcm, Nonce};
+use base64::{engine::general_purpose, Engine};
+use chrono::{FixedOffset, SecondsFormat, Utc};
+use http_body_util::{BodyExt, Full};
+use hyper::body::{Bytes, Incoming};
+use hyper::server::conn::http1;
+use hyper::service::service_fn;
+use hyper::{Method, Request, Response};
+use hyper_util::rt::TokioIo;
+use rand::{distributions::Alphanumeric, thread_rng, Rng};
+use rsa::sha2::{Digest, Sha256};
+use rsa::{Pkcs1v15Sign, RsaPrivateKey, RsaPublicKey};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::convert::Infallible;
+use std::sync::{Arc, Mutex};
+use tokio::net::TcpListener;
+
+/// 模拟服务器的配置和状态
+pub(crate) struct Shared {
+  pub merchant_id: String,
+  pub merchant_serial_no: String,
+  pub merchant_public_key: RsaPublicKey,
+  pub api_key: String,
+  /// 用于解密请求中的敏感信息
+  pub platform_private_key: RsaPrivateKey,
+  pub platform_signer: RsaSigner,
+  pub platform_serial_no: String,
+  pub platform_certificate_pem: String,
+  pub state: Mutex<State>,
+}
+
+#[derive(Default)]
+pub(crate) struct State {
+  pub orders: HashMap<String, MockOrder>,
+  /// 合单商户订单号 -> 合单
+  pub combines: HashMap<String, MockCombineOrder>,
+  pub refunds: HashMap<String, MockRefund>,
+  /// 商户批次单号 -> 微信批次单号
+  pub transfers: HashMap<String, (String, String)>,
+  /// 业务申请编号 -> 申请单号
+  pub applyments: HashMap<String, String>,
+  pub errors: Vec<(Method, String, InjectedError)>,
+  pub requests: Vec<RecordedRequest>,
+  sequence: u64,
+}
+
+impl State {
+  pub fn next_id(&mut self) -> u64 {
+    self.sequence += 1;
+    self.sequence
+  }
+
+  /// 取出匹配的注入错误，次数用完后移除
+  fn take_error(&mut self, method: &Method, path: &str) -> Option<InjectedError> {
+    let index = self
+      .errors
+      .iter()
+      .position(|(m, template, _)| m == method && matches_template(template, path))?;
+    let error = self.errors[index].2.clone();
+    match &mut self.errors[index].2.times {
+      Some(1) => {
+        self.errors.remove(index);
+      }
+      Some(times) => *times -= 1,
+      None => {}
+    }
+    Some(error)
+  }
+}
+
+/// 处理结果，`body` 为空时返回空的消息体
+struct Reply {
+  status: u16,
+  body: Option<Value>,
+}
+
+impl Reply {
+  fn ok(body: Value) -> Self {
+    Self {
+      status: 200,
+      body: Some(body),
+    }
+  }
+
+  fn no_content() -> Self {
+    Self {
+      status: 204,
+      body: None,
+    }
+  }
+
+  fn error(status: u16, code: &str, message: &str) -> Self {
+    Self {
+      status,
+      body: match status {
+        202 | 204 => None,
+        _ => Some(json!({ "code": code, "message": message })),
+      },
+    }
+  }
+}
+
+pub(crate) async fn serve(listener: TcpListener, shared: Arc<Shared>) {
+  loop {
+    let Ok((stream, _)) = listener.accept().await else {
+      continue;
+    };
+    let shared = shared.clone();
+    tokio::spawn(async move {
+      let service = service_fn(move |req| {
+        let shared = shared.clone();
+        async move { Ok::<_, Infallible>(shared.handle(req).await) }
+      });
+      let _ = http1::Builder::new()
+        .serve_connection(TokioIo::new(stream), service)
+        .await;
+    });
+  }
+}
+
+impl Shared {
+  async fn handle(&self, req: Request<Incoming>) -> Response<Full<Bytes>> {
+    let (parts, body) = req.into_parts();
+    let body = body
+      .collect()
+      .await
+      .map(|body| body.to_bytes())
+      .unwrap_or_default();
+    let reply = self.dispatch(&parts, &body);
+    self.respond(reply)
+  }
+
+  fn dispatch(&self, parts: &hyper::http::request::Parts, body: &[u8]) -> Reply {
+    let path = parts.uri.path();
+    let path_and_query = parts
+      .uri
+      .path_and_query()
+      .map(|pq| pq.as_str())
+      .unwrap_or(path);
+    let is_multipart = parts
+      .headers
+      .get_all(hyper::header::CONTENT_TYPE)
+      .iter()
+      .any(|v| v.as_bytes().starts_with(b"multipart/form-data"));
+    // 上传接口只对 meta 签名
+    let signed_body = if is_multipart {
+      multipart_meta(body).unwrap_or_default()
+    } else {
+      String::from_utf8_lossy(body).into_owned()
+    };
+
+    let mut state = self.state.lock().unwrap();
+    state.requests.push(RecordedRequest {
+      method: parts.method.to_string(),
+      path: path_and_query.to_string(),
+      body: signed_body.clone(),
+      headers: parts.headers.clone(),
+    });
+    let authorization = parts
+      .headers
+      .get(hyper::header::AUTHORIZATION)
+      .and_then(|v| v.to_str().ok())
+      .unwrap_or_default();
+    if let Err(message) =
+      self.verify_authorization(&parts.method, path_and_query, &signed_body, authorization)
+    {
+      return Reply::error(401, "SIGN_ERROR", &message);
+    }
+    if let Some(error) = state.take_error(&parts.method, path) {
+      return Reply::error(error.status, &error.code, &error.message);
+    }
+    let request = match signed_body.as_str() {
+      "" => Value::Null,
+      text if is_multipart => serde_json::from_str(text).unwrap_or(Value::Null),
+      text => match serde_json::from_str(text) {
+        Ok(value) => value,
+        Err(_) => return Reply::error(400, "PARAM_ERROR", "请求体不是合法的 JSON"),
+      },
+    };
+
+    let segments = path.trim_start_matches('/').split('/').collect::<Vec<_>>();
+    match (&parts.method, segments.as_slice()) {
+      (&Method::GET, ["v3", "certificates"]) => self.certificates(),
+      (
+        &Method::POST,
+        ["v3", "pay", "transactions", kind @ ("jsapi" | "app" | "h5" | "native")],
+      ) => self.create_order(&mut state, kind, &request),
+      (&Method::GET, ["v3", "pay", "transactions", "out-trade-no", out_trade_no]) => state
+        .orders
+        .get(*out_trade_no)
+        .map(|order| Reply::ok(order.to_json()))
+        .unwrap_or_else(order_not_exist),
+      (&Method::GET, ["v3", "pay", "transactions", "id", transaction_id]) => state
+        .orders
+        .values()
+        .find(|order| order.transaction_id.as_deref() == Some(*transaction_id))
+        .map(|order| Reply::ok(order.to_json()))
+        .unwrap_or_else(order_not_exist),
+      (&Method::POST, ["v3", "pay", "transactions", "out-trade-no", out_trade_no, "close"]) => {
+        close_order(&mut state, out_trade_no)
+      }
+      (
+        &Method::POST,
+        ["v3", "combine-transactions", kind @ ("jsapi" | "app" | "h5" | "native")],
+      ) => self.create_combine_order(&mut state, kind, &request),
+      (&Method::GET, ["v3", "combine-transactions", "out-trade-no", combine_out_trade_no]) => state
+        .combines
+        .get(*combine_out_trade_no)
+        .map(|combine| Reply::ok(combine.to_json(&state.orders)))
+        .unwrap_or_else(order_not_exist),
+      (
+        &Method::POST,
+        ["v3", "combine-transactions", "out-trade-no", combine_out_trade_no, "close"],
+      ) => close_combine_order(&mut state, combine_out_trade_no),
+      (&Method::POST, ["v3", "refund", "domestic", "refunds"]) => {
+        create_refund(&mut state, &request)
+      }
+      (&Method::GET, ["v3", "refund", "domestic", "refunds", out_refund_no]) => state
+        .refunds
+        .get(*out_refund_no)
+        .map(|refund| Reply::ok(refund.to_json()))
+        .unwrap_or_else(|| Reply::error(404, "RESOURCE_NOT_EXISTS", "退款单不存在")),
+      (&Method::POST, ["v3", "transfer", "batches"]) => create_transfer(&mut state, &request),
+      (&Method::POST, ["v3", "merchant", "media", "upload"]) => {
+        if request.get("sha256").and_then(Value::as_str).is_none() {
+          return Reply::error(400, "PARAM_ERROR", "缺少 meta");
+        }
+        let id = state.next_id();
+        Reply::ok(json!({ "media_id": format!("mock-media-{}", id) }))
+      }
+      (&Method::POST, ["v3", "ecommerce", "applyments"]) => create_applyment(&mut state, &request),
+      _ => Reply::error(404, "RESOURCE_NOT_EXISTS", "接口不存在"),
+    }
+  }
+
+  /// 验证商户请求签名
+  fn verify_authorization(
+    &self,
+    method: &Method,
+    path: &str,
+    body: &str,
+    authorization: &str,
+  ) -> Result<(), String> {
+    let params = authorization
+      .strip_prefix("WECHATPAY2-SHA256-RSA2048 ")
+      .ok_or("Authorization 格式错误")?
+      .split(',')
+      .filter_map(|pair| pair.split_once('='))
+      .map(|(key, value)| (key.trim(), value.trim_matches('"')))
+      .collect::<HashMap<_, _>>();
+    let param = |key: &str| params.get(key).copied().ok_or(format!("缺少 {}", key));
+    if param("mchid")? != self.merchant_id {
+      return Err("商户号不匹配".to_string());
+    }
+    if param("serial_no")? != self.merchant_serial_no {
+      return Err("商户证书序列号不匹配".to_string());
+    }
+    let message = format!(
+      "{}\n{}\n{}\n{}\n{}\n",
+      method.as_str(),
+      path,
+      param("timestamp")?,
+      param("nonce_str")?,
+      body
+    );
+    let signature = general_purpose::STANDARD
+      .decode(param("signature")?)
+      .map_err(|_| "签名不是合法的 Base64".to_string())?;
+    self
+      .merchant_public_key
+      .verify(
+        Pkcs1v15Sign::new::<Sha256>(),
+        &Sha256::digest(message.as_bytes()),
+        &signature,
+      )
+      .map_err(|_| "签名错误".to_string())
+  }
+
+  /// 使用平台私钥为应答签名
+  fn respond(&self, reply: Reply) -> Response<Full<Bytes>> {
+    let body = reply.body.map(|body| body.to_string()).unwrap_or_default();
+    let (timestamp, nonce, signature) = self.sign(&body);
+    let mut builder = Response::builder()
+      .status(reply.status)
+      .header("Request-ID", format!("mock-{}", random_string(16)))
+      .header("Wechatpay-Timestamp", timestamp)
+      .header("Wechatpay-Nonce", nonce)
+      .header("Wechatpay-Serial", &self.platform_serial_no)
+      .header("Wechatpay-Signature", signature);
+    if !body.is_empty() {
+      builder = builder.header(hyper::header::CONTENT_TYPE, "application/json");
+    }
+    builder.body(Full::new(Bytes::from(body))).unwrap()
+  }
+
+  /// 使用平台私钥为应答或回调报文签名，返回时间戳、随机串和 Base64 编码的签名
+  pub fn sign(&self, body: &str) -> (String, String, String) {
+    let timestamp = Utc::now().timestamp().to_string();
+    let nonce = random_string(32);
+    let message = format!("{}\n{}\n{}\n", timestamp, nonce, body);
+    let signature = self
+      .platform_signer
+      .sign_sync(message.as_bytes())
+      .map(|signature| general_purpose::STANDARD.encode(signature))
+      .unwrap_or_default();
+    (timestamp, nonce, signature)
+  }
+
+  /// 使用 APIv3 密钥加密，返回随机串和 Base64 编码的密文
+  pub fn encrypt(&self, plaintext: &[u8], associated_data: &str) -> (String, String) {
+    let nonce = random_string(12);
+    let cipher = Aes256Gcm::new_from_slice(self.api_key.as_bytes()).unwrap();
+    let ciphertext = cipher
+      .encrypt(
+        Nonce::from_slice(nonce.as_bytes()),
+        Payload {
+          msg: plaintext,
+          aad: associated_data.as_bytes(),
+        },
+      )
+      .unwrap();
+    (nonce, general_purpose::STANDARD.encode(ciphertext))
+  }
+
+  fn certificates(&self) -> Reply {
+    let associated_data = "certificate";
+    let (nonce, ciphertext) =
+      self.encrypt(self.platform_certificate_pem.as_bytes(), associated_data);
+    let now = Utc::now();
+    Reply::ok(json!({
+      "data": [{
+        "serial_no": self.platform_serial_no,
+        "effective_time": rfc3339(now - chrono::Duration::days(1)),
+        "expire_time": rfc3339(now + chrono::Duration::days(365)),
+        "encrypt_certificate": {
+          "algorithm": "AEAD_AES_256_GCM",
+          "nonce": nonce,
+          "associated_data": associated_data,
+          "ciphertext": ciphertext,
+        }
+      }]
+    }))
+  }
+
+  fn create_order(&self, state: &mut State, kind: &str, request: &Value) -> Reply {
+    let Some(out_trade_no) = str_field(request, "/out_trade_no") else {
+      return param_error("缺少 out_trade_no");
+    };
+    let Some(total) = request.pointer("/amount/total").and_then(Value::as_i64) else {
+      return param_error("缺少 amount.total");
+    };
+    if total <= 0 {
+      return param_error("amount.total 必须大于 0");
+    }
+    if str_field(request, "/mchid") != Some(self.merchant_id.as_str()) {
+      return Reply::error(400, "MCH_NOT_EXISTS", "商户号不存在");
+    }
+    let trade_type = trade_type(kind);
+    if let Some(order) = state.orders.get(out_trade_no) {
+      return match order.trade_state.as_str() {
+        "SUCCESS" | "REFUND" => Reply::error(400, "ORDERPAID", "该订单已支付"),
+        "CLOSED" => Reply::error(400, "ORDER_CLOSED", "订单已关闭"),
+        _ if order.trade_type != trade_type || order.total != total as i32 => {
+          Reply::error(400, "OUT_TRADE_NO_USED", "商户订单号重复")
+        }
+        _ => Reply::ok(order.prepay_json()),
+      };
+    }
+    let id = state.next_id();
+    let order = MockOrder {
+      appid: str_field(request, "/appid").unwrap_or_default().to_string(),
+      mchid: self.merchant_id.clone(),
+      out_trade_no: out_trade_no.to_string(),
+      transaction_id: None,
+      prepay_id: format!("wx{}{:012}", Utc::now().format("%d%H%M%S"), id),
+      trade_type: trade_type.to_string(),
+      trade_state: "NOTPAY".to_string(),
+      description: str_field(request, "/description")
+        .unwrap_or_default()
+        .to_string(),
+      attach: str_field(request, "/attach").map(str::to_string),
+      total: total as i32,
+      refunded: 0,
+      openid: str_field(request, "/payer/openid").map(str::to_string),
+      success_time: None,
+    };
+    let reply = Reply::ok(order.prepay_json());
+    state.orders.insert(order.out_trade_no.clone(), order);
+    reply
+  }
+
+  fn create_combine_order(&self, state: &mut State, kind: &str, request: &Value) -> Reply {
+    let Some(combine_out_trade_no) = str_field(request, "/combine_out_trade_no") else {
+      return param_error("缺少 combine_out_trade_no");
+    };
+    if str_field(request, "/combine_mchid") != Some(self.merchant_id.as_str()) {
+      return Reply::error(400, "MCH_NOT_EXISTS", "商户号不存在");
+    }
+    let trade_type = trade_type(kind);
+    if let Some(combine) = state.combines.get(combine_out_trade_no) {
+      return if combine.trade_type != trade_type {
+        Reply::error(400, "OUT_TRADE_NO_USED", "商户订单号重复")
+      } else {
+        Reply::ok(combine.prepay_json())
+      };
+    }
+    let openid = str_field(request, "/combine_payer_info/openid").map(str::to_string);
+    if trade_type == "JSAPI" && openid.is_none() {
+      return param_error("缺少 combine_payer_info.openid");
+    }
+    let sub_orders = match request.get("sub_orders").and_then(Value::as_array) {
+      Some(sub_orders) if !sub_orders.is_empty() && sub_orders.len() <= 50 => sub_orders,
+      _ => return param_error("sub_orders 需要包含 1 至 50 笔子单"),
+    };
+    let mut out_trade_nos = Vec::new();
+    for sub_order in sub_orders {
+      let Some(out_trade_no) = str_field(sub_order, "/out_trade_no") else {
+        return param_error("缺少 sub_orders.out_trade_no");
+      };
+      let total = sub_order
+        .pointer("/amount/total_amount")
+        .and_then(Value::as_i64);
+      if total.is_none_or(|total| total <= 0) {
+        return param_error("sub_orders.amount.total_amount 必须大于 0");
+      }
+      if state.orders.contains_key(out_trade_no) || out_trade_nos.contains(&out_trade_no) {
+        return Reply::error(400, "OUT_TRADE_NO_USED", "商户订单号重复");
+      }
+      out_trade_nos.push(out_trade_no);
+    }
+    let id = state.next_id();
+    let prepay_id = format!("wx{}{:012}", Utc::now().format("%d%H%M%S"), id);
+    let combine_appid = str_field(request, "/combine_appid").unwrap_or_default();
+    for sub_order in sub_orders {
+      let order = MockOrder {
+        appid: combine_appid.to_string(),
+        mchid: str_field(sub_order, "/mchid")
+          .unwrap_or_default()
+          .to_string(),
+        out_trade_no: str_field(sub_order, "/out_trade_no")
+          .unwrap_or_default()
+          .to_string(),
+        transaction_id: None,
+        prepay_id: prepay_id.clone(),
+        trade_type: trade_type.to_string(),
+        trade_state: "NOTPAY".to_string(),
+        description: str_field(sub_order, "/description")
+          .unwrap_or_default()
+          .to_string(),
+        attach: str_field(sub_order, "/attach").map(str::to_string),
+        total: sub_order
+          .pointer("/amount/total_amount")
+          .and_then(Value::as_i64)
+          .unwrap_or_default() as i32,
+        refunded: 0,
+        openid: openid.clone(),
+        success_time: None,
+      };
+      state.orders.insert(order.out_trade_no.clone(), order);
+    }
+    let combine = MockCombineOrder {
+      combine_appid: combine_appid.to_string(),
+      combine_mchid: self.merchant_id.clone(),
+      combine_out_trade_no: combine_out_trade_no.to_string(),
+      prepay_id,
+      trade_type: trade_type.to_string(),
+      openid,
+      sub_orders: out_trade_nos.into_iter().map(str::to_string).collect(),
+    };
+    let reply = Reply::ok(combine.prepay_json());
+    state
+      .combines
+      .insert(combine.combine_out_trade_no.clone(), combine);
+    reply
+  }
+}
+
+fn trade_type(kind: &str) -> &'static str {
+  match kind {
+    "jsapi" => "JSAPI",
+    "app" => "APP",
+    "native" => "NATIVE",
+    _ => "MWEB",
+  }
+}
+
+fn close_order(state: &mut State, out_trade_no: &str) -> Reply {
+  let Some(order) = state.orders.get_mut(out_trade_no) else {
+    return order_not_exist();
+  };
+  match order.trade_state.as_str() {
+    "SUCCESS" | "REFUND" => Reply::error(400, "ORDERPAID", "该订单已支付"),
+    _ => {
+      order.trade_state = "CLOSED".to_string();
+      Reply::no_content()
+    }
+  }
+}
+
+fn close_combine_order(state: &mut State, combine_out_trade_no: &str) -> Reply {
+  let Some(combine) = state.combines.get(combine_out_trade_no) else {
+    return order_not_exist();
+  };
+  let sub_orders = combine.sub_orders.clone();
+  let paid = sub_orders.iter().any(|out_trade_no| {
+    state
+      .orders
+      .get(out_trade_no)
+      .is_some_and(|order| order.transaction_id.is_some())
+  });
+  if paid {
+    return Reply::error(400, "ORDERPAID", "该订单已支付");
+  }
+  for out_trade_no in sub_orders {
+    if let Some(order) = state.orders.get_mut(&out_trade_no) {
+      order.trade_state = "CLOSED".to_string();
+    }
+  }
+  Reply::no_content()
+}
+
+fn create_refund(state: &mut State, request: &Value) -> Reply {
+  let Some(out_refund_no) = str_field(request, "/out_refund_no") else {
+    return param_error("缺少 out_refund_no");
+  };
+  if let Some(refund) = state.refunds.get(out_refund_no) {
+    return Reply::ok(refund.to_json());
+  }
+  let (Some(refund), Some(total)) = (
+    request.pointer("/amount/refund").and_then(Value::as_i64),
+    request.pointer("/amount/total").and_then(Value::as_i64),
+  ) else {
+    return param_error("缺少 amount.refund 或 amount.total");
+  };
+  let out_trade_no = str_field(request, "/out_trade_no");
+  let transaction_id = str_field(request, "/transaction_id");
+  let Some(order) = state.orders.values_mut().find(|order| {
+    Some(order.out_trade_no.as_str()) == out_trade_no
+      || (transaction_id.is_some() && order.transaction_id.as_deref() == transaction_id)
+  }) else {
+    return Reply::error(404, "RESOURCE_NOT_EXISTS", "订单不存在");
+  };
+  if !matches!(order.trade_state.as_str(), "SUCCESS" | "REFUND") {
+    return Reply::error(403, "INVALID_REQUEST", "订单未支付");
+  }
+  if total as i32 != order.total {
+    return param_error("amount.total 与订单金额不一致");
+  }
+  if refund <= 0 || order.refunded + refund as i32 > order.total {
+    return Reply::error(403, "NOT_ENOUGH", "可退款金额不足");
+  }
+  order.refunded += refund as i32;
+  order.trade_state = "REFUND".to_string();
+  let (out_trade_no, transaction_id, total) = (
+    order.out_trade_no.clone(),
+    order.transaction_id.clone().unwrap_or_default(),
+    order.total,
+  );
+  let id = state.next_id();
+  let refund = MockRefund {
+    out_refund_no: out_refund_no.to_string(),
+    refund_id: format!("5030{:024}", id),
+    out_trade_no,
+    transaction_id,
+    refund: refund as i32,
+    total,
+    status: "PROCESSING".to_string(),
+    create_time: rfc3339(Utc::now()),
+    success_time: None,
+  };
+  let reply = Reply::ok(refund.to_json());
+  state.refunds.insert(refund.out_refund_no.clone(), refund);
+  reply
+}
+
+fn create_transfer(state: &mut State, request: &Value) -> Reply {
+  let Some(out_batch_no) = str_field(request, "/out_batch_no") else {
+    return param_error("缺少 out_batch_no");
+  };
+  let details = request
+    .get("transfer_detail_list")
+    .and_then(Value::as_array)
+    .cloned()
+    .unwrap_or_default();
+  let amount = details
+    .iter()
+    .filter_map(|detail| detail.get("transfer_amount").and_then(Value::as_i64))
+    .sum::<i64>();
+  if request.get("total_num").and_then(Value::as_i64) != Some(details.len() as i64)
+    || request.get("total_amount").and_then(Value::as_i64) != Some(amount)
+  {
+    return param_error("total_num 或 total_amount 与明细不一致");
+  }
+  let (batch_id, create_time) = match state.transfers.get(out_batch_no) {
+    Some(batch) => batch.clone(),
+    None => {
+      let batch = (format!("1030{:024}", state.next_id()), rfc3339(Utc::now()));
+      state
+        .transfers
+        .insert(out_batch_no.to_string(), batch.clone());
+      batch
+    }
+  };
+  Reply::ok(json!({
+    "out_batch_no": out_batch_no,
+    "batch_id": batch_id,
+    "create_time": create_time,
+  }))
+}
+
+fn create_applyment(state: &mut State, request: &Value) -> Reply {
+  let Some(out_request_no) = str_field(request, "/out_request_no") else {
+    return param_error("缺少 out_request_no");
+  };
+  let applyment_id = match state.applyments.get(out_request_no) {
+    Some(applyment_id) => applyment_id.clone(),
+    None => {
+      let applyment_id = format!("{}", 2_000_000_000 + state.next_id());
+      state
+        .applyments
+        .insert(out_request_no.to_string(), applyment_id.clone());
+      applyment_id
+    }
+  };
+  Reply::ok(json!({
+    "applyment_id": applyment_id,
+    "out_request_no": out_request_no,
+  }))
+}
+
+/// 取出 multipart 请求体中 `meta` 部分的内容
+fn multipart_meta(body: &[u8]) -> Option<String> {
+  let body = String::from_utf8_lossy(body);
+  let part = &body[body.find("name=\"meta\"")?..];
+  let content = &part[part.find("\r\n\r\n")? + 4..];
+  Some(content[..content.find("\r\n--")?].to_string())
+}
+
+fn str_field<'a>(value: &'a Value, pointer: &str) -> Option<&'a str> {
+  value.pointer(pointer).and_then(Value::as_str)
+}
+
+fn param_error(message: &str) -> Reply {
+  Reply::error(400, "PARAM_ERROR", message)
+}
+
+fn order_not_exist() -> Reply {
+  Reply::error(404, "ORDER_NOT_EXIST", "订单不存在")
+}
+
+fn random_string(len: usize) -> String {
+  thread_rng()
+    .sample_iter(Alphanumeric)
+    .take(len)
+    .map(char::from)
+    .collect()
+}
+
+/// 北京时间的 RFC 3339 时间
+pub(crate) fn rfc3339(time: chrono::DateTime<Utc>) -> String {
+  time
+    .with_timezone(&FixedOffset::east_opt(8 * 3600).unwrap())
+    .to_rfc3339_opts(SecondsFormat::Secs, true)
+}
@@ -0,0 +1,96 @@
+//! # actix-web 集成
+//! 启用 `actix-web` feature 后可用。[Notification] 实现了 [FromRequest]，从 `web::Data<Client>` 中取出客户端，
+//! 读取原始报文并验证、解密；失败时直接以 [WebhookAck] 应答。处理函数返回 [WebhookAck] 即可生成应答。
+//!
+//! # Example
+//! ```no_run
+//! use actix_web::{web, App};
+//! use wechat_pay_sdk::webhook::handler::{Notification, WebhookAck};
+//! use wechat_pay_sdk::webhook::transaction::TransactionSuccess;
+//! use wechat_pay_sdk::Client;
+//!
+//! async fn notify(notification: Notification<TransactionSuccess>) -> WebhookAck {
+//!   let result: Result<(), String> = {
+//!     println!("{} paid", notification.resource.out_trade_no);
+//!     Ok(())
+//!   };
+//!   result.into()
+//! }
+//!
+//! fn configure(client: web::Data<Client>) -> impl FnOnce(&mut web::ServiceConfig) {
+//!   move |cfg| {
+//!     cfg.app_data(client).route("/notify", web::post().to(notify));
+//!   }
+//! }
+//! ```
+use super::handler::{Notification, WebhookAck};
+use crate::Client;
+use actix_web::body::BoxBody;
+use actix_web::dev::Payload;
+use actix_web::http::StatusCode;
+use actix_web::{web, FromRequest, HttpRequest, HttpResponse, Responder, ResponseError};
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
+use serde::de::DeserializeOwned;
+use std::future::Future;
+use std::pin::Pin;
+
+impl<Message> FromRequest for Notification<Message>
+where
+  Message: DeserializeOwned + 'static,
+{
+  type Error = WebhookAck;
+  type Future = Pin<Box<dyn Future<Output = Result<Self, Self::Error>>>>;
+
+  fn from_request(req: &HttpRequest, payload: &mut Payload) -> Self::Future {
+    let client = req.app_data::<web::Data<Client>>().cloned();
+    let headers = convert_headers(req);
+    let body = web::Bytes::from_request(req, payload);
+    Box::pin(async move {
+      let Some(client) = client else {
+        tracing::error!("web::Data<Client> is not registered");
+        return Err(WebhookAck::fail("系统错误"));
+      };
+      let body = body
+        .await
+        .map_err(|_| WebhookAck::with_status(reqwest::StatusCode::BAD_REQUEST, "读取报文失败"))?;
+      client
+        .notification(&headers, &body)
+        .await
+        .map_err(|err| WebhookAck::from_error(&err))
+    })
+  }
+}
+
+/// actix-web 使用的 `http` 版本与 reqwest 不同，需要逐个复制
+fn convert_headers(req: &HttpRequest) -> HeaderMap {
+  req
+    .headers()
+    .iter()
+    .filter_map(|(name, value)| {
+      Some((
+        HeaderName::from_bytes(name.as_str().as_bytes()).ok()?,
+        HeaderValue::from_bytes(value.as_bytes()).ok()?,
+      ))
+    })
+    .collect()
+}
+
+impl ResponseError for WebhookAck {
+  fn status_code(&self) -> StatusCode {
+    StatusCode::from_u16(self.status.as_u16()).unwrap_or(StatusCode::INTERNAL_SERVER_ERROR)
+  }
+
+  fn error_response(&self) -> HttpResponse {
+    HttpResponse::build(self.status_code())
+      .content_type("application/json")
+      .body(self.to_json())
+  }
+}
+
+impl Responder for WebhookAck {
+  type Body = BoxBody;
+
+  fn respond_to(self, _req: &HttpRequest) -> HttpResponse<Self::Body> {
+    self.error_response()
+  }
+}
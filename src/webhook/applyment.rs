@@ -0,0 +1,33 @@
+//! # 进件状态通知
+//!
+//! [二级商户进件](crate::sdk::partner::ecommerce::application)的申请状态变化时，微信会通知服务商，通知类型以 `APPLYMENT_STATE.` 开头。
+//! 通知只包含申请单号和状态，驳回原因等详情需要调用查询申请状态接口获取。
+use serde::Deserialize;
+
+#[derive(Deserialize, Debug)]
+pub struct ApplymentStateNotification {
+  /// 微信支付申请单号
+  ///
+  /// 示例值：2000002124775691
+  pub applyment_id: u64,
+  /// 业务申请编号，即进件时的 `out_request_no`
+  ///
+  /// 示例值：APPLYMENT_00000000001
+  pub out_request_no: Option<String>,
+  /// 申请状态
+  /// - CHECKING：资料校验中
+  /// - ACCOUNT_NEED_VERIFY：待账户验证
+  /// - AUDITING：审核中
+  /// - REJECTED：已驳回
+  /// - NEED_SIGN：待签约
+  /// - FINISH：完成
+  /// - FROZEN：已冻结
+  /// - CANCELED：已作废
+  ///
+  /// 示例值：FINISH
+  pub applyment_state: String,
+  /// 电商平台二级商户号，进件完成后返回
+  ///
+  /// 示例值：1542488531
+  pub sub_mchid: Option<String>,
+}
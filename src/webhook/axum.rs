@@ -0,0 +1,67 @@
+//! # axum 集成
+//! 启用 `axum` feature 后可用。[Notification] 实现了 [FromRequest]，从应用状态中取出 `Arc<Client>`，
+//! 读取原始报文并验证、解密；失败时直接以 [WebhookAck] 应答。处理函数返回 [WebhookAck] 即可生成应答。
+//!
+//! # Example
+//! ```no_run
+//! use axum::{routing::post, Router};
+//! use std::sync::Arc;
+//! use wechat_pay_sdk::webhook::handler::{Notification, WebhookAck};
+//! use wechat_pay_sdk::webhook::transaction::TransactionSuccess;
+//! use wechat_pay_sdk::Client;
+//!
+//! async fn notify(notification: Notification<TransactionSuccess>) -> WebhookAck {
+//!   let result: Result<(), String> = {
+//!     println!("{} paid", notification.resource.out_trade_no);
+//!     Ok(())
+//!   };
+//!   result.into()
+//! }
+//!
+//! fn router(client: Client) -> Router {
+//!   Router::new()
+//!     .route("/notify", post(notify))
+//!     .with_state(Arc::new(client))
+//! }
+//! ```
+use super::handler::{Notification, WebhookAck};
+use crate::Client;
+use ::axum::body::Bytes;
+use ::axum::extract::{FromRef, FromRequest, Request};
+use ::axum::http::header::CONTENT_TYPE;
+use ::axum::response::{IntoResponse, Response};
+use reqwest::StatusCode;
+use serde::de::DeserializeOwned;
+use std::sync::Arc;
+
+impl<S, Message> FromRequest<S> for Notification<Message>
+where
+  Arc<Client>: FromRef<S>,
+  S: Send + Sync,
+  Message: DeserializeOwned,
+{
+  type Rejection = WebhookAck;
+
+  async fn from_request(req: Request, state: &S) -> Result<Self, Self::Rejection> {
+    let client = Arc::<Client>::from_ref(state);
+    let headers = req.headers().clone();
+    let body = Bytes::from_request(req, state)
+      .await
+      .map_err(|_| WebhookAck::with_status(StatusCode::BAD_REQUEST, "读取报文失败"))?;
+    client
+      .notification(&headers, &body)
+      .await
+      .map_err(|err| WebhookAck::from_error(&err))
+  }
+}
+
+impl IntoResponse for WebhookAck {
+  fn into_response(self) -> Response {
+    (
+      self.status,
+      [(CONTENT_TYPE, "application/json")],
+      self.to_json(),
+    )
+      .into_response()
+  }
+}
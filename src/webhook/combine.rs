@@ -0,0 +1,21 @@
+//! # [合单支付通知 API](https://pay.weixin.qq.com/wiki/doc/apiv3/apis/chapter5_1_13.shtml)
+//! 最新更新时间：2022.09.05
+//!
+//! 合单支付完成后，微信会把相关支付结果发送给合单发起方商户，通知类型同样为 `TRANSACTION.SUCCESS`，
+//! 通知数据包含 `combine_out_trade_no` 和全部子单，[WebhookEvent](super::event::WebhookEvent) 据此区分普通支付通知和合单支付通知。
+//!
+//! > **注意：**
+//! >
+//! > 同样的通知可能会多次发送给商户系统。商户系统必须能够正确处理重复的通知。
+//! >
+//! > **特别提醒：** 商户系统对于合单支付结果通知的内容一定要做签名验证，并校验通知的信息是否与商户侧的信息一致，防止数据泄露导致出现“假通知”，造成资金损失。
+
+/// ## 接口说明
+/// 适用对象：`直连商户`
+///
+/// 请求方式：POST
+///
+/// 回调 URL：该链接是通过[合单下单](crate::sdk::basic::combine::order)中的请求参数 `notify_url` 来设置的，要求必须为 https 地址。
+///
+/// 通知数据与[合单查询订单](crate::sdk::basic::combine::query)的应答相同
+pub type CombineTransactionSuccess = crate::sdk::basic::combine::query::CombineTransaction;
@@ -0,0 +1,28 @@
+//! # [投诉通知 API](https://pay.weixin.qq.com/wiki/doc/apiv3/apis/chapter10_2_16.shtml)
+//!
+//! 用户发起投诉或投诉状态变化时，微信会通知商户在投诉通知回调 API 中设置的地址，通知类型以 `COMPLAINT.` 开头。
+//! 通知只包含投诉单号和动作类型，详情需要调用查询投诉单详情接口获取。
+use serde::Deserialize;
+
+#[derive(Deserialize, Debug)]
+pub struct ComplaintNotification {
+  /// 投诉单号
+  ///
+  /// 示例值：200201820200101080076610000
+  pub complaint_id: String,
+  /// 动作类型
+  /// - CREATE_COMPLAINT：用户提交投诉
+  /// - CONTINUE_COMPLAINT：用户继续投诉
+  /// - USER_RESPONSE：用户新留言
+  /// - RESPONSE_BY_PLATFORM：平台新留言
+  /// - SELLER_REFUND：商户发起全额退款
+  /// - MERCHANT_RESPONSE：商户新回复
+  /// - MERCHANT_CONFIRM_COMPLETE：商户反馈处理完成
+  ///
+  /// 示例值：CREATE_COMPLAINT
+  pub action_type: String,
+  /// 商户订单号，部分动作类型返回
+  pub out_trade_no: Option<String>,
+  /// 投诉单关联的微信订单号，部分动作类型返回
+  pub transaction_id: Option<String>,
+}
@@ -0,0 +1,112 @@
+//! # 通知事件
+//! [WebhookEvent] 根据通知类型（`event_type`）和加密前的对象类型（`original_type`）选择通知数据的类型，
+//! 调用方无需事先知道应该用哪个结构体解析。未知的通知解析为 [WebhookEvent::Raw]。
+use super::applyment::ApplymentStateNotification;
+use super::combine::CombineTransactionSuccess;
+use super::complaint::ComplaintNotification;
+use super::handler::Notification;
+use super::profit_sharing::ProfitSharingNotification;
+use super::refund::RefundSuccess;
+use super::transaction::TransactionSuccess;
+use super::transfer::TransferBatchNotification;
+use super::WeChatWebhook;
+use crate::{Client, WeChatPayError};
+use reqwest::header::HeaderMap;
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+
+/// 支付成功
+pub const TRANSACTION_SUCCESS: &str = "TRANSACTION.SUCCESS";
+/// 退款成功
+pub const REFUND_SUCCESS: &str = "REFUND.SUCCESS";
+/// 退款异常
+pub const REFUND_ABNORMAL: &str = "REFUND.ABNORMAL";
+/// 退款关闭
+pub const REFUND_CLOSED: &str = "REFUND.CLOSED";
+/// 商家转账批次完成
+pub const TRANSFER_BATCH_FINISHED: &str = "MCHTRANSFER.BATCH.FINISHED";
+/// 商家转账批次关闭
+pub const TRANSFER_BATCH_CLOSED: &str = "MCHTRANSFER.BATCH.CLOSED";
+
+/// # 通知事件
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum WebhookEvent {
+  /// `TRANSACTION.SUCCESS`
+  TransactionSuccess(TransactionSuccess),
+  /// `TRANSACTION.SUCCESS`，通知数据包含 `combine_out_trade_no` 的合单支付通知
+  CombineTransactionSuccess(CombineTransactionSuccess),
+  /// `REFUND.SUCCESS`
+  RefundSuccess(RefundSuccess),
+  /// `REFUND.ABNORMAL`
+  RefundAbnormal(RefundSuccess),
+  /// `REFUND.CLOSED`
+  RefundClosed(RefundSuccess),
+  /// 分账动账，`original_type` 为 `profitsharing` 或通知类型以 `PROFITSHARING.` 开头
+  ProfitSharing(ProfitSharingNotification),
+  /// `MCHTRANSFER.BATCH.FINISHED`、`MCHTRANSFER.BATCH.CLOSED`
+  TransferBatch(TransferBatchNotification),
+  /// 通知类型以 `COMPLAINT.` 开头
+  Complaint(ComplaintNotification),
+  /// 通知类型以 `APPLYMENT_STATE.` 开头
+  ApplymentState(ApplymentStateNotification),
+  /// 未知的通知，保留解密后的原始数据
+  Raw(Value),
+}
+
+impl WebhookEvent {
+  /// 根据通知类型和对象类型解析解密后的通知数据
+  pub fn from_value(
+    event_type: &str,
+    original_type: &str,
+    value: Value,
+  ) -> Result<Self, WeChatPayError> {
+    fn parse<T: DeserializeOwned>(value: Value) -> Result<T, WeChatPayError> {
+      Ok(serde_json::from_value(value)?)
+    }
+    Ok(match (event_type, original_type) {
+      (_, "profitsharing") => Self::ProfitSharing(parse(value)?),
+      (event, _) if event.starts_with("PROFITSHARING.") => Self::ProfitSharing(parse(value)?),
+      (TRANSACTION_SUCCESS, _) if value.get("combine_out_trade_no").is_some() => {
+        Self::CombineTransactionSuccess(parse(value)?)
+      }
+      (TRANSACTION_SUCCESS, _) => Self::TransactionSuccess(parse(value)?),
+      (REFUND_SUCCESS, _) => Self::RefundSuccess(parse(value)?),
+      (REFUND_ABNORMAL, _) => Self::RefundAbnormal(parse(value)?),
+      (REFUND_CLOSED, _) => Self::RefundClosed(parse(value)?),
+      (TRANSFER_BATCH_FINISHED | TRANSFER_BATCH_CLOSED, _) => Self::TransferBatch(parse(value)?),
+      (event, _) if event.starts_with("COMPLAINT.") => Self::Complaint(parse(value)?),
+      (event, _) if event.starts_with("APPLYMENT_STATE.") => Self::ApplymentState(parse(value)?),
+      _ => Self::Raw(value),
+    })
+  }
+}
+
+impl WeChatWebhook {
+  /// 解密通知数据，并根据通知类型选择数据类型，见 [WebhookEvent]
+  pub fn parse_event(&self, cli: &Client) -> Result<WebhookEvent, WeChatPayError> {
+    let value = self.parse::<Value>(cli)?;
+    WebhookEvent::from_value(&self.event_type, &self.resource.original_type, value).inspect_err(
+      |_| {
+        tracing::warn!(
+          event_type = %self.event_type,
+          original_type = %self.resource.original_type,
+          "failed to deserialize webhook event"
+        )
+      },
+    )
+  }
+}
+
+impl Client {
+  /// 验证签名并解密通知，根据通知类型解析为 [WebhookEvent]
+  pub async fn webhook_event(
+    &self,
+    headers: &HeaderMap,
+    body: &[u8],
+  ) -> Result<Notification<WebhookEvent>, WeChatPayError> {
+    let webhook = self.verify_webhook(headers, body).await?;
+    let resource = webhook.parse_event(self)?;
+    Ok(Notification { webhook, resource })
+  }
+}
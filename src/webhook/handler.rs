@@ -0,0 +1,160 @@
+//! # 通知处理
+//! 把收到的 HTTP 请求转换为 [Notification]，并根据处理结果生成微信支付要求的[应答](WebhookAck)。
+//!
+//! 启用 `axum`、`actix-web` 或 `hyper` feature 后，可以使用对应模块中现成的提取器或处理函数；
+//! 其他框架可以直接调用 [Client::handle_webhook]。
+use super::WeChatWebhook;
+use crate::{Client, ErrorKind, WeChatPayError};
+use reqwest::header::HeaderMap;
+use reqwest::StatusCode;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::fmt::{Display, Formatter};
+use std::future::Future;
+
+/// 验证签名并解密后的回调通知
+#[derive(Debug)]
+pub struct Notification<Message> {
+  /// 通知报文
+  pub webhook: WeChatWebhook,
+  /// 解密后的通知数据，如 [TransactionSuccess](super::transaction::TransactionSuccess)
+  pub resource: Message,
+}
+
+/// 通知应答报文
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WebhookResponse {
+  /// `SUCCESS` 为接收成功，其他为失败
+  pub code: String,
+  /// 失败原因
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub message: Option<String>,
+}
+
+/// # 通知应答
+/// - 接收成功：200，`{"code":"SUCCESS"}`
+/// - 签名验证失败（包括签名探测通知）：401
+/// - 报文无法解密或解析：400
+/// - 处理失败：500，微信支付会按通知频率重新发送
+#[derive(Debug, Clone)]
+pub struct WebhookAck {
+  pub status: StatusCode,
+  pub body: WebhookResponse,
+}
+
+impl WebhookAck {
+  /// 接收成功
+  pub fn success() -> Self {
+    Self {
+      status: StatusCode::OK,
+      body: WebhookResponse {
+        code: "SUCCESS".to_string(),
+        message: None,
+      },
+    }
+  }
+  /// 处理失败，微信支付会重新发送通知
+  pub fn fail(message: impl Into<String>) -> Self {
+    Self::with_status(StatusCode::INTERNAL_SERVER_ERROR, message)
+  }
+  /// 指定状态码的失败应答
+  pub fn with_status(status: StatusCode, message: impl Into<String>) -> Self {
+    Self {
+      status,
+      body: WebhookResponse {
+        code: "FAIL".to_string(),
+        message: Some(message.into()),
+      },
+    }
+  }
+  /// 验证或解密通知失败时的应答，应答中不包含错误详情
+  pub fn from_error(err: &WeChatPayError) -> Self {
+    match err.kind() {
+      ErrorKind::Signature => Self::with_status(StatusCode::UNAUTHORIZED, "签名验证失败"),
+      ErrorKind::Crypto | ErrorKind::Deserialization => {
+        Self::with_status(StatusCode::BAD_REQUEST, "报文解析失败")
+      }
+      _ => Self::fail("系统错误"),
+    }
+  }
+  pub fn is_success(&self) -> bool {
+    self.status.is_success()
+  }
+  /// 应答报文 JSON
+  pub fn to_json(&self) -> String {
+    serde_json::to_string(&self.body).unwrap_or_default()
+  }
+}
+
+impl Display for WebhookAck {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    match &self.body.message {
+      Some(message) => write!(f, "{} {}: {}", self.status, self.body.code, message),
+      None => write!(f, "{} {}", self.status, self.body.code),
+    }
+  }
+}
+
+impl<E: Display> From<Result<(), E>> for WebhookAck {
+  /// `Ok` 为接收成功，`Err` 为处理失败
+  ///
+  /// 错误只记录在日志中，应答使用通用的失败原因，以免内部信息返回给调用方
+  fn from(result: Result<(), E>) -> Self {
+    match result {
+      Ok(()) => Self::success(),
+      Err(err) => {
+        tracing::warn!(error = %err, "failed to handle webhook");
+        Self::fail("系统错误")
+      }
+    }
+  }
+}
+
+impl Client {
+  /// # 处理回调通知
+  /// 验证签名并解密通知，交给 `handler` 处理，并根据结果生成应答
+  ///
+  /// # Example
+  /// ```no_run
+  /// use reqwest::header::HeaderMap;
+  /// use wechat_pay_sdk::webhook::handler::{Notification, WebhookAck};
+  /// use wechat_pay_sdk::webhook::transaction::TransactionSuccess;
+  /// use wechat_pay_sdk::Client;
+  ///
+  /// async fn notify(client: &Client, headers: &HeaderMap, body: &[u8]) -> WebhookAck {
+  ///   client
+  ///     .handle_webhook(headers, body, |notification: Notification<TransactionSuccess>| async move {
+  ///       println!("{} paid", notification.resource.out_trade_no);
+  ///       Ok::<_, std::io::Error>(())
+  ///     })
+  ///     .await
+  /// }
+  /// ```
+  pub async fn handle_webhook<Message, F, Fut, E>(
+    &self,
+    headers: &HeaderMap,
+    body: &[u8],
+    handler: F,
+  ) -> WebhookAck
+  where
+    Message: DeserializeOwned,
+    F: FnOnce(Notification<Message>) -> Fut,
+    Fut: Future<Output = Result<(), E>>,
+    E: Display,
+  {
+    match self.notification(headers, body).await {
+      Ok(notification) => handler(notification).await.into(),
+      Err(err) => WebhookAck::from_error(&err),
+    }
+  }
+
+  /// 验证签名并解密通知，见 [parse_webhook](Self::parse_webhook)
+  pub async fn notification<Message: DeserializeOwned>(
+    &self,
+    headers: &HeaderMap,
+    body: &[u8],
+  ) -> Result<Notification<Message>, WeChatPayError> {
+    let (webhook, resource) = self.parse_webhook(headers, body).await?;
+    Ok(Notification { webhook, resource })
+  }
+}
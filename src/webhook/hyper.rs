@@ -0,0 +1,74 @@
+//! # hyper 集成
+//! 启用 `hyper` feature 后可用。[handle] 读取原始报文，验证、解密后交给处理函数，并生成应答。
+//!
+//! 通知接口无需认证即可访问，报文超过 [MAX_BODY_SIZE] 时不做验证，直接应答 413。
+//!
+//! # Example
+//! ```no_run
+//! use http_body_util::Full;
+//! use hyper::body::{Bytes, Incoming};
+//! use hyper::{Request, Response};
+//! use std::sync::Arc;
+//! use wechat_pay_sdk::webhook::handler::Notification;
+//! use wechat_pay_sdk::webhook::transaction::TransactionSuccess;
+//! use wechat_pay_sdk::Client;
+//!
+//! async fn notify(client: Arc<Client>, req: Request<Incoming>) -> Response<Full<Bytes>> {
+//!   wechat_pay_sdk::webhook::hyper::handle(&client, req, |notification: Notification<TransactionSuccess>| async move {
+//!     println!("{} paid", notification.resource.out_trade_no);
+//!     Ok::<_, std::io::Error>(())
+//!   })
+//!   .await
+//! }
+//! ```
+use super::handler::{Notification, WebhookAck};
+use crate::Client;
+use http_body_util::{BodyExt, Full, LengthLimitError, Limited};
+use hyper::body::{Body, Bytes};
+use hyper::header::CONTENT_TYPE;
+use hyper::{Request, Response, StatusCode};
+use serde::de::DeserializeOwned;
+use std::fmt::Display;
+use std::future::Future;
+
+/// 通知报文的最大长度，微信支付的通知远小于此值
+pub const MAX_BODY_SIZE: usize = 64 * 1024;
+
+/// 处理回调通知，见 [Client::handle_webhook]
+pub async fn handle<B, Message, F, Fut, E>(
+  client: &Client,
+  req: Request<B>,
+  handler: F,
+) -> Response<Full<Bytes>>
+where
+  B: Body,
+  B::Error: Into<Box<dyn std::error::Error + Send + Sync>>,
+  Message: DeserializeOwned,
+  F: FnOnce(Notification<Message>) -> Fut,
+  Fut: Future<Output = Result<(), E>>,
+  E: Display,
+{
+  let (parts, body) = req.into_parts();
+  let ack = match Limited::new(body, MAX_BODY_SIZE).collect().await {
+    Ok(body) => {
+      client
+        .handle_webhook(&parts.headers, &body.to_bytes(), handler)
+        .await
+    }
+    Err(err) if err.is::<LengthLimitError>() => {
+      WebhookAck::with_status(StatusCode::PAYLOAD_TOO_LARGE, "报文过长")
+    }
+    Err(_) => WebhookAck::with_status(StatusCode::BAD_REQUEST, "读取报文失败"),
+  };
+  ack.into()
+}
+
+impl From<WebhookAck> for Response<Full<Bytes>> {
+  fn from(ack: WebhookAck) -> Self {
+    Response::builder()
+      .status(ack.status)
+      .header(CONTENT_TYPE, "application/json")
+      .body(Full::new(Bytes::from(ack.to_json())))
+      .unwrap()
+  }
+}
@@ -0,0 +1,280 @@
+use super::{InboxEntry, InboxLease, InboxStore, DEFAULT_RETENTION};
+use crate::crypto::nonce_str;
+use crate::WeChatPayError;
+use async_trait::async_trait;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::Duration;
+
+#[derive(Debug)]
+enum State {
+  /// 在此时间之后可以领取
+  Queued(u64),
+  Done,
+  Dead(u64),
+}
+
+#[derive(Debug)]
+struct Stored {
+  entry: InboxEntry,
+  state: State,
+  /// 当前租约
+  lease: Option<String>,
+}
+
+/// `token` 是通知当前的租约时释放租约并返回通知
+fn release<'a>(
+  entries: &'a mut HashMap<String, Stored>,
+  id: &str,
+  token: &str,
+) -> Option<&'a mut Stored> {
+  let stored = entries.get_mut(id)?;
+  if !matches!(stored.state, State::Queued(_)) || stored.lease.as_deref() != Some(token) {
+    return None;
+  }
+  stored.lease = None;
+  Some(stored)
+}
+
+/// # 内存收件箱存储
+/// 只在单个实例内去重，进程退出后未处理的通知会丢失，适用于测试或单实例部署
+#[derive(Debug)]
+pub struct MemoryInboxStore {
+  retention: Duration,
+  entries: Mutex<HashMap<String, Stored>>,
+}
+
+impl Default for MemoryInboxStore {
+  fn default() -> Self {
+    Self::new(DEFAULT_RETENTION)
+  }
+}
+
+impl MemoryInboxStore {
+  /// `retention` 为已处理通知的去重时间
+  pub fn new(retention: Duration) -> Self {
+    Self {
+      retention,
+      entries: Mutex::new(HashMap::new()),
+    }
+  }
+}
+
+#[async_trait]
+impl InboxStore for MemoryInboxStore {
+  async fn insert(&self, entry: &InboxEntry, now: u64) -> Result<bool, WeChatPayError> {
+    let mut entries = self.entries.lock().unwrap();
+    let retention = self.retention.as_millis() as u64;
+    entries.retain(|_, stored| {
+      !matches!(stored.state, State::Done) || stored.entry.received_at + retention > now
+    });
+    if entries.contains_key(&entry.id) {
+      return Ok(false);
+    }
+    entries.insert(
+      entry.id.clone(),
+      Stored {
+        entry: entry.clone(),
+        state: State::Queued(now),
+        lease: None,
+      },
+    );
+    Ok(true)
+  }
+
+  async fn claim(&self, now: u64, lease: Duration) -> Result<Option<InboxLease>, WeChatPayError> {
+    let mut entries = self.entries.lock().unwrap();
+    let due = entries
+      .values_mut()
+      .filter(|stored| matches!(stored.state, State::Queued(at) if at <= now))
+      .min_by_key(|stored| match stored.state {
+        State::Queued(at) => at,
+        _ => u64::MAX,
+      });
+    Ok(due.map(|stored| {
+      let token = nonce_str();
+      stored.state = State::Queued(now + lease.as_millis() as u64);
+      stored.lease = Some(token.clone());
+      stored.entry.attempts += 1;
+      InboxLease {
+        entry: stored.entry.clone(),
+        token,
+      }
+    }))
+  }
+
+  async fn complete(&self, id: &str, token: &str) -> Result<bool, WeChatPayError> {
+    match release(&mut self.entries.lock().unwrap(), id, token) {
+      Some(stored) => {
+        stored.state = State::Done;
+        Ok(true)
+      }
+      None => Ok(false),
+    }
+  }
+
+  async fn retry(
+    &self,
+    id: &str,
+    token: &str,
+    error: &str,
+    retry_at: u64,
+  ) -> Result<bool, WeChatPayError> {
+    match release(&mut self.entries.lock().unwrap(), id, token) {
+      Some(stored) => {
+        stored.state = State::Queued(retry_at);
+        stored.entry.last_error = Some(error.to_string());
+        Ok(true)
+      }
+      None => Ok(false),
+    }
+  }
+
+  async fn dead_letter(
+    &self,
+    id: &str,
+    token: &str,
+    error: &str,
+    now: u64,
+  ) -> Result<bool, WeChatPayError> {
+    match release(&mut self.entries.lock().unwrap(), id, token) {
+      Some(stored) => {
+        stored.state = State::Dead(now);
+        stored.entry.last_error = Some(error.to_string());
+        Ok(true)
+      }
+      None => Ok(false),
+    }
+  }
+
+  async fn dead_letters(&self, limit: usize) -> Result<Vec<InboxEntry>, WeChatPayError> {
+    let entries = self.entries.lock().unwrap();
+    let mut dead = entries
+      .values()
+      .filter_map(|stored| match stored.state {
+        State::Dead(at) => Some((at, stored.entry.clone())),
+        _ => None,
+      })
+      .collect::<Vec<_>>();
+    dead.sort_by_key(|(at, _)| *at);
+    Ok(
+      dead
+        .into_iter()
+        .take(limit)
+        .map(|(_, entry)| entry)
+        .collect(),
+    )
+  }
+
+  async fn requeue(&self, id: &str, now: u64) -> Result<bool, WeChatPayError> {
+    let mut entries = self.entries.lock().unwrap();
+    match entries.get_mut(id) {
+      Some(stored) if matches!(stored.state, State::Dead(_)) => {
+        stored.state = State::Queued(now);
+        stored.entry.attempts = 0;
+        Ok(true)
+      }
+      _ => Ok(false),
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn entry(id: &str) -> InboxEntry {
+    InboxEntry {
+      id: id.to_string(),
+      event_type: "TRANSACTION.SUCCESS".to_string(),
+      body: "{}".to_string(),
+      received_at: 1_000,
+      attempts: 0,
+      last_error: None,
+    }
+  }
+
+  const LEASE: Duration = Duration::from_secs(60);
+
+  #[tokio::test]
+  async fn insert_dedupes_by_id() {
+    let store = MemoryInboxStore::default();
+    assert!(store.insert(&entry("EV-1"), 1_000).await.unwrap());
+    assert!(!store.insert(&entry("EV-1"), 1_001).await.unwrap());
+    let lease = store.claim(1_001, LEASE).await.unwrap().unwrap();
+    assert!(store.complete("EV-1", &lease.token).await.unwrap());
+    // 处理完成后在去重时间内仍然去重
+    assert!(!store.insert(&entry("EV-1"), 2_000).await.unwrap());
+    assert!(store.claim(2_000, LEASE).await.unwrap().is_none());
+  }
+
+  #[tokio::test]
+  async fn claim_hides_entry_until_lease_expires() {
+    let store = MemoryInboxStore::default();
+    store.insert(&entry("EV-1"), 1_000).await.unwrap();
+    let first = store.claim(1_000, LEASE).await.unwrap().unwrap();
+    assert_eq!(first.entry.attempts, 1);
+    assert!(store.claim(1_001, LEASE).await.unwrap().is_none());
+
+    // 租约过期后被重新领取，原租约不能再提交结果
+    let second = store.claim(61_000, LEASE).await.unwrap().unwrap();
+    assert_eq!(second.entry.attempts, 2);
+    assert_ne!(first.token, second.token);
+    assert!(!store.complete("EV-1", &first.token).await.unwrap());
+    assert!(!store
+      .retry("EV-1", &first.token, "stale", 61_001)
+      .await
+      .unwrap());
+    assert!(!store
+      .dead_letter("EV-1", &first.token, "stale", 61_001)
+      .await
+      .unwrap());
+    assert!(store.complete("EV-1", &second.token).await.unwrap());
+    // 租约已释放，同一个租约不能重复提交
+    assert!(!store.complete("EV-1", &second.token).await.unwrap());
+  }
+
+  #[tokio::test]
+  async fn retry_requeues_after_backoff() {
+    let store = MemoryInboxStore::default();
+    store.insert(&entry("EV-1"), 1_000).await.unwrap();
+    let lease = store.claim(1_000, LEASE).await.unwrap().unwrap();
+    assert!(store
+      .retry("EV-1", &lease.token, "timeout", 5_000)
+      .await
+      .unwrap());
+    assert!(store.claim(4_999, LEASE).await.unwrap().is_none());
+    let lease = store.claim(5_000, LEASE).await.unwrap().unwrap();
+    assert_eq!(lease.entry.attempts, 2);
+    assert_eq!(lease.entry.last_error.as_deref(), Some("timeout"));
+  }
+
+  #[tokio::test]
+  async fn dead_letter_and_requeue() {
+    let store = MemoryInboxStore::default();
+    store.insert(&entry("EV-1"), 1_000).await.unwrap();
+    store.insert(&entry("EV-2"), 1_000).await.unwrap();
+    let lease = store.claim(1_000, LEASE).await.unwrap().unwrap();
+    let id = lease.entry.id.clone();
+    assert!(store
+      .dead_letter(&id, &lease.token, "failed", 2_000)
+      .await
+      .unwrap());
+
+    let dead = store.dead_letters(10).await.unwrap();
+    assert_eq!(dead.len(), 1);
+    assert_eq!(dead[0].id, id);
+    assert_eq!(dead[0].last_error.as_deref(), Some("failed"));
+    // 死信不会被领取
+    let other = store.claim(3_000, LEASE).await.unwrap().unwrap();
+    assert_ne!(other.entry.id, id);
+    assert!(store.claim(3_000, LEASE).await.unwrap().is_none());
+
+    assert!(store.requeue(&id, 4_000).await.unwrap());
+    assert!(!store.requeue(&id, 4_000).await.unwrap());
+    assert!(store.dead_letters(10).await.unwrap().is_empty());
+    let lease = store.claim(4_000, LEASE).await.unwrap().unwrap();
+    assert_eq!(lease.entry.id, id);
+    assert_eq!(lease.entry.attempts, 1);
+  }
+}
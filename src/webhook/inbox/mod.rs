@@ -0,0 +1,306 @@
+//! # 通知收件箱
+//! 微信支付在收到成功应答前会重复发送同一条通知，多个实例还可能同时收到。[WebhookInbox] 在应答前把验证通过的通知写入
+//! [InboxStore]，并按通知 ID 去重，再由后台任务把通知交给 [WebhookRouter] 处理：
+//! - 通知写入存储（或已存在）后才应答成功，重复的通知不会被再次处理
+//! - 处理通知前先领取租约，租约期间其他实例不会处理同一条通知；实例在处理过程中退出时，租约过期后通知会被重新处理。
+//!   租约过期并被其他实例领取后，原实例的处理结果不再写入存储
+//! - 处理失败按 [RetryPolicy] 重试，超过最大次数后移入死信列表，可以在排查后通过 [requeue](WebhookInbox::requeue) 重新处理
+//! - 通知数据无法解析为 [WebhookEvent](super::event::WebhookEvent) 时不重试，直接移入死信列表
+//!
+//! 通知至少被处理一次，处理函数仍需保证幂等，例如在更新订单状态前检查是否已经更新。
+//! 存储中只保存通知原文，通知数据保持加密，处理时才解密。
+//!
+//! 内置 [RedisInboxStore] 和单实例使用的 [MemoryInboxStore]。
+//!
+//! # Example
+//! ```no_run
+//! use reqwest::header::HeaderMap;
+//! use std::sync::Arc;
+//! use wechat_pay_sdk::webhook::event::TRANSACTION_SUCCESS;
+//! use wechat_pay_sdk::webhook::handler::WebhookAck;
+//! use wechat_pay_sdk::webhook::inbox::{RedisInboxStore, WebhookInbox};
+//! use wechat_pay_sdk::webhook::router::WebhookRouter;
+//! use wechat_pay_sdk::Client;
+//!
+//! async fn start(client: Arc<Client>) -> Result<Arc<WebhookInbox>, wechat_pay_sdk::WeChatPayError> {
+//!   let connection = redis::Client::open("redis://127.0.0.1/")?
+//!     .get_multiplexed_tokio_connection()
+//!     .await?;
+//!   let store = RedisInboxStore::new(connection, "wechat-pay", &client.merchant_id);
+//!   let router = WebhookRouter::new().on(TRANSACTION_SUCCESS, |notification| async move {
+//!     println!("{:?}", notification.resource);
+//!     Ok::<_, String>(())
+//!   });
+//!   let inbox = Arc::new(WebhookInbox::new(Arc::new(store), router));
+//!   tokio::spawn({
+//!     let inbox = inbox.clone();
+//!     async move { inbox.run(&client).await }
+//!   });
+//!   Ok(inbox)
+//! }
+//!
+//! // 在通知回调接口中调用
+//! async fn notify(inbox: &WebhookInbox, client: &Client, headers: &HeaderMap, body: &[u8]) -> WebhookAck {
+//!   inbox.receive(client, headers, body).await
+//! }
+//! ```
+mod memory;
+mod redis;
+
+use super::handler::{Notification, WebhookAck};
+use super::router::WebhookRouter;
+use super::WeChatWebhook;
+use crate::{Client, RetryPolicy, WeChatPayError};
+use async_trait::async_trait;
+pub use memory::MemoryInboxStore;
+pub use redis::RedisInboxStore;
+use reqwest::header::HeaderMap;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// 默认的去重时间，微信支付的重试在 24 小时 4 分钟内完成
+pub const DEFAULT_RETENTION: Duration = Duration::from_secs(7 * 24 * 3600);
+
+/// 收件箱中的通知
+#[derive(Debug, Clone)]
+pub struct InboxEntry {
+  /// 通知 ID
+  pub id: String,
+  /// 通知类型
+  pub event_type: String,
+  /// 通知原文，通知数据未解密
+  pub body: String,
+  /// 接收时间，Unix 时间戳（毫秒）
+  pub received_at: u64,
+  /// 已领取的次数，包括当前这次
+  pub attempts: u32,
+  /// 最近一次处理失败的原因
+  pub last_error: Option<String>,
+}
+
+/// 领取到的通知
+#[derive(Debug, Clone)]
+pub struct InboxLease {
+  pub entry: InboxEntry,
+  /// 租约标识，每次领取都不同，提交处理结果时用于确认仍持有租约
+  pub token: String,
+}
+
+/// 收件箱存储
+///
+/// 时间均为 Unix 时间戳（毫秒）。提交处理结果的方法在 `token` 不是该通知当前的租约时不做修改并返回 `false`，
+/// 例如租约过期后通知已被其他调用方重新领取
+#[async_trait]
+pub trait InboxStore: Send + Sync + std::fmt::Debug {
+  /// 保存新通知并加入待处理队列，通知 ID 已存在时不做修改并返回 `false`
+  async fn insert(&self, entry: &InboxEntry, now: u64) -> Result<bool, WeChatPayError>;
+  /// 领取一条到期的待处理通知，并将 `attempts` 加一；领取后的 `lease` 内其他调用方不会领取到同一条通知
+  async fn claim(&self, now: u64, lease: Duration) -> Result<Option<InboxLease>, WeChatPayError>;
+  /// 处理成功，从待处理队列中移除；通知本身保留到去重时间结束
+  async fn complete(&self, id: &str, token: &str) -> Result<bool, WeChatPayError>;
+  /// 处理失败，在 `retry_at` 之后重新处理
+  async fn retry(
+    &self,
+    id: &str,
+    token: &str,
+    error: &str,
+    retry_at: u64,
+  ) -> Result<bool, WeChatPayError>;
+  /// 移入死信列表，死信不会过期
+  async fn dead_letter(
+    &self,
+    id: &str,
+    token: &str,
+    error: &str,
+    now: u64,
+  ) -> Result<bool, WeChatPayError>;
+  /// 最早的 `limit` 条死信
+  async fn dead_letters(&self, limit: usize) -> Result<Vec<InboxEntry>, WeChatPayError>;
+  /// 将死信重新加入待处理队列并清零处理次数，死信不存在时返回 `false`
+  async fn requeue(&self, id: &str, now: u64) -> Result<bool, WeChatPayError>;
+}
+
+/// # 通知收件箱
+#[derive(Debug)]
+pub struct WebhookInbox {
+  store: Arc<dyn InboxStore>,
+  router: WebhookRouter,
+  retry_policy: RetryPolicy,
+  lease: Duration,
+  poll_interval: Duration,
+}
+
+impl WebhookInbox {
+  /// 默认最多处理 8 次，重试间隔从 1 秒开始指数增长，最长 10 分钟
+  pub fn new(store: Arc<dyn InboxStore>, router: WebhookRouter) -> Self {
+    Self {
+      store,
+      router,
+      retry_policy: RetryPolicy::default()
+        .max_attempts(8)
+        .backoff(Duration::from_secs(1), Duration::from_secs(600)),
+      lease: Duration::from_secs(60),
+      poll_interval: Duration::from_secs(1),
+    }
+  }
+  /// 处理失败时的重试策略，只使用其中的次数和间隔
+  pub fn retry_policy(mut self, retry_policy: RetryPolicy) -> Self {
+    self.retry_policy = retry_policy;
+    self
+  }
+  /// 处理一条通知的租约时间，应大于处理函数的最长执行时间，默认 60 秒
+  pub fn lease(mut self, lease: Duration) -> Self {
+    self.lease = lease;
+    self
+  }
+  /// 没有待处理通知时的轮询间隔，默认 1 秒
+  pub fn poll_interval(mut self, poll_interval: Duration) -> Self {
+    self.poll_interval = poll_interval;
+    self
+  }
+
+  /// # 接收通知
+  /// 验证签名并确认可以解密后写入收件箱，写入成功或通知已存在时应答成功
+  ///
+  /// 这里不检查通知数据能否解析为具体的类型，解析失败的通知在处理时移入死信列表
+  pub async fn receive(&self, client: &Client, headers: &HeaderMap, body: &[u8]) -> WebhookAck {
+    match self.try_receive(client, headers, body).await {
+      Ok(()) => WebhookAck::success(),
+      Err(err) => {
+        if err.kind() == crate::ErrorKind::Transport {
+          tracing::error!(error = %err, "failed to store webhook");
+        }
+        WebhookAck::from_error(&err)
+      }
+    }
+  }
+
+  async fn try_receive(
+    &self,
+    client: &Client,
+    headers: &HeaderMap,
+    body: &[u8],
+  ) -> Result<(), WeChatPayError> {
+    let webhook = client.verify_webhook(headers, body).await?;
+    webhook.parse::<serde_json::Value>(client)?;
+    let now = now_millis();
+    let entry = InboxEntry {
+      id: webhook.id,
+      event_type: webhook.event_type,
+      body: String::from_utf8_lossy(body).into_owned(),
+      received_at: now,
+      attempts: 0,
+      last_error: None,
+    };
+    if !self.store.insert(&entry, now).await? {
+      tracing::debug!(id = %entry.id, "duplicate webhook");
+    }
+    Ok(())
+  }
+
+  /// 处理一条到期的通知，没有待处理的通知时返回 `false`
+  pub async fn process_next(&self, client: &Client) -> Result<bool, WeChatPayError> {
+    let Some(InboxLease { entry, token }) = self.store.claim(now_millis(), self.lease).await?
+    else {
+      return Ok(false);
+    };
+    let committed = match self.dispatch(client, &entry).await {
+      Ok(()) => self.store.complete(&entry.id, &token).await?,
+      Err(Failure { error, retryable })
+        if !retryable || entry.attempts >= self.retry_policy.max_attempts =>
+      {
+        tracing::error!(
+          id = %entry.id,
+          event_type = %entry.event_type,
+          attempts = entry.attempts,
+          error = %error,
+          "webhook moved to dead letters"
+        );
+        self
+          .store
+          .dead_letter(&entry.id, &token, &error, now_millis())
+          .await?
+      }
+      Err(Failure { error, .. }) => {
+        let backoff = self.retry_policy.backoff_for(entry.attempts);
+        tracing::warn!(
+          id = %entry.id,
+          event_type = %entry.event_type,
+          attempts = entry.attempts,
+          error = %error,
+          backoff_ms = backoff.as_millis() as u64,
+          "failed to process webhook"
+        );
+        let retry_at = now_millis() + backoff.as_millis() as u64;
+        self
+          .store
+          .retry(&entry.id, &token, &error, retry_at)
+          .await?
+      }
+    };
+    if !committed {
+      tracing::warn!(
+        id = %entry.id,
+        event_type = %entry.event_type,
+        "webhook lease expired before processing finished"
+      );
+    }
+    Ok(true)
+  }
+
+  async fn dispatch(&self, client: &Client, entry: &InboxEntry) -> Result<(), Failure> {
+    // 报文和通知数据在接收时已经验证过，解析失败时重试也不会成功
+    let fatal = |error: WeChatPayError| Failure {
+      error: error.to_string(),
+      retryable: false,
+    };
+    let webhook =
+      serde_json::from_str::<WeChatWebhook>(&entry.body).map_err(|e| fatal(e.into()))?;
+    let resource = webhook.parse_event(client).map_err(fatal)?;
+    self
+      .router
+      .route(Notification { webhook, resource })
+      .await
+      .map_err(|error| Failure {
+        error,
+        retryable: true,
+      })
+  }
+
+  /// 持续处理待处理的通知，需要在后台任务中运行
+  pub async fn run(&self, client: &Client) {
+    loop {
+      match self.process_next(client).await {
+        Ok(true) => {}
+        Ok(false) => tokio::time::sleep(self.poll_interval).await,
+        Err(err) => {
+          tracing::warn!(error = %err, "failed to poll webhook inbox");
+          tokio::time::sleep(self.poll_interval).await
+        }
+      }
+    }
+  }
+
+  /// 最早的 `limit` 条死信
+  pub async fn dead_letters(&self, limit: usize) -> Result<Vec<InboxEntry>, WeChatPayError> {
+    self.store.dead_letters(limit).await
+  }
+
+  /// 重新处理死信
+  pub async fn requeue(&self, id: &str) -> Result<bool, WeChatPayError> {
+    self.store.requeue(id, now_millis()).await
+  }
+}
+
+/// 处理失败的原因
+struct Failure {
+  error: String,
+  retryable: bool,
+}
+
+fn now_millis() -> u64 {
+  SystemTime::now()
+    .duration_since(UNIX_EPOCH)
+    .map(|d| d.as_millis() as u64)
+    .unwrap_or_default()
+}
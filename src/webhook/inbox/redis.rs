@@ -0,0 +1,290 @@
+use super::{InboxEntry, InboxLease, InboxStore, DEFAULT_RETENTION};
+use crate::crypto::nonce_str;
+use crate::WeChatPayError;
+use async_trait::async_trait;
+use redis::aio::MultiplexedConnection;
+use redis::Script;
+use std::collections::HashMap;
+use std::time::Duration;
+
+/// 通知不存在时保存并加入待处理队列
+const INSERT_SCRIPT: &str = r#"
+if redis.call("EXISTS", KEYS[1]) == 1 then
+  return 0
+end
+redis.call("HSET", KEYS[1], "event_type", ARGV[2], "body", ARGV[3], "received_at", ARGV[4], "attempts", 0)
+redis.call("PEXPIRE", KEYS[1], ARGV[5])
+redis.call("ZADD", KEYS[2], ARGV[4], ARGV[1])
+return 1
+"#;
+
+/// 领取最早到期的通知：将其到期时间推迟一个租约，记录租约标识，并增加处理次数
+const CLAIM_SCRIPT: &str = r#"
+local ids = redis.call("ZRANGEBYSCORE", KEYS[1], "-inf", ARGV[1], "LIMIT", 0, 1)
+if #ids == 0 then
+  return false
+end
+local id = ids[1]
+local key = ARGV[3] .. id
+if redis.call("EXISTS", key) == 0 then
+  redis.call("ZREM", KEYS[1], id)
+  return false
+end
+redis.call("ZADD", KEYS[1], tonumber(ARGV[1]) + tonumber(ARGV[2]), id)
+redis.call("HINCRBY", key, "attempts", 1)
+redis.call("HSET", key, "lease", ARGV[4])
+local fields = redis.call("HGETALL", key)
+table.insert(fields, 1, id)
+return fields
+"#;
+
+/// 仍持有租约时处理成功：从待处理队列中移除
+const COMPLETE_SCRIPT: &str = r#"
+if redis.call("HGET", KEYS[1], "lease") ~= ARGV[2] or not redis.call("ZSCORE", KEYS[2], ARGV[1]) then
+  return 0
+end
+redis.call("HDEL", KEYS[1], "lease")
+redis.call("ZREM", KEYS[2], ARGV[1])
+return 1
+"#;
+
+/// 仍持有租约时处理失败：记录错误并在 `retry_at` 之后重新处理
+const RETRY_SCRIPT: &str = r#"
+if redis.call("HGET", KEYS[1], "lease") ~= ARGV[2] or not redis.call("ZSCORE", KEYS[2], ARGV[1]) then
+  return 0
+end
+redis.call("HDEL", KEYS[1], "lease")
+redis.call("HSET", KEYS[1], "last_error", ARGV[3])
+redis.call("ZADD", KEYS[2], ARGV[4], ARGV[1])
+return 1
+"#;
+
+/// 仍持有租约时移入死信列表，死信不再过期
+const DEAD_LETTER_SCRIPT: &str = r#"
+if redis.call("HGET", KEYS[1], "lease") ~= ARGV[2] or not redis.call("ZSCORE", KEYS[2], ARGV[1]) then
+  return 0
+end
+redis.call("HDEL", KEYS[1], "lease")
+redis.call("HSET", KEYS[1], "last_error", ARGV[3])
+redis.call("PERSIST", KEYS[1])
+redis.call("ZREM", KEYS[2], ARGV[1])
+redis.call("ZADD", KEYS[3], ARGV[4], ARGV[1])
+return 1
+"#;
+
+/// 仅当通知在死信列表中时重新加入待处理队列
+const REQUEUE_SCRIPT: &str = r#"
+if redis.call("ZREM", KEYS[2], ARGV[1]) == 0 then
+  return 0
+end
+redis.call("HSET", KEYS[1], "attempts", 0)
+redis.call("PEXPIRE", KEYS[1], ARGV[3])
+redis.call("ZADD", KEYS[3], ARGV[2], ARGV[1])
+return 1
+"#;
+
+/// # 基于 Redis 的收件箱存储
+/// 键以 `{prefix}:webhook_inbox:{merchant_id}` 开头：
+/// - `...:entry:{id}`：通知，Hash 类型，在去重时间后过期，移入死信列表后不再过期；处理期间 `lease` 字段为租约标识
+/// - `...:queue`：待处理队列，Sorted Set，分数为可以领取的时间
+/// - `...:dead`：死信列表，Sorted Set，分数为移入的时间
+///
+/// 领取通知的脚本会访问未在 `KEYS` 中声明的通知键，在 Redis Cluster 中需要让这些键位于同一个槽，
+/// 例如在 `prefix` 中使用 hash tag：`{wechat-pay}`
+#[derive(Clone)]
+pub struct RedisInboxStore {
+  connection: MultiplexedConnection,
+  key: String,
+  retention: Duration,
+}
+
+impl std::fmt::Debug for RedisInboxStore {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    f.debug_struct("RedisInboxStore")
+      .field("key", &self.key)
+      .field("retention", &self.retention)
+      .finish()
+  }
+}
+
+impl RedisInboxStore {
+  /// # Arguments
+  ///
+  /// * `connection` - Redis 连接
+  /// * `prefix` - 键前缀，例如 `wechat-pay`
+  /// * `merchant_id` - 商户号
+  pub fn new(connection: MultiplexedConnection, prefix: &str, merchant_id: &str) -> Self {
+    Self {
+      connection,
+      key: format!("{}:webhook_inbox:{}", prefix, merchant_id),
+      retention: DEFAULT_RETENTION,
+    }
+  }
+  /// 已处理通知的去重时间，默认为 [DEFAULT_RETENTION]
+  pub fn retention(mut self, retention: Duration) -> Self {
+    self.retention = retention;
+    self
+  }
+
+  fn entry_prefix(&self) -> String {
+    format!("{}:entry:", self.key)
+  }
+  fn entry_key(&self, id: &str) -> String {
+    format!("{}{}", self.entry_prefix(), id)
+  }
+  fn queue_key(&self) -> String {
+    format!("{}:queue", self.key)
+  }
+  fn dead_key(&self) -> String {
+    format!("{}:dead", self.key)
+  }
+}
+
+fn entry_from_fields(id: String, mut fields: HashMap<String, String>) -> InboxEntry {
+  InboxEntry {
+    id,
+    event_type: fields.remove("event_type").unwrap_or_default(),
+    body: fields.remove("body").unwrap_or_default(),
+    received_at: fields
+      .get("received_at")
+      .and_then(|value| value.parse().ok())
+      .unwrap_or_default(),
+    attempts: fields
+      .get("attempts")
+      .and_then(|value| value.parse().ok())
+      .unwrap_or_default(),
+    last_error: fields.remove("last_error"),
+  }
+}
+
+#[async_trait]
+impl InboxStore for RedisInboxStore {
+  async fn insert(&self, entry: &InboxEntry, now: u64) -> Result<bool, WeChatPayError> {
+    let mut connection = self.connection.clone();
+    let inserted: i32 = Script::new(INSERT_SCRIPT)
+      .key(self.entry_key(&entry.id))
+      .key(self.queue_key())
+      .arg(&entry.id)
+      .arg(&entry.event_type)
+      .arg(&entry.body)
+      .arg(now)
+      .arg(self.retention.as_millis() as u64)
+      .invoke_async(&mut connection)
+      .await?;
+    Ok(inserted == 1)
+  }
+
+  async fn claim(&self, now: u64, lease: Duration) -> Result<Option<InboxLease>, WeChatPayError> {
+    let mut connection = self.connection.clone();
+    let token = nonce_str();
+    let fields: Option<Vec<String>> = Script::new(CLAIM_SCRIPT)
+      .key(self.queue_key())
+      .arg(now)
+      .arg(lease.as_millis() as u64)
+      .arg(self.entry_prefix())
+      .arg(&token)
+      .invoke_async(&mut connection)
+      .await?;
+    Ok(fields.and_then(|fields| {
+      let mut fields = fields.into_iter();
+      let id = fields.next()?;
+      let mut map = HashMap::new();
+      while let (Some(name), Some(value)) = (fields.next(), fields.next()) {
+        map.insert(name, value);
+      }
+      Some(InboxLease {
+        entry: entry_from_fields(id, map),
+        token,
+      })
+    }))
+  }
+
+  async fn complete(&self, id: &str, token: &str) -> Result<bool, WeChatPayError> {
+    let mut connection = self.connection.clone();
+    let completed: i32 = Script::new(COMPLETE_SCRIPT)
+      .key(self.entry_key(id))
+      .key(self.queue_key())
+      .arg(id)
+      .arg(token)
+      .invoke_async(&mut connection)
+      .await?;
+    Ok(completed == 1)
+  }
+
+  async fn retry(
+    &self,
+    id: &str,
+    token: &str,
+    error: &str,
+    retry_at: u64,
+  ) -> Result<bool, WeChatPayError> {
+    let mut connection = self.connection.clone();
+    let retried: i32 = Script::new(RETRY_SCRIPT)
+      .key(self.entry_key(id))
+      .key(self.queue_key())
+      .arg(id)
+      .arg(token)
+      .arg(error)
+      .arg(retry_at)
+      .invoke_async(&mut connection)
+      .await?;
+    Ok(retried == 1)
+  }
+
+  async fn dead_letter(
+    &self,
+    id: &str,
+    token: &str,
+    error: &str,
+    now: u64,
+  ) -> Result<bool, WeChatPayError> {
+    let mut connection = self.connection.clone();
+    let moved: i32 = Script::new(DEAD_LETTER_SCRIPT)
+      .key(self.entry_key(id))
+      .key(self.queue_key())
+      .key(self.dead_key())
+      .arg(id)
+      .arg(token)
+      .arg(error)
+      .arg(now)
+      .invoke_async(&mut connection)
+      .await?;
+    Ok(moved == 1)
+  }
+
+  async fn dead_letters(&self, limit: usize) -> Result<Vec<InboxEntry>, WeChatPayError> {
+    if limit == 0 {
+      return Ok(Vec::new());
+    }
+    let mut connection = self.connection.clone();
+    let ids: Vec<String> = redis::cmd("ZRANGE")
+      .arg(self.dead_key())
+      .arg(0)
+      .arg(limit - 1)
+      .query_async(&mut connection)
+      .await?;
+    let mut entries = Vec::with_capacity(ids.len());
+    for id in ids {
+      let fields: HashMap<String, String> = redis::cmd("HGETALL")
+        .arg(self.entry_key(&id))
+        .query_async(&mut connection)
+        .await?;
+      entries.push(entry_from_fields(id, fields));
+    }
+    Ok(entries)
+  }
+
+  async fn requeue(&self, id: &str, now: u64) -> Result<bool, WeChatPayError> {
+    let mut connection = self.connection.clone();
+    let requeued: i32 = Script::new(REQUEUE_SCRIPT)
+      .key(self.entry_key(id))
+      .key(self.dead_key())
+      .key(self.queue_key())
+      .arg(id)
+      .arg(now)
+      .arg(self.retention.as_millis() as u64)
+      .invoke_async(&mut connection)
+      .await?;
+    Ok(requeued == 1)
+  }
+}
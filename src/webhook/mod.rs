@@ -33,7 +33,7 @@
 //! ```no_run
 //! use serde::Deserialize;
 //! #[derive(Deserialize, Debug)]
-//! pub struct WeChatWebhook<Resource: Deserialize + std::fmt::Debug> {
+//! pub struct WeChatWebhook<Resource> {
 //!   pub id: String,
 //!   pub create_time: String,
 //!   pub event_type: String,
@@ -42,13 +42,58 @@
 //!   pub summary: String,
 //! }
 //! ```
+//!
+//! ## 验证签名
+//! 回调通知应当使用 [Client::parse_webhook] 处理：先用平台公钥验证原始报文的签名并检查时间戳，验证通过后再解密。
+//! ```no_run
+//! use reqwest::header::HeaderMap;
+//! use wechat_pay_sdk::webhook::transaction::TransactionSuccess;
+//! use wechat_pay_sdk::{Client, WeChatPayError};
+//!
+//! async fn notify(client: &Client, headers: &HeaderMap, body: &[u8]) -> Result<(), WeChatPayError> {
+//!   let (webhook, transaction) = client
+//!     .parse_webhook::<TransactionSuccess>(headers, body)
+//!     .await?;
+//!   println!("{} {}", webhook.event_type, transaction.out_trade_no);
+//!   Ok(())
+//! }
+//! ```
+//!
+//! 不确定通知类型时，可以用 [Client::webhook_event] 解析为 [WebhookEvent](event::WebhookEvent)，
+//! 或者用 [WebhookRouter](router::WebhookRouter) 按通知类型分发。
+//! 需要去重或失败重试时，可以先写入[收件箱](inbox)再处理。
+//!
+//! [handler] 模块负责根据处理结果生成微信支付要求的应答，启用 `axum`、`actix-web`、`hyper` feature 后可以使用对应框架的集成。
+#[cfg(feature = "actix-web")]
+pub mod actix;
+pub mod applyment;
+#[cfg(feature = "axum")]
+pub mod axum;
+pub mod combine;
+pub mod complaint;
+pub mod event;
+pub mod handler;
+#[cfg(feature = "hyper")]
+pub mod hyper;
+pub mod inbox;
+pub mod profit_sharing;
 pub mod refund;
+pub mod router;
 pub mod transaction;
+pub mod transfer;
 
+use crate::crypto::SignatureHeaders;
 use crate::{Client, WeChatPayError};
 use base64::{engine::general_purpose, Engine};
+use reqwest::header::HeaderMap;
 use serde::de::DeserializeOwned;
 use serde::Deserialize;
+use tracing::{field, Span};
+
+/// 签名探测通知的 `Wechatpay-Signature` 前缀
+///
+/// 微信支付会不定期发送签名错误的探测通知，以检查商户是否验证了签名，商户应当按验签失败应答
+pub const SIGNTEST_PREFIX: &str = "WECHATPAY/SIGNTEST/";
 
 #[derive(Deserialize, Debug)]
 pub struct Resource {
@@ -103,6 +148,8 @@ pub struct Resource {
 /// ## 通知签名
 /// 加密不能保证通知请求来自微信。微信会对发送给商户的通知进行签名，并将签名值放在通知的 HTTP 头 Wechatpay-Signature。商户应当验证签名，以确认请求来自微信，而不是其他的第三方。签名验证的算法请参考
 /// [《微信支付API v3签名验证》](https://pay.weixin.qq.com/wiki/doc/apiv3/wechatpay/wechatpay4_1.shtml)。
+///
+/// [Client::verify_webhook] 验证签名后返回本结构，直接反序列化报文则不会验证签名。
 /// ## 回调示例
 /// ### 支付成功结果通知
 /// ```json
@@ -188,18 +235,107 @@ pub struct WeChatWebhook {
 }
 
 impl WeChatWebhook {
+  /// 解密通知数据
+  ///
+  /// 解密过程记录在 `wechat_pay.webhook.parse` span 中，解密后的明文不会被记录
+  #[tracing::instrument(
+    name = "wechat_pay.webhook.parse",
+    skip_all,
+    fields(
+      mchid = %cli.merchant_id,
+      id = %self.id,
+      event_type = %self.event_type,
+      original_type = %self.resource.original_type,
+    )
+  )]
   pub fn parse<Message: DeserializeOwned>(&self, cli: &Client) -> Result<Message, WeChatPayError> {
-    let plaintext = cli.aead_aes_256_gcm_decrypt(
-      self.resource.nonce.as_bytes(),
-      general_purpose::STANDARD
-        .decode(&self.resource.ciphertext)?
-        .as_slice(),
-      match &self.resource.associated_data {
-        Some(associated_data) => Some(associated_data.as_bytes()),
-        None => None,
-      },
-    )?;
+    let plaintext = cli
+      .aead_aes_256_gcm_decrypt(
+        self.resource.nonce.as_bytes(),
+        general_purpose::STANDARD
+          .decode(&self.resource.ciphertext)?
+          .as_slice(),
+        match &self.resource.associated_data {
+          Some(associated_data) => Some(associated_data.as_bytes()),
+          None => None,
+        },
+      )
+      .inspect_err(|err| tracing::warn!(error = %err, "failed to decrypt webhook resource"))?;
     let plaintext = std::str::from_utf8(plaintext.as_slice())?;
-    Ok(serde_json::from_str::<Message>(plaintext)?)
+    // serde 的错误信息可能包含明文片段，只记录出错位置
+    serde_json::from_str::<Message>(plaintext).map_err(|err| {
+      tracing::warn!(
+        line = err.line(),
+        column = err.column(),
+        "failed to deserialize webhook resource"
+      );
+      err.into()
+    })
+  }
+}
+
+impl Client {
+  /// # 验证回调通知
+  /// 使用 `Wechatpay-Serial` 对应的平台公钥验证 `Wechatpay-Signature`，并检查 `Wechatpay-Timestamp` 与当前时间相差不超过 5 分钟，
+  /// 验证通过后才解析报文。`body` 必须是收到的原始报文，不能是重新序列化的 JSON。
+  ///
+  /// 签名探测通知返回 [WeChatPayError::SignatureProbe]，其他验签失败返回 [WeChatPayError::VerifySignatureFail]，
+  /// 两种情况都应当以 4XX 应答。
+  #[tracing::instrument(
+    name = "wechat_pay.webhook.verify",
+    skip_all,
+    fields(mchid = %self.merchant_id, serial = field::Empty)
+  )]
+  pub async fn verify_webhook(
+    &self,
+    headers: &HeaderMap,
+    body: &[u8],
+  ) -> Result<WeChatWebhook, WeChatPayError> {
+    let result = self.verify_notification(headers, body).await;
+    match &result {
+      Err(WeChatPayError::SignatureProbe) => {
+        tracing::info!("received wechat pay signature probe")
+      }
+      Err(err) => tracing::warn!(error = %err, "failed to verify webhook"),
+      Ok(_) => {}
+    }
+    result
+  }
+
+  async fn verify_notification(
+    &self,
+    headers: &HeaderMap,
+    body: &[u8],
+  ) -> Result<WeChatWebhook, WeChatPayError> {
+    let signature = SignatureHeaders::from_headers(headers)?;
+    Span::current().record("serial", signature.serial.as_str());
+    // 探测通知的签名必然无法通过验证，无需查找（或下载）平台公钥
+    if signature.signature.starts_with(SIGNTEST_PREFIX) {
+      return Err(WeChatPayError::SignatureProbe);
+    }
+    Self::verify_timestamp(signature.timestamp.as_str())?;
+    // 非 UTF-8 的报文不可能是微信支付签名的通知，按验签失败处理
+    let body = std::str::from_utf8(body).map_err(|e| {
+      WeChatPayError::VerifySignatureFail(format!("Notification body is not valid UTF-8: {}", e))
+    })?;
+    let pub_key = self
+      .find_public_key(signature.serial.as_str())
+      .await
+      .ok_or(WeChatPayError::VerifySignatureFail(
+        "No public key found".to_string(),
+      ))?;
+    signature.verify(&pub_key.key, body)?;
+    Ok(serde_json::from_str(body)?)
+  }
+
+  /// 验证并解密回调通知，见 [verify_webhook](Self::verify_webhook) 和 [WeChatWebhook::parse]
+  pub async fn parse_webhook<Message: DeserializeOwned>(
+    &self,
+    headers: &HeaderMap,
+    body: &[u8],
+  ) -> Result<(WeChatWebhook, Message), WeChatPayError> {
+    let webhook = self.verify_webhook(headers, body).await?;
+    let message = webhook.parse(self)?;
+    Ok((webhook, message))
   }
 }
@@ -0,0 +1,57 @@
+//! # [分账动账通知 API](https://pay.weixin.qq.com/wiki/doc/apiv3/apis/chapter8_1_10.shtml)
+//!
+//! 分账或分账回退成功后，微信会把相关变动结果发送给需要知悉的商户，加密前的对象类型为 `profitsharing`。
+use serde::Deserialize;
+
+/// ## 接口说明
+/// 适用对象：`直连商户`
+///
+/// 请求方式：POST
+#[derive(Deserialize, Debug)]
+pub struct ProfitSharingNotification {
+  /// 直连商户号
+  ///
+  /// 示例值：1900000100
+  pub mchid: String,
+  /// 微信订单号
+  ///
+  /// 示例值：4200000000000000000000000000
+  pub transaction_id: String,
+  /// 微信分账/回退单号
+  ///
+  /// 示例值：3008450740201411110007820472
+  pub order_id: String,
+  /// 商户分账/回退单号
+  ///
+  /// 示例值：P20150806125346
+  pub out_order_no: String,
+  /// 分账接收方
+  pub receiver: ProfitSharingReceiver,
+  /// 成功时间
+  ///
+  /// 示例值：2018-06-08T10:34:56+08:00
+  pub success_time: String,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct ProfitSharingReceiver {
+  /// 分账接收方类型
+  /// - MERCHANT_ID：商户号
+  /// - PERSONAL_OPENID：个人 openid
+  ///
+  /// 示例值：MERCHANT_ID
+  #[serde(rename = "type")]
+  pub receiver_type: String,
+  /// 分账接收方账号
+  ///
+  /// 示例值：1900000109
+  pub account: String,
+  /// 分账动账金额，单位为分
+  ///
+  /// 示例值：100
+  pub amount: i64,
+  /// 分账/回退描述
+  ///
+  /// 示例值：分给商户1900000109
+  pub description: String,
+}
@@ -66,9 +66,9 @@ pub struct RefundSuccess {
   /// 退款成功时间
   ///
   /// 1. 退款成功时间，遵循 [rfc3339](https://datatracker.ietf.org/doc/html/rfc3339) 标准格式，格式为
-  /// yyyy-MM-DDTHH:mm:ss+TIMEZONE，yyyy-MM-DD 表示年月日，T 出现在字符串中，表示 time 元素的开头，HH:mm:ss
-  /// 表示时分秒，TIMEZONE 表示时区（+08:00表示东八区时间，领先 UTC 8 小时，即北京时间）。例如：
-  /// 2015-05-20T13:29:35+08:00 表示，北京时间 2015 年 5 月 20 日 13 点 29 分 35 秒。
+  ///    yyyy-MM-DDTHH:mm:ss+TIMEZONE，yyyy-MM-DD 表示年月日，T 出现在字符串中，表示 time 元素的开头，HH:mm:ss
+  ///    表示时分秒，TIMEZONE 表示时区（+08:00表示东八区时间，领先 UTC 8 小时，即北京时间）。例如：
+  ///    2015-05-20T13:29:35+08:00 表示，北京时间 2015 年 5 月 20 日 13 点 29 分 35 秒。
   /// 2. 当退款状态为退款成功时返回此参数。
   ///
   /// 示例值：2018-06-08T10:34:56+08:00
@@ -0,0 +1,121 @@
+//! # 通知路由
+//! [WebhookRouter] 按通知类型把 [WebhookEvent] 分发给注册的异步处理函数，并根据处理结果生成[应答](WebhookAck)。
+//!
+//! 没有注册处理函数、也没有设置 [fallback](WebhookRouter::fallback) 的通知直接按接收成功应答，避免微信支付反复重试。
+//!
+//! # Example
+//! ```no_run
+//! use reqwest::header::HeaderMap;
+//! use wechat_pay_sdk::webhook::event::{WebhookEvent, REFUND_SUCCESS, TRANSACTION_SUCCESS};
+//! use wechat_pay_sdk::webhook::handler::WebhookAck;
+//! use wechat_pay_sdk::webhook::router::WebhookRouter;
+//! use wechat_pay_sdk::Client;
+//!
+//! async fn notify(client: &Client, headers: &HeaderMap, body: &[u8]) -> WebhookAck {
+//!   let router = WebhookRouter::new()
+//!     .on(TRANSACTION_SUCCESS, |notification| async move {
+//!       if let WebhookEvent::TransactionSuccess(transaction) = notification.resource {
+//!         println!("{} paid", transaction.out_trade_no);
+//!       }
+//!       Ok::<_, String>(())
+//!     })
+//!     .on(REFUND_SUCCESS, |_| async { Ok::<_, String>(()) });
+//!   router.handle(client, headers, body).await
+//! }
+//! ```
+use super::event::WebhookEvent;
+use super::handler::{Notification, WebhookAck};
+use crate::Client;
+use reqwest::header::HeaderMap;
+use std::collections::HashMap;
+use std::fmt::Display;
+use std::future::Future;
+use std::pin::Pin;
+
+type BoxFuture = Pin<Box<dyn Future<Output = Result<(), String>> + Send>>;
+type Handler = Box<dyn Fn(Notification<WebhookEvent>) -> BoxFuture + Send + Sync>;
+
+/// # 通知路由
+#[derive(Default)]
+pub struct WebhookRouter {
+  handlers: HashMap<String, Handler>,
+  fallback: Option<Handler>,
+}
+
+fn boxed<F, Fut, E>(handler: F) -> Handler
+where
+  F: Fn(Notification<WebhookEvent>) -> Fut + Send + Sync + 'static,
+  Fut: Future<Output = Result<(), E>> + Send + 'static,
+  E: Display,
+{
+  Box::new(move |notification| {
+    let future = handler(notification);
+    Box::pin(async move { future.await.map_err(|err| err.to_string()) })
+  })
+}
+
+impl WebhookRouter {
+  pub fn new() -> Self {
+    Self::default()
+  }
+  /// 注册通知类型为 `event_type` 的处理函数，如 [TRANSACTION_SUCCESS](super::event::TRANSACTION_SUCCESS)
+  pub fn on<F, Fut, E>(mut self, event_type: &str, handler: F) -> Self
+  where
+    F: Fn(Notification<WebhookEvent>) -> Fut + Send + Sync + 'static,
+    Fut: Future<Output = Result<(), E>> + Send + 'static,
+    E: Display,
+  {
+    self.handlers.insert(event_type.to_string(), boxed(handler));
+    self
+  }
+  /// 处理没有注册处理函数的通知
+  pub fn fallback<F, Fut, E>(mut self, handler: F) -> Self
+  where
+    F: Fn(Notification<WebhookEvent>) -> Fut + Send + Sync + 'static,
+    Fut: Future<Output = Result<(), E>> + Send + 'static,
+    E: Display,
+  {
+    self.fallback = Some(boxed(handler));
+    self
+  }
+
+  /// 把已验证的通知分发给对应的处理函数
+  pub async fn dispatch(&self, notification: Notification<WebhookEvent>) -> WebhookAck {
+    self.route(notification).await.into()
+  }
+
+  /// 分发通知并返回处理函数的错误信息，没有对应的处理函数时视为成功
+  pub(crate) async fn route(&self, notification: Notification<WebhookEvent>) -> Result<(), String> {
+    let handler = self
+      .handlers
+      .get(notification.webhook.event_type.as_str())
+      .or(self.fallback.as_ref());
+    match handler {
+      Some(handler) => handler(notification).await,
+      None => {
+        tracing::debug!(
+          event_type = %notification.webhook.event_type,
+          "no handler registered for webhook event"
+        );
+        Ok(())
+      }
+    }
+  }
+
+  /// 验证签名并解密通知，然后分发给对应的处理函数
+  pub async fn handle(&self, client: &Client, headers: &HeaderMap, body: &[u8]) -> WebhookAck {
+    match client.webhook_event(headers, body).await {
+      Ok(notification) => self.dispatch(notification).await,
+      Err(err) => WebhookAck::from_error(&err),
+    }
+  }
+}
+
+impl std::fmt::Debug for WebhookRouter {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    f.debug_struct("WebhookRouter")
+      .field("event_types", &self.handlers.keys().collect::<Vec<_>>())
+      .field("fallback", &self.fallback.is_some())
+      .finish()
+  }
+}
@@ -37,6 +37,8 @@ pub enum TradeStatus {
   Success,
   /// 转入退款
   Refund,
+  /// 未支付
+  NotPay,
   /// 已关闭
   Closed,
   /// 已撤销（付款码支付）
@@ -0,0 +1,49 @@
+//! # [商家转账批次回调通知 API](https://pay.weixin.qq.com/docs/merchant/apis/batch-transfer-to-balance/transfer-batch-callback-notice.html)
+//!
+//! 商家转账批次完成（`MCHTRANSFER.BATCH.FINISHED`）或关闭（`MCHTRANSFER.BATCH.CLOSED`）时，
+//! 微信会通过[发起批量转账](crate::sdk::fund::transfer)时指定的 notify_url 通知商户，加密前的对象类型为 `mch_payment`。
+use serde::Deserialize;
+
+#[derive(Deserialize, Debug)]
+pub struct TransferBatchNotification {
+  /// 商户号
+  ///
+  /// 示例值：1900001109
+  pub mchid: String,
+  /// 商家批次单号
+  ///
+  /// 示例值：plfk2020042013
+  pub out_batch_no: String,
+  /// 微信批次单号
+  ///
+  /// 示例值：1030000071100999991182020050700019480001
+  pub batch_id: String,
+  /// 批次状态
+  /// - FINISHED：已完成，批次内的所有转账明细单都已处理完成
+  /// - CLOSED：已关闭
+  ///
+  /// 示例值：FINISHED
+  pub batch_status: String,
+  /// 批次总笔数
+  pub total_num: i64,
+  /// 批次总金额，单位为分
+  pub total_amount: i64,
+  /// 转账成功金额，批次状态为 FINISHED 时返回
+  pub success_amount: Option<i64>,
+  /// 转账成功笔数，批次状态为 FINISHED 时返回
+  pub success_num: Option<i64>,
+  /// 转账失败金额，批次状态为 FINISHED 时返回
+  pub fail_amount: Option<i64>,
+  /// 转账失败笔数，批次状态为 FINISHED 时返回
+  pub fail_num: Option<i64>,
+  /// 批次更新时间
+  ///
+  /// 示例值：2015-05-20T13:29:35+08:00
+  pub update_time: String,
+  /// 批次关闭原因，批次状态为 CLOSED 时返回
+  /// - OVERDUE_CLOSE：系统超时关闭
+  /// - TRANSFER_SCENE_INVALID：付款场景已失效
+  ///
+  /// 示例值：OVERDUE_CLOSE
+  pub close_reason: Option<String>,
+}
@@ -0,0 +1,92 @@
+//! 同步客户端，需要启用 `testing` 和 `blocking` feature
+#![cfg(all(feature = "testing", feature = "blocking"))]
+
+use tokio::runtime::Runtime;
+use wechat_pay_sdk::sdk::common::{Amount, OrderRequest, Payer};
+use wechat_pay_sdk::testing::MockServer;
+use wechat_pay_sdk::webhook::event::WebhookEvent;
+use wechat_pay_sdk::webhook::handler::Notification;
+use wechat_pay_sdk::webhook::transaction::TransactionSuccess;
+
+fn order(server: &MockServer, out_trade_no: &str) -> OrderRequest {
+  OrderRequest {
+    appid: "wxd678efh567hg6787".to_string(),
+    mchid: server.merchant_id().to_string(),
+    description: "Image形象店-深圳腾大-QQ公仔".to_string(),
+    out_trade_no: out_trade_no.to_string(),
+    time_expire: None,
+    attach: None,
+    notify_url: "https://www.weixin.qq.com/wxpay/pay.php".to_string(),
+    goods_tag: None,
+    support_fapiao: None,
+    amount: Amount {
+      total: 100,
+      currency: None,
+    },
+    payer: Payer {
+      openid: "oUpF8uMuAJO_M2pxb1Q9zNjWeS6o".to_string(),
+    },
+    detail: None,
+    scene_info: None,
+    settle_info: None,
+  }
+}
+
+/// 模拟服务器运行在单独的运行时中，同步客户端在测试线程中调用
+fn start() -> (Runtime, MockServer) {
+  let runtime = Runtime::new().unwrap();
+  let server = runtime.block_on(MockServer::start()).unwrap();
+  (runtime, server)
+}
+
+#[test]
+fn blocking_order_and_webhook() {
+  let (_runtime, server) = start();
+  let client = server.client_builder().build_blocking().unwrap();
+
+  let response = client
+    .jsapi_order(&order(&server, "1217752501201407033233368018"))
+    .unwrap();
+  assert!(!response.prepay_id.is_empty());
+  assert_eq!(server.requests().len(), 1);
+
+  server.pay_order("1217752501201407033233368018").unwrap();
+  let notification = server
+    .order_notification("1217752501201407033233368018")
+    .unwrap();
+  let body = notification.body.as_bytes();
+
+  let webhook = client.verify_webhook(&notification.headers, body).unwrap();
+  assert_eq!(webhook.event_type, "TRANSACTION.SUCCESS");
+
+  let (_, transaction) = client
+    .parse_webhook::<TransactionSuccess>(&notification.headers, body)
+    .unwrap();
+  assert_eq!(transaction.out_trade_no, "1217752501201407033233368018");
+
+  let event = client.webhook_event(&notification.headers, body).unwrap();
+  assert!(matches!(
+    event.resource,
+    WebhookEvent::TransactionSuccess(_)
+  ));
+
+  let ack = client.handle_webhook(
+    &notification.headers,
+    body,
+    |notification: Notification<TransactionSuccess>| {
+      assert_eq!(
+        notification.resource.out_trade_no,
+        "1217752501201407033233368018"
+      );
+      Ok::<_, std::io::Error>(())
+    },
+  );
+  assert!(ack.status.is_success());
+
+  let ack = client.handle_webhook(
+    &notification.headers,
+    body,
+    |_: Notification<TransactionSuccess>| Err("数据库不可用"),
+  );
+  assert!(ack.status.is_server_error());
+}
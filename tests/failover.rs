@@ -0,0 +1,242 @@
+//! 备用域名容灾，需要启用 `testing` feature
+#![cfg(feature = "testing")]
+
+use reqwest::Method;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::net::TcpListener;
+use wechat_pay_sdk::sdk::common::{Amount, OrderRequest, Payer};
+use wechat_pay_sdk::testing::{InjectedError, MockServer};
+use wechat_pay_sdk::{
+  CircuitState, Client, ErrorKind, Failover, FailoverEvent, FailoverReason, WeChatPayError,
+};
+
+fn order(server: &MockServer, out_trade_no: &str) -> OrderRequest {
+  OrderRequest {
+    appid: "wxd678efh567hg6787".to_string(),
+    mchid: server.merchant_id().to_string(),
+    description: "Image形象店-深圳腾大-QQ公仔".to_string(),
+    out_trade_no: out_trade_no.to_string(),
+    time_expire: None,
+    attach: None,
+    notify_url: "https://www.weixin.qq.com/wxpay/pay.php".to_string(),
+    goods_tag: None,
+    support_fapiao: None,
+    amount: Amount {
+      total: 100,
+      currency: None,
+    },
+    payer: Payer {
+      openid: "oUpF8uMuAJO_M2pxb1Q9zNjWeS6o".to_string(),
+    },
+    detail: None,
+    scene_info: None,
+    settle_info: None,
+  }
+}
+
+/// 一个没有监听的本地地址，连接会被拒绝
+async fn refused_addr() -> String {
+  let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+  listener.local_addr().unwrap().to_string()
+}
+
+/// 记录容灾事件
+fn recorder() -> (Failover, Arc<Mutex<Vec<FailoverEvent>>>) {
+  let events = Arc::new(Mutex::new(Vec::new()));
+  let sink = events.clone();
+  let failover = Failover::new().on_event(move |event| sink.lock().unwrap().push(event.clone()));
+  (failover, events)
+}
+
+fn client(server: &MockServer, primary: &str, failover: Failover) -> Client {
+  server
+    .client_builder()
+    .base_url(primary)
+    .failover(failover)
+    .build()
+    .unwrap()
+}
+
+#[tokio::test]
+async fn connect_error_fails_over_to_backup() {
+  let server = MockServer::start().await.unwrap();
+  let primary = format!("http://{}", refused_addr().await);
+  let (failover, events) = recorder();
+  let client = client(
+    &server,
+    &primary,
+    failover.backup_base_url(&server.base_url()),
+  );
+
+  client
+    .jsapi_order(&order(&server, "1217752501201407033233368018"))
+    .await
+    .unwrap();
+  assert_eq!(server.requests().len(), 1);
+  assert!(server.order("1217752501201407033233368018").is_some());
+
+  let events = events.lock().unwrap();
+  assert_eq!(events.len(), 1);
+  match &events[0] {
+    FailoverEvent::FailedOver { path, reason } => {
+      assert_eq!(path, "/v3/pay/transactions/jsapi");
+      assert!(matches!(reason, FailoverReason::ConnectError(_)));
+    }
+    event => panic!("unexpected event: {:?}", event),
+  }
+  assert_eq!(client.failover().unwrap().state(), CircuitState::Closed);
+}
+
+#[tokio::test]
+async fn circuit_opens_after_threshold() {
+  let server = MockServer::start().await.unwrap();
+  let primary = format!("http://{}", refused_addr().await);
+  let (failover, events) = recorder();
+  let failover = failover
+    .backup_base_url(&server.base_url())
+    .failure_threshold(2)
+    .recovery_timeout(Duration::from_secs(3600));
+  let client = client(&server, &primary, failover);
+
+  for i in 0..3 {
+    client
+      .jsapi_order(&order(
+        &server,
+        &format!("121775250120140703323336801{}", i),
+      ))
+      .await
+      .unwrap();
+  }
+  assert_eq!(client.failover().unwrap().state(), CircuitState::Open);
+
+  let events = events.lock().unwrap();
+  assert!(matches!(
+    events[..],
+    [
+      FailoverEvent::FailedOver {
+        reason: FailoverReason::ConnectError(_),
+        ..
+      },
+      FailoverEvent::CircuitOpened {
+        consecutive_failures: 2
+      },
+      FailoverEvent::FailedOver {
+        reason: FailoverReason::ConnectError(_),
+        ..
+      },
+      // 熔断打开后不再尝试主域名
+      FailoverEvent::FailedOver {
+        reason: FailoverReason::CircuitOpen,
+        ..
+      },
+    ]
+  ));
+}
+
+#[tokio::test]
+async fn half_open_probe_recovers_primary() {
+  let server = MockServer::start().await.unwrap();
+  let backup = MockServer::start().await.unwrap();
+  let primary_addr = refused_addr().await;
+  let (failover, events) = recorder();
+  let failover = failover
+    .backup_base_url(&backup.base_url())
+    .failure_threshold(1)
+    .recovery_timeout(Duration::ZERO);
+  let client = client(&server, &format!("http://{}", primary_addr), failover);
+
+  // 备用域名指向另一个模拟服务器，应答签名无法验证，只用于确认请求被改发
+  let _ = client
+    .jsapi_order(&order(&server, "1217752501201407033233368018"))
+    .await;
+  let failed_over = backup.requests().len();
+  assert!(failed_over > 0);
+  assert_eq!(client.failover().unwrap().state(), CircuitState::HalfOpen);
+
+  // 主域名恢复：在原地址上转发到模拟服务器
+  let listener = TcpListener::bind(&primary_addr).await.unwrap();
+  let upstream = server.base_url().trim_start_matches("http://").to_string();
+  tokio::spawn(async move {
+    while let Ok((mut inbound, _)) = listener.accept().await {
+      let upstream = upstream.clone();
+      tokio::spawn(async move {
+        let mut outbound = tokio::net::TcpStream::connect(upstream).await.unwrap();
+        let _ = tokio::io::copy_bidirectional(&mut inbound, &mut outbound).await;
+      });
+    }
+  });
+
+  client
+    .jsapi_order(&order(&server, "1217752501201407033233368019"))
+    .await
+    .unwrap();
+  assert!(server.order("1217752501201407033233368019").is_some());
+  assert_eq!(backup.requests().len(), failed_over);
+  assert_eq!(client.failover().unwrap().state(), CircuitState::Closed);
+  assert!(matches!(
+    events.lock().unwrap().last(),
+    Some(FailoverEvent::Recovered)
+  ));
+}
+
+#[tokio::test]
+async fn server_error_does_not_fail_over() {
+  let server = MockServer::start().await.unwrap();
+  let backup = MockServer::start().await.unwrap();
+  let (failover, events) = recorder();
+  let client = client(
+    &server,
+    &server.base_url(),
+    failover.backup_base_url(&backup.base_url()),
+  );
+  server.inject_error(
+    Method::POST,
+    "/v3/pay/transactions/jsapi",
+    InjectedError::new(500, "SYSTEM_ERROR"),
+  );
+
+  let err = client
+    .jsapi_order(&order(&server, "1217752501201407033233368018"))
+    .await
+    .unwrap_err();
+  match err {
+    WeChatPayError::WeChatApiError(err) => assert_eq!(err.status.as_u16(), 500),
+    err => panic!("unexpected error: {}", err),
+  }
+  assert!(backup.requests().is_empty());
+  assert!(events.lock().unwrap().is_empty());
+  assert_eq!(client.failover().unwrap().state(), CircuitState::Closed);
+}
+
+#[tokio::test]
+async fn read_timeout_does_not_fail_over() {
+  let server = MockServer::start().await.unwrap();
+  let backup = MockServer::start().await.unwrap();
+  // 接受连接但不应答
+  let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+  let primary = format!("http://{}", listener.local_addr().unwrap());
+  tokio::spawn(async move {
+    let mut connections = Vec::new();
+    while let Ok((stream, _)) = listener.accept().await {
+      connections.push(stream);
+    }
+  });
+  let (failover, events) = recorder();
+  let client = server
+    .client_builder()
+    .base_url(&primary)
+    .timeout(Duration::from_millis(200))
+    .failover(failover.backup_base_url(&backup.base_url()))
+    .build()
+    .unwrap();
+
+  let err = client
+    .jsapi_order(&order(&server, "1217752501201407033233368018"))
+    .await
+    .unwrap_err();
+  assert_eq!(err.kind(), ErrorKind::Transport);
+  assert!(backup.requests().is_empty());
+  assert!(events.lock().unwrap().is_empty());
+  assert_eq!(client.failover().unwrap().state(), CircuitState::Closed);
+}
@@ -0,0 +1,49 @@
+//! hyper 集成，需要启用 `testing` 和 `hyper` feature
+#![cfg(all(feature = "testing", feature = "hyper"))]
+
+use http_body_util::{BodyExt, Full};
+use hyper::body::Bytes;
+use hyper::{Request, StatusCode};
+use serde_json::json;
+use wechat_pay_sdk::testing::MockServer;
+use wechat_pay_sdk::webhook::handler::Notification;
+use wechat_pay_sdk::webhook::hyper::{handle, MAX_BODY_SIZE};
+
+#[tokio::test]
+async fn handles_notification() {
+  let server = MockServer::start().await.unwrap();
+  let notification = server.notification(
+    "TRANSACTION.SUCCESS",
+    "transaction",
+    "支付成功",
+    &json!({ "out_trade_no": "1217752501201407033233368018" }),
+  );
+  let mut req = Request::post("/notify")
+    .body(Full::new(Bytes::from(notification.body)))
+    .unwrap();
+  *req.headers_mut() = notification.headers;
+  let res = handle(
+    &server.client(),
+    req,
+    |_: Notification<serde_json::Value>| async { Ok::<_, String>(()) },
+  )
+  .await;
+  assert_eq!(res.status(), StatusCode::OK);
+}
+
+#[tokio::test]
+async fn oversized_body_is_rejected_before_verification() {
+  let server = MockServer::start().await.unwrap();
+  let req = Request::post("/notify")
+    .body(Full::new(Bytes::from(vec![b' '; MAX_BODY_SIZE + 1])))
+    .unwrap();
+  let res = handle(
+    &server.client(),
+    req,
+    |_: Notification<serde_json::Value>| async { Ok::<_, String>(()) },
+  )
+  .await;
+  assert_eq!(res.status(), StatusCode::PAYLOAD_TOO_LARGE);
+  let body = res.into_body().collect().await.unwrap().to_bytes();
+  assert!(String::from_utf8_lossy(&body).contains("FAIL"));
+}
@@ -0,0 +1,147 @@
+//! 使用 [MemoryInboxStore] 测试通知收件箱的去重、重试和死信，需要启用 `testing` feature
+#![cfg(feature = "testing")]
+
+use serde_json::json;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use wechat_pay_sdk::testing::{MockNotification, MockServer};
+use wechat_pay_sdk::webhook::event::TRANSACTION_SUCCESS;
+use wechat_pay_sdk::webhook::inbox::{MemoryInboxStore, WebhookInbox};
+use wechat_pay_sdk::webhook::router::WebhookRouter;
+use wechat_pay_sdk::{Client, RetryPolicy};
+
+/// 处理函数被调用的次数，前 `failures` 次返回错误
+fn router(calls: Arc<AtomicU32>, failures: u32) -> WebhookRouter {
+  WebhookRouter::new().on(TRANSACTION_SUCCESS, move |_| {
+    let calls = calls.clone();
+    async move {
+      if calls.fetch_add(1, Ordering::SeqCst) < failures {
+        Err("database unavailable".to_string())
+      } else {
+        Ok(())
+      }
+    }
+  })
+}
+
+fn inbox(router: WebhookRouter, max_attempts: u32) -> WebhookInbox {
+  WebhookInbox::new(Arc::new(MemoryInboxStore::default()), router).retry_policy(
+    RetryPolicy::default()
+      .max_attempts(max_attempts)
+      .backoff(Duration::ZERO, Duration::ZERO),
+  )
+}
+
+async fn paid_order_notification(server: &MockServer, client: &Client) -> MockNotification {
+  let order = json!({
+    "appid": "wxd678efh567hg6787",
+    "mchid": server.merchant_id(),
+    "description": "Image形象店-深圳腾大-QQ公仔",
+    "out_trade_no": "1217752501201407033233368018",
+    "notify_url": "https://www.weixin.qq.com/wxpay/pay.php",
+    "amount": { "total": 100 },
+    "payer": { "openid": "oUpF8uMuAJO_M2pxb1Q9zNjWeS6o" },
+  });
+  client
+    .send_request::<_, serde_json::Value>(
+      reqwest::Method::POST,
+      "/v3/pay/transactions/jsapi",
+      None,
+      Some(&order),
+    )
+    .await
+    .unwrap();
+  server.pay_order("1217752501201407033233368018").unwrap();
+  server
+    .order_notification("1217752501201407033233368018")
+    .unwrap()
+}
+
+#[tokio::test]
+async fn duplicate_notification_is_processed_once() {
+  let server = MockServer::start().await.unwrap();
+  let client = server.client();
+  let notification = paid_order_notification(&server, &client).await;
+  let calls = Arc::new(AtomicU32::new(0));
+  let inbox = inbox(router(calls.clone(), 0), 3);
+
+  for _ in 0..2 {
+    let ack = inbox
+      .receive(&client, &notification.headers, notification.body.as_bytes())
+      .await;
+    assert!(ack.is_success());
+  }
+  assert!(inbox.process_next(&client).await.unwrap());
+  assert!(!inbox.process_next(&client).await.unwrap());
+  assert_eq!(calls.load(Ordering::SeqCst), 1);
+}
+
+#[tokio::test]
+async fn failed_notification_is_retried() {
+  let server = MockServer::start().await.unwrap();
+  let client = server.client();
+  let notification = paid_order_notification(&server, &client).await;
+  let calls = Arc::new(AtomicU32::new(0));
+  let inbox = inbox(router(calls.clone(), 2), 3);
+
+  inbox
+    .receive(&client, &notification.headers, notification.body.as_bytes())
+    .await;
+  for _ in 0..3 {
+    assert!(inbox.process_next(&client).await.unwrap());
+  }
+  assert!(!inbox.process_next(&client).await.unwrap());
+  assert_eq!(calls.load(Ordering::SeqCst), 3);
+  assert!(inbox.dead_letters(10).await.unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn exhausted_notification_is_dead_lettered_and_requeued() {
+  let server = MockServer::start().await.unwrap();
+  let client = server.client();
+  let notification = paid_order_notification(&server, &client).await;
+  let calls = Arc::new(AtomicU32::new(0));
+  let inbox = inbox(router(calls.clone(), 2), 2);
+
+  inbox
+    .receive(&client, &notification.headers, notification.body.as_bytes())
+    .await;
+  while inbox.process_next(&client).await.unwrap() {}
+  assert_eq!(calls.load(Ordering::SeqCst), 2);
+  let dead = inbox.dead_letters(10).await.unwrap();
+  assert_eq!(dead.len(), 1);
+  assert_eq!(dead[0].attempts, 2);
+  // 死信保留处理函数返回的错误，而不是应答中的通用信息
+  assert_eq!(dead[0].last_error.as_deref(), Some("database unavailable"));
+
+  assert!(inbox.requeue(&dead[0].id).await.unwrap());
+  assert!(inbox.process_next(&client).await.unwrap());
+  assert_eq!(calls.load(Ordering::SeqCst), 3);
+  assert!(inbox.dead_letters(10).await.unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn unparseable_notification_is_accepted_and_dead_lettered() {
+  let server = MockServer::start().await.unwrap();
+  let client = server.client();
+  // 可以解密，但缺少 TransactionSuccess 的必填字段
+  let notification = server.notification(
+    TRANSACTION_SUCCESS,
+    "transaction",
+    "支付成功",
+    &json!({ "out_trade_no": "1217752501201407033233368018" }),
+  );
+  let calls = Arc::new(AtomicU32::new(0));
+  let inbox = inbox(router(calls.clone(), 0), 8);
+
+  let ack = inbox
+    .receive(&client, &notification.headers, notification.body.as_bytes())
+    .await;
+  assert!(ack.is_success());
+  assert!(inbox.process_next(&client).await.unwrap());
+  assert_eq!(calls.load(Ordering::SeqCst), 0);
+  let dead = inbox.dead_letters(10).await.unwrap();
+  assert_eq!(dead.len(), 1);
+  assert_eq!(dead[0].attempts, 1);
+}
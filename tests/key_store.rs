@@ -0,0 +1,63 @@
+//! 平台公钥共享存储，需要启用 `testing` feature
+#![cfg(feature = "testing")]
+
+use async_trait::async_trait;
+use reqwest::Method;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use wechat_pay_sdk::store::{PlatformKeyStore, StoredPlatformKeys};
+use wechat_pay_sdk::testing::MockServer;
+use wechat_pay_sdk::{ErrorKind, WeChatPayError};
+
+/// 记录读取次数的空存储
+#[derive(Debug, Default)]
+struct CountingStore {
+  loads: AtomicUsize,
+}
+
+#[async_trait]
+impl PlatformKeyStore for CountingStore {
+  async fn load(&self) -> Result<Option<StoredPlatformKeys>, WeChatPayError> {
+    self.loads.fetch_add(1, Ordering::SeqCst);
+    Ok(None)
+  }
+  async fn save(&self, _keys: &StoredPlatformKeys) -> Result<(), WeChatPayError> {
+    Ok(())
+  }
+  async fn try_lock(&self, _ttl: Duration) -> Result<Option<String>, WeChatPayError> {
+    Ok(None)
+  }
+  async fn unlock(&self, _token: &str) -> Result<(), WeChatPayError> {
+    Ok(())
+  }
+}
+
+#[tokio::test]
+async fn unknown_serial_loads_store_once_per_cooldown() {
+  let server = MockServer::start().await.unwrap();
+  let other = MockServer::start().await.unwrap();
+  let store = Arc::new(CountingStore::default());
+  // 客户端只有另一个平台的公钥，应答中的序列号未知
+  let client = server
+    .client_builder()
+    .platform_pub_keys(vec![other.platform_public_key()])
+    .key_store(store.clone())
+    .auto_refresh_certificates(false)
+    .build()
+    .unwrap();
+
+  for _ in 0..3 {
+    let err = client
+      .send_request::<(), serde_json::Value>(
+        Method::GET,
+        "/v3/pay/transactions/out-trade-no/1217752501201407033233368018",
+        Some(&[("mchid", server.merchant_id())]),
+        None,
+      )
+      .await
+      .unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::Signature);
+  }
+  assert_eq!(store.loads.load(Ordering::SeqCst), 1);
+}
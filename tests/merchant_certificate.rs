@@ -0,0 +1,88 @@
+//! 商户 API 证书，需要启用 `testing` feature
+#![cfg(feature = "testing")]
+
+use chrono::{Duration, Utc};
+use wechat_pay_sdk::testing::{MockServer, MOCK_API_V3_KEY, MOCK_MERCHANT_ID};
+use wechat_pay_sdk::{Client, ErrorKind};
+
+#[tokio::test]
+async fn serial_number_is_read_from_certificate() {
+  let server = MockServer::start().await.unwrap();
+  let client = Client::builder(MOCK_MERCHANT_ID, "", MOCK_API_V3_KEY)
+    .private_key_pem(server.merchant_private_key_pem())
+    .merchant_certificate_pem(server.merchant_certificate_pem())
+    .build()
+    .unwrap();
+
+  let cert = client.merchant_certificate().unwrap();
+  assert_eq!(cert.serial_number, server.merchant_serial_no());
+  assert_eq!(cert.serial_number, cert.serial_number.to_uppercase());
+}
+
+#[tokio::test]
+async fn serial_number_matching_certificate_is_accepted() {
+  let server = MockServer::start().await.unwrap();
+  let client = Client::builder(
+    MOCK_MERCHANT_ID,
+    &server.merchant_serial_no().to_lowercase(),
+    MOCK_API_V3_KEY,
+  )
+  .private_key_pem(server.merchant_private_key_pem())
+  .merchant_certificate_pem(server.merchant_certificate_pem())
+  .build()
+  .unwrap();
+  assert_eq!(
+    client.merchant_certificate().unwrap().serial_number,
+    server.merchant_serial_no()
+  );
+}
+
+#[tokio::test]
+async fn private_key_must_match_certificate() {
+  let server = MockServer::start().await.unwrap();
+  let other = MockServer::start().await.unwrap();
+  let err = Client::builder(MOCK_MERCHANT_ID, "", MOCK_API_V3_KEY)
+    .private_key_pem(other.merchant_private_key_pem())
+    .merchant_certificate_pem(server.merchant_certificate_pem())
+    .build()
+    .unwrap_err();
+  assert_eq!(err.kind(), ErrorKind::Configuration);
+  assert!(err
+    .to_string()
+    .contains("Merchant private key does not match the merchant certificate"));
+}
+
+#[tokio::test]
+async fn serial_number_must_match_certificate() {
+  let server = MockServer::start().await.unwrap();
+  let other = MockServer::start().await.unwrap();
+  let err = Client::builder(
+    MOCK_MERCHANT_ID,
+    other.merchant_serial_no(),
+    MOCK_API_V3_KEY,
+  )
+  .private_key_pem(server.merchant_private_key_pem())
+  .merchant_certificate_pem(server.merchant_certificate_pem())
+  .build()
+  .unwrap_err();
+  assert_eq!(err.kind(), ErrorKind::Configuration);
+  let message = err.to_string();
+  assert!(message.contains(other.merchant_serial_no()));
+  assert!(message.contains(server.merchant_serial_no()));
+}
+
+#[tokio::test]
+async fn expiry_fields() {
+  let server = MockServer::start().await.unwrap();
+  let client = server.client();
+  let cert = client.merchant_certificate().unwrap();
+
+  // 模拟证书有效期为生成时起 365 天
+  let now = Utc::now();
+  assert!(cert.not_before <= now);
+  assert!(cert.not_before > now - Duration::days(1));
+  assert_eq!((cert.not_after - cert.not_before).num_days(), 365);
+  assert!(cert.is_valid());
+  let expires_in = cert.expires_in();
+  assert!(expires_in > Duration::days(364) && expires_in <= Duration::days(365));
+}
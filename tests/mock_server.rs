@@ -0,0 +1,236 @@
+//! 使用 [MockServer] 对 [Client] 做端到端测试，需要启用 `testing` feature
+#![cfg(feature = "testing")]
+
+use reqwest::Method;
+use wechat_pay_sdk::sdk::common::{Amount, OrderRequest, Payer};
+use wechat_pay_sdk::testing::{InjectedError, MockServer, MOCK_API_V3_KEY, MOCK_MERCHANT_ID};
+use wechat_pay_sdk::{
+  Client, ErrorKind, PlatformPubKey, VerificationMode, WeChatPayApiErrorCode, WeChatPayError,
+};
+
+fn order(server: &MockServer, out_trade_no: &str) -> OrderRequest {
+  OrderRequest {
+    appid: "wxd678efh567hg6787".to_string(),
+    mchid: server.merchant_id().to_string(),
+    description: "Image形象店-深圳腾大-QQ公仔".to_string(),
+    out_trade_no: out_trade_no.to_string(),
+    time_expire: None,
+    attach: None,
+    notify_url: "https://www.weixin.qq.com/wxpay/pay.php".to_string(),
+    goods_tag: None,
+    support_fapiao: None,
+    amount: Amount {
+      total: 100,
+      currency: None,
+    },
+    payer: Payer {
+      openid: "oUpF8uMuAJO_M2pxb1Q9zNjWeS6o".to_string(),
+    },
+    detail: None,
+    scene_info: None,
+    settle_info: None,
+  }
+}
+
+#[tokio::test]
+async fn signed_request_and_verified_response() {
+  let server = MockServer::start().await.unwrap();
+  let client = server.client();
+
+  let response = client
+    .jsapi_order(&order(&server, "1217752501201407033233368018"))
+    .await
+    .unwrap();
+  assert!(!response.prepay_id.is_empty());
+  let requests = server.requests();
+  assert_eq!(requests.len(), 1);
+  assert_eq!(requests[0].path, "/v3/pay/transactions/jsapi");
+  assert!(server.order("1217752501201407033233368018").is_some());
+}
+
+#[tokio::test]
+async fn request_signed_with_wrong_key_is_rejected() {
+  let server = MockServer::start().await.unwrap();
+  let other = MockServer::start().await.unwrap();
+  // 商户证书序列号正确，但使用另一个商户的私钥签名
+  let client = Client::builder(
+    MOCK_MERCHANT_ID,
+    server.merchant_serial_no(),
+    MOCK_API_V3_KEY,
+  )
+  .private_key_pem(other.merchant_private_key_pem())
+  .platform_pub_keys(vec![server.platform_public_key()])
+  .verification_mode(VerificationMode::PlatformCertificate)
+  .base_url(&server.base_url())
+  .build()
+  .unwrap();
+
+  let err = client
+    .jsapi_order(&order(&server, "1217752501201407033233368018"))
+    .await
+    .unwrap_err();
+  match err {
+    WeChatPayError::WeChatApiError(err) => assert_eq!(err.raw_code, "SIGN_ERROR"),
+    err => panic!("unexpected error: {}", err),
+  }
+  assert!(server.order("1217752501201407033233368018").is_none());
+}
+
+#[tokio::test]
+async fn response_with_wrong_signature_is_rejected() {
+  let server = MockServer::start().await.unwrap();
+  let other = MockServer::start().await.unwrap();
+  // 序列号正确但公钥属于另一个平台，应答签名无法通过验证
+  let client = server
+    .client_builder()
+    .platform_pub_keys(vec![PlatformPubKey {
+      serial_no: server.platform_serial_no().to_string(),
+      ..other.platform_public_key()
+    }])
+    .auto_refresh_certificates(false)
+    .build()
+    .unwrap();
+
+  let err = client
+    .jsapi_order(&order(&server, "1217752501201407033233368018"))
+    .await
+    .unwrap_err();
+  assert_eq!(err.kind(), ErrorKind::Signature);
+}
+
+#[tokio::test]
+async fn download_certificates_from_fresh_client() {
+  let server = MockServer::start().await.unwrap();
+  let client = server
+    .client_builder()
+    .platform_pub_keys(vec![])
+    .build()
+    .unwrap();
+  assert!(client.is_public_keys_empty());
+
+  let keys = client.download_certificates().await.unwrap();
+  assert_eq!(keys.len(), 1);
+  assert_eq!(keys[0].serial_no, server.platform_serial_no());
+  assert_eq!(keys[0].key, server.platform_public_key().key);
+
+  // 下载后可以验证业务接口的应答
+  client.update_public_keys(keys);
+  client
+    .jsapi_order(&order(&server, "1217752501201407033233368018"))
+    .await
+    .unwrap();
+}
+
+#[tokio::test]
+async fn injected_error_is_returned_without_changing_state() {
+  let server = MockServer::start().await.unwrap();
+  let client = server.client();
+  server.inject_error(
+    Method::POST,
+    "/v3/pay/transactions/jsapi",
+    InjectedError::new(500, "SYSTEM_ERROR").message("系统错误"),
+  );
+
+  let err = client
+    .jsapi_order(&order(&server, "1217752501201407033233368018"))
+    .await
+    .unwrap_err();
+  assert_eq!(err.kind(), ErrorKind::Api);
+  match err {
+    WeChatPayError::WeChatApiError(err) => {
+      assert_eq!(err.status.as_u16(), 500);
+      assert_eq!(err.raw_code, "SYSTEM_ERROR");
+    }
+    err => panic!("unexpected error: {}", err),
+  }
+  assert!(server.order("1217752501201407033233368018").is_none());
+
+  // 注入的错误默认只生效一次
+  client
+    .jsapi_order(&order(&server, "1217752501201407033233368018"))
+    .await
+    .unwrap();
+}
+
+#[tokio::test]
+async fn unlisted_error_code_is_kept_raw() {
+  let server = MockServer::start().await.unwrap();
+  let client = server.client();
+  server.inject_error(
+    Method::POST,
+    "/v3/pay/transactions/jsapi",
+    InjectedError::new(403, "BRAND_NEW_ERROR").message("新增的错误码"),
+  );
+
+  let err = client
+    .jsapi_order(&order(&server, "1217752501201407033233368018"))
+    .await
+    .unwrap_err();
+  let err = err.api_error().unwrap();
+  assert_eq!(err.status.as_u16(), 403);
+  assert_eq!(err.raw_code, "BRAND_NEW_ERROR");
+  assert_eq!(
+    err.code,
+    WeChatPayApiErrorCode::Other("BRAND_NEW_ERROR".to_string())
+  );
+  assert_eq!(err.message.as_deref(), Some("新增的错误码"));
+  assert!(err
+    .request_id
+    .as_deref()
+    .is_some_and(|id| id.starts_with("mock-")));
+}
+
+#[tokio::test]
+async fn gateway_error_is_parsed() {
+  let server = MockServer::start().await.unwrap();
+  let client = server.client();
+  server.inject_error(
+    Method::POST,
+    "/v3/pay/transactions/jsapi",
+    InjectedError::new(504, "SYSTEM_ERROR"),
+  );
+
+  let err = client
+    .jsapi_order(&order(&server, "1217752501201407033233368018"))
+    .await
+    .unwrap_err();
+  let err = err.api_error().unwrap();
+  assert_eq!(err.status.as_u16(), 504);
+  assert_eq!(err.raw_code, "SYSTEM_ERROR");
+  assert!(err.request_id.is_some());
+}
+
+#[tokio::test]
+async fn mismatched_success_body_is_typed_error() {
+  #[derive(Debug, serde::Deserialize)]
+  struct Unexpected {
+    #[allow(dead_code)]
+    code_url: String,
+  }
+
+  let server = MockServer::start().await.unwrap();
+  let client = server.client();
+  let err = client
+    .send_request::<_, Unexpected>(
+      Method::POST,
+      "/v3/pay/transactions/jsapi",
+      None,
+      Some(&order(&server, "1217752501201407033233368018")),
+    )
+    .await
+    .unwrap_err();
+  assert_eq!(err.kind(), ErrorKind::Deserialization);
+  match err {
+    WeChatPayError::InvalidResponse {
+      status,
+      request_id,
+      body,
+      ..
+    } => {
+      assert_eq!(status.as_u16(), 200);
+      assert!(request_id.is_some());
+      assert!(body.contains("prepay_id"));
+    }
+    err => panic!("unexpected error: {}", err),
+  }
+}
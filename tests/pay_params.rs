@@ -0,0 +1,96 @@
+//! 调起支付参数的签名，需要启用 `testing` feature
+#![cfg(feature = "testing")]
+
+use base64::{engine::general_purpose, Engine};
+use rsa::pkcs8::DecodePrivateKey;
+use rsa::sha2::{Digest, Sha256};
+use rsa::{Pkcs1v15Sign, RsaPrivateKey};
+use wechat_pay_sdk::testing::MockServer;
+
+const APPID: &str = "wxd678efh567hg6787";
+const PREPAY_ID: &str = "wx201410272009395522657a690389285100";
+
+/// 使用商户公钥验证签名
+fn verify(server: &MockServer, message: &str, signature: &str) -> bool {
+  let public_key = RsaPrivateKey::from_pkcs8_pem(server.merchant_private_key_pem())
+    .unwrap()
+    .to_public_key();
+  let signature = general_purpose::STANDARD.decode(signature).unwrap();
+  let hash = Sha256::digest(message.as_bytes());
+  public_key
+    .verify(Pkcs1v15Sign::new::<Sha256>(), &hash, &signature)
+    .is_ok()
+}
+
+#[tokio::test]
+async fn jsapi_pay_params_are_signed() {
+  let server = MockServer::start().await.unwrap();
+  let client = server.client();
+  let params = client.jsapi_pay_params(APPID, PREPAY_ID).await.unwrap();
+
+  assert_eq!(params.app_id, APPID);
+  assert_eq!(params.package, format!("prepay_id={}", PREPAY_ID));
+  assert_eq!(params.sign_type, "RSA");
+  let message = format!(
+    "{}\n{}\n{}\n{}\n",
+    params.app_id, params.time_stamp, params.nonce_str, params.package
+  );
+  assert!(verify(&server, &message, &params.pay_sign));
+  // 签名串必须包含 package 而不是裸的 prepay_id
+  let wrong = format!(
+    "{}\n{}\n{}\n{}\n",
+    params.app_id, params.time_stamp, params.nonce_str, PREPAY_ID
+  );
+  assert!(!verify(&server, &wrong, &params.pay_sign));
+}
+
+#[tokio::test]
+async fn app_pay_params_are_signed() {
+  let server = MockServer::start().await.unwrap();
+  let client = server.client();
+  let params = client.app_pay_params(APPID, PREPAY_ID).await.unwrap();
+
+  assert_eq!(params.appid, APPID);
+  assert_eq!(params.partnerid, server.merchant_id());
+  assert_eq!(params.prepayid, PREPAY_ID);
+  assert_eq!(params.package, "Sign=WXPay");
+  let message = format!(
+    "{}\n{}\n{}\n{}\n",
+    params.appid, params.timestamp, params.noncestr, params.prepayid
+  );
+  assert!(verify(&server, &message, &params.sign));
+  // APP 的签名串使用 prepayid，而不是 package
+  let wrong = format!(
+    "{}\n{}\n{}\n{}\n",
+    params.appid, params.timestamp, params.noncestr, params.package
+  );
+  assert!(!verify(&server, &wrong, &params.sign));
+}
+
+#[tokio::test]
+async fn combine_pay_params_are_signed() {
+  let server = MockServer::start().await.unwrap();
+  let client = server.client();
+
+  let params = client
+    .combine_jsapi_pay_params(APPID, PREPAY_ID)
+    .await
+    .unwrap();
+  assert_eq!(params.package, format!("prepay_id={}", PREPAY_ID));
+  let message = format!(
+    "{}\n{}\n{}\n{}\n",
+    params.app_id, params.time_stamp, params.nonce_str, params.package
+  );
+  assert!(verify(&server, &message, &params.pay_sign));
+
+  let params = client
+    .combine_app_pay_params(APPID, PREPAY_ID)
+    .await
+    .unwrap();
+  assert_eq!(params.package, "Sign=WXPay");
+  let message = format!(
+    "{}\n{}\n{}\n{}\n",
+    params.appid, params.timestamp, params.noncestr, params.prepayid
+  );
+  assert!(verify(&server, &message, &params.sign));
+}
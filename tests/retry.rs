@@ -0,0 +1,130 @@
+//! 自动重试，需要启用 `testing` feature
+#![cfg(feature = "testing")]
+
+use reqwest::Method;
+use std::collections::HashMap;
+use std::time::Duration;
+use wechat_pay_sdk::sdk::common::{Amount, OrderRequest, Payer};
+use wechat_pay_sdk::testing::{InjectedError, MockServer, RecordedRequest};
+use wechat_pay_sdk::{Client, RetryPolicy, WeChatPayError};
+
+fn order(server: &MockServer, out_trade_no: &str) -> OrderRequest {
+  OrderRequest {
+    appid: "wxd678efh567hg6787".to_string(),
+    mchid: server.merchant_id().to_string(),
+    description: "Image形象店-深圳腾大-QQ公仔".to_string(),
+    out_trade_no: out_trade_no.to_string(),
+    time_expire: None,
+    attach: None,
+    notify_url: "https://www.weixin.qq.com/wxpay/pay.php".to_string(),
+    goods_tag: None,
+    support_fapiao: None,
+    amount: Amount {
+      total: 100,
+      currency: None,
+    },
+    payer: Payer {
+      openid: "oUpF8uMuAJO_M2pxb1Q9zNjWeS6o".to_string(),
+    },
+    detail: None,
+    scene_info: None,
+    settle_info: None,
+  }
+}
+
+fn client(server: &MockServer) -> Client {
+  server
+    .client_builder()
+    .retry_policy(
+      RetryPolicy::default()
+        .backoff(Duration::from_millis(1), Duration::from_millis(1))
+        .jitter(false),
+    )
+    .build()
+    .unwrap()
+}
+
+/// 解析 `Authorization` 头中的各个字段
+fn authorization(request: &RecordedRequest) -> HashMap<String, String> {
+  let value = request.headers["Authorization"].to_str().unwrap();
+  let (_, params) = value.split_once(' ').unwrap();
+  params
+    .split(',')
+    .filter_map(|param| param.split_once('='))
+    .map(|(k, v)| (k.to_string(), v.trim_matches('"').to_string()))
+    .collect()
+}
+
+#[tokio::test]
+async fn system_error_is_retried_until_success() {
+  let server = MockServer::start().await.unwrap();
+  let client = client(&server);
+  server.inject_error(
+    Method::POST,
+    "/v3/pay/transactions/jsapi",
+    InjectedError::new(500, "SYSTEM_ERROR").times(2),
+  );
+
+  client
+    .jsapi_order(&order(&server, "1217752501201407033233368018"))
+    .await
+    .unwrap();
+  assert!(server.order("1217752501201407033233368018").is_some());
+
+  let requests = server.requests();
+  assert_eq!(requests.len(), 3);
+  assert!(requests.iter().all(|req| req.body == requests[0].body));
+  // 每次重试都重新签名
+  let auths: Vec<_> = requests.iter().map(authorization).collect();
+  for (prev, next) in auths.iter().zip(&auths[1..]) {
+    assert_ne!(prev["nonce_str"], next["nonce_str"]);
+    assert_ne!(prev["signature"], next["signature"]);
+    let prev_ts: i64 = prev["timestamp"].parse().unwrap();
+    let next_ts: i64 = next["timestamp"].parse().unwrap();
+    assert!(next_ts >= prev_ts);
+  }
+}
+
+#[tokio::test]
+async fn retries_stop_at_max_attempts() {
+  let server = MockServer::start().await.unwrap();
+  let client = client(&server);
+  server.inject_error(
+    Method::POST,
+    "/v3/pay/transactions/jsapi",
+    InjectedError::new(500, "SYSTEM_ERROR").always(),
+  );
+
+  let err = client
+    .jsapi_order(&order(&server, "1217752501201407033233368018"))
+    .await
+    .unwrap_err();
+  match err {
+    WeChatPayError::WeChatApiError(err) => assert_eq!(err.raw_code, "SYSTEM_ERROR"),
+    err => panic!("unexpected error: {}", err),
+  }
+  assert_eq!(server.requests().len(), 3);
+}
+
+#[tokio::test]
+async fn unlisted_post_is_not_retried() {
+  let server = MockServer::start().await.unwrap();
+  let client = client(&server);
+  server.inject_error(
+    Method::POST,
+    "/v3/unlisted",
+    InjectedError::new(500, "SYSTEM_ERROR").always(),
+  );
+
+  let err = client
+    .send_request::<_, serde_json::Value>(
+      Method::POST,
+      "/v3/unlisted",
+      None,
+      Some(&serde_json::json!({ "out_trade_no": "1217752501201407033233368018" })),
+    )
+    .await
+    .unwrap_err();
+  assert!(matches!(err, WeChatPayError::WeChatApiError(_)));
+  assert_eq!(server.requests().len(), 1);
+}
@@ -0,0 +1,78 @@
+//! 敏感信息加解密，需要启用 `testing` feature
+#![cfg(feature = "testing")]
+
+use reqwest::Method;
+use serde::Serialize;
+use wechat_pay_sdk::testing::MockServer;
+use wechat_pay_sdk::WeChatPayError;
+
+#[derive(Serialize)]
+struct Receiver {
+  account: String,
+  #[serde(serialize_with = "wechat_pay_sdk::sensitive::encrypt")]
+  name: String,
+  #[serde(
+    serialize_with = "wechat_pay_sdk::sensitive::encrypt",
+    skip_serializing_if = "Option::is_none"
+  )]
+  phone: Option<String>,
+}
+
+#[tokio::test]
+async fn encrypt_and_decrypt_round_trip() {
+  let server = MockServer::start().await.unwrap();
+  let client = server.client();
+
+  let (serial_no, ciphertext) = client.encrypt_sensitive("张三").unwrap();
+  assert_eq!(serial_no, server.platform_serial_no());
+  assert_ne!(ciphertext, "张三");
+  assert_eq!(
+    server.decrypt_sensitive(&ciphertext).as_deref(),
+    Some("张三")
+  );
+
+  let ciphertext = server.encrypt_sensitive("13800138000");
+  assert_eq!(
+    client.decrypt_sensitive(&ciphertext).unwrap(),
+    "13800138000"
+  );
+}
+
+#[tokio::test]
+async fn encrypt_without_key_is_config_error() {
+  let server = MockServer::start().await.unwrap();
+  let client = server
+    .client_builder()
+    .platform_pub_keys(vec![])
+    .build()
+    .unwrap();
+  let err = client.encrypt_sensitive("张三").unwrap_err();
+  assert!(matches!(err, WeChatPayError::ConfigError(_)));
+}
+
+#[tokio::test]
+async fn send_request_encrypts_annotated_fields() {
+  let server = MockServer::start().await.unwrap();
+  let client = server.client();
+  let receiver = Receiver {
+    account: "1900000109".to_string(),
+    name: "张三".to_string(),
+    phone: None,
+  };
+  // 模拟服务器没有该接口，只检查收到的请求
+  let _ = client
+    .send_request::<_, serde_json::Value>(Method::POST, "/v3/sensitive", None, Some(&receiver))
+    .await;
+
+  let request = server.requests().pop().unwrap();
+  assert_eq!(
+    request.headers["Wechatpay-Serial"].to_str().unwrap(),
+    server.platform_serial_no()
+  );
+  let body: serde_json::Value = serde_json::from_str(&request.body).unwrap();
+  assert_eq!(body["account"], "1900000109");
+  let name = body["name"].as_str().unwrap();
+  assert_ne!(name, "张三");
+  assert_eq!(server.decrypt_sensitive(name).as_deref(), Some("张三"));
+  assert!(body.get("phone").is_none());
+}
@@ -0,0 +1,71 @@
+//! 自定义签名器，需要启用 `testing` feature
+#![cfg(feature = "testing")]
+
+use async_trait::async_trait;
+use rsa::pkcs8::DecodePrivateKey;
+use rsa::RsaPrivateKey;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use wechat_pay_sdk::signer::{RsaSigner, Signer};
+use wechat_pay_sdk::testing::{MockServer, MOCK_API_V3_KEY, MOCK_MERCHANT_ID};
+use wechat_pay_sdk::{Client, ErrorKind, VerificationMode};
+
+/// 模拟远程签名服务，客户端只能拿到签名结果
+#[derive(Debug)]
+struct RemoteSigner {
+  inner: RsaSigner,
+  calls: AtomicUsize,
+}
+
+#[async_trait]
+impl Signer for RemoteSigner {
+  async fn sign(&self, message: &[u8]) -> Result<Vec<u8>, wechat_pay_sdk::WeChatPayError> {
+    self.calls.fetch_add(1, Ordering::SeqCst);
+    self.inner.sign_sync(message)
+  }
+}
+
+fn client(server: &MockServer, signer: Arc<RemoteSigner>) -> Client {
+  Client::builder(
+    MOCK_MERCHANT_ID,
+    server.merchant_serial_no(),
+    MOCK_API_V3_KEY,
+  )
+  .signer(signer)
+  .platform_pub_keys(vec![server.platform_public_key()])
+  .verification_mode(VerificationMode::PlatformCertificate)
+  .base_url(&server.base_url())
+  .build()
+  .unwrap()
+}
+
+fn remote_signer(server: &MockServer) -> Arc<RemoteSigner> {
+  let key = RsaPrivateKey::from_pkcs8_pem(server.merchant_private_key_pem()).unwrap();
+  Arc::new(RemoteSigner {
+    inner: RsaSigner::new(key),
+    calls: AtomicUsize::new(0),
+  })
+}
+
+#[tokio::test]
+async fn custom_signer_signs_requests() {
+  let server = MockServer::start().await.unwrap();
+  let signer = remote_signer(&server);
+  let client = client(&server, signer.clone());
+
+  let keys = client.download_certificates().await.unwrap();
+  assert_eq!(keys[0].serial_no, server.platform_serial_no());
+  assert_eq!(signer.calls.load(Ordering::SeqCst), 1);
+  assert_eq!(server.requests().len(), 1);
+}
+
+#[tokio::test]
+async fn custom_signer_cannot_decrypt() {
+  let server = MockServer::start().await.unwrap();
+  let client = client(&server, remote_signer(&server));
+
+  let ciphertext = server.encrypt_sensitive("张三");
+  let err = client.decrypt_sensitive(&ciphertext).unwrap_err();
+  assert_eq!(err.kind(), ErrorKind::Configuration);
+  assert!(err.to_string().contains("merchant private key"));
+}
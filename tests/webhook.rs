@@ -0,0 +1,131 @@
+//! 回调通知的签名验证，需要启用 `testing` feature
+#![cfg(feature = "testing")]
+
+use reqwest::header::HeaderValue;
+use reqwest::StatusCode;
+use serde_json::json;
+use wechat_pay_sdk::testing::{MockNotification, MockServer};
+use wechat_pay_sdk::webhook::handler::{Notification, WebhookAck};
+use wechat_pay_sdk::{ErrorKind, WeChatPayError};
+
+async fn notification() -> (MockServer, MockNotification) {
+  let server = MockServer::start().await.unwrap();
+  let notification = server.notification(
+    "TRANSACTION.SUCCESS",
+    "transaction",
+    "支付成功",
+    &json!({ "out_trade_no": "1217752501201407033233368018" }),
+  );
+  (server, notification)
+}
+
+fn assert_unauthorized(err: &WeChatPayError) {
+  assert_eq!(err.kind(), ErrorKind::Signature);
+  assert_eq!(WebhookAck::from_error(err).status, StatusCode::UNAUTHORIZED);
+}
+
+#[tokio::test]
+async fn valid_signature() {
+  let (server, notification) = notification().await;
+  let webhook = server
+    .client()
+    .verify_webhook(&notification.headers, notification.body.as_bytes())
+    .await
+    .unwrap();
+  assert_eq!(webhook.event_type, "TRANSACTION.SUCCESS");
+}
+
+#[tokio::test]
+async fn tampered_body() {
+  let (server, notification) = notification().await;
+  let body = notification.body.replace("支付成功", "支付失败");
+  let err = server
+    .client()
+    .verify_webhook(&notification.headers, body.as_bytes())
+    .await
+    .unwrap_err();
+  assert!(matches!(err, WeChatPayError::VerifySignatureFail(_)));
+  assert_unauthorized(&err);
+}
+
+#[tokio::test]
+async fn signature_probe() {
+  let (server, notification) = notification().await;
+  let notification = notification.signtest();
+  let err = server
+    .client()
+    .verify_webhook(&notification.headers, notification.body.as_bytes())
+    .await
+    .unwrap_err();
+  assert!(matches!(err, WeChatPayError::SignatureProbe));
+  assert_unauthorized(&err);
+}
+
+#[tokio::test]
+async fn timestamp_out_of_range() {
+  let (server, mut notification) = notification().await;
+  let timestamp = notification.headers["Wechatpay-Timestamp"]
+    .to_str()
+    .unwrap()
+    .parse::<u64>()
+    .unwrap();
+  // 留出足够余量，避免测试执行耗时使时间戳重新落入 ±300 秒内
+  for skew in [timestamp - 600, timestamp + 600] {
+    notification.headers.insert(
+      "Wechatpay-Timestamp",
+      HeaderValue::from_str(&skew.to_string()).unwrap(),
+    );
+    let err = server
+      .client()
+      .verify_webhook(&notification.headers, notification.body.as_bytes())
+      .await
+      .unwrap_err();
+    assert!(err.to_string().contains("Timestamp expired"));
+    assert_unauthorized(&err);
+  }
+}
+
+#[tokio::test]
+async fn unknown_serial() {
+  let (server, mut notification) = notification().await;
+  notification.headers.insert(
+    "Wechatpay-Serial",
+    HeaderValue::from_static("5157F09EFDC096DE15EBE81A47057A72"),
+  );
+  let err = server
+    .client()
+    .verify_webhook(&notification.headers, notification.body.as_bytes())
+    .await
+    .unwrap_err();
+  assert!(matches!(err, WeChatPayError::VerifySignatureFail(_)));
+  assert_unauthorized(&err);
+}
+
+#[tokio::test]
+async fn non_utf8_body() {
+  let (server, notification) = notification().await;
+  let err = server
+    .client()
+    .verify_webhook(&notification.headers, &[0xff, 0xfe, 0xfd])
+    .await
+    .unwrap_err();
+  assert!(matches!(err, WeChatPayError::VerifySignatureFail(_)));
+  assert_unauthorized(&err);
+}
+
+#[tokio::test]
+async fn handler_error_is_not_returned() {
+  let (server, notification) = notification().await;
+  let ack = server
+    .client()
+    .handle_webhook(
+      &notification.headers,
+      notification.body.as_bytes(),
+      |_: Notification<serde_json::Value>| async {
+        Err::<(), _>("database password=secret rejected")
+      },
+    )
+    .await;
+  assert_eq!(ack.status, StatusCode::INTERNAL_SERVER_ERROR);
+  assert!(!ack.to_json().contains("secret"));
+}
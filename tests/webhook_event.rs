@@ -0,0 +1,259 @@
+//! 按通知类型解析和分发回调通知，需要启用 `testing` feature
+#![cfg(feature = "testing")]
+
+use reqwest::StatusCode;
+use serde_json::{json, Value};
+use std::sync::{Arc, Mutex};
+use wechat_pay_sdk::sdk::basic::combine::order::{
+  CombineAmount, CombineOrderRequest, CombineSubOrder,
+};
+use wechat_pay_sdk::sdk::basic::jsapi::refund::RefundRequest;
+use wechat_pay_sdk::sdk::common::{Amount, OrderRequest, Payer, RefundAmount};
+use wechat_pay_sdk::testing::{MockNotification, MockServer};
+use wechat_pay_sdk::webhook::event::{WebhookEvent, REFUND_SUCCESS, TRANSACTION_SUCCESS};
+use wechat_pay_sdk::webhook::router::WebhookRouter;
+use wechat_pay_sdk::Client;
+
+const NOTIFY_URL: &str = "https://www.weixin.qq.com/wxpay/pay.php";
+
+fn payer() -> Payer {
+  Payer {
+    openid: "oUpF8uMuAJO_M2pxb1Q9zNjWeS6o".to_string(),
+  }
+}
+
+fn order(server: &MockServer, out_trade_no: &str) -> OrderRequest {
+  OrderRequest {
+    appid: "wxd678efh567hg6787".to_string(),
+    mchid: server.merchant_id().to_string(),
+    description: "Image形象店-深圳腾大-QQ公仔".to_string(),
+    out_trade_no: out_trade_no.to_string(),
+    time_expire: None,
+    attach: None,
+    notify_url: NOTIFY_URL.to_string(),
+    goods_tag: None,
+    support_fapiao: None,
+    amount: Amount {
+      total: 100,
+      currency: None,
+    },
+    payer: payer(),
+    detail: None,
+    scene_info: None,
+    settle_info: None,
+  }
+}
+
+/// 下单并支付，返回支付成功通知
+async fn paid_order(server: &MockServer, client: &Client, out_trade_no: &str) -> MockNotification {
+  client
+    .jsapi_order(&order(server, out_trade_no))
+    .await
+    .unwrap();
+  server.pay_order(out_trade_no).unwrap();
+  server.order_notification(out_trade_no).unwrap()
+}
+
+/// 合单下单并支付，返回合单支付成功通知
+async fn paid_combine(server: &MockServer, client: &Client) -> MockNotification {
+  let sub_order = |out_trade_no: &str| CombineSubOrder {
+    mchid: server.merchant_id().to_string(),
+    attach: "深圳分店".to_string(),
+    amount: CombineAmount {
+      total_amount: 10,
+      currency: "CNY".to_string(),
+    },
+    out_trade_no: out_trade_no.to_string(),
+    goods_tag: None,
+    description: "腾讯充值中心-QQ会员充值".to_string(),
+    settle_info: None,
+  };
+  client
+    .combine_jsapi_order(&CombineOrderRequest {
+      combine_appid: "wxd678efh567hg6787".to_string(),
+      combine_mchid: server.merchant_id().to_string(),
+      combine_out_trade_no: "P20150806125346".to_string(),
+      scene_info: None,
+      sub_orders: vec![sub_order("20150806125346"), sub_order("20150806125347")],
+      combine_payer_info: Some(payer()),
+      time_start: None,
+      time_expire: None,
+      notify_url: NOTIFY_URL.to_string(),
+    })
+    .await
+    .unwrap();
+  server.pay_combine_order("P20150806125346").unwrap();
+  server.combine_notification("P20150806125346").unwrap()
+}
+
+/// 下单、支付并退款到账，返回退款通知的明文数据
+async fn refund_resource(server: &MockServer, client: &Client) -> Value {
+  paid_order(server, client, "4208450740201411110007820472").await;
+  client
+    .refund(&RefundRequest {
+      transaction_id: None,
+      out_trade_no: Some("4208450740201411110007820472".to_string()),
+      out_refund_no: "4208450740201411110007820472".to_string(),
+      reason: None,
+      notify_url: None,
+      funds_account: None,
+      amount: RefundAmount {
+        refund: 100,
+        from: None,
+        total: 100,
+        currency: "CNY".to_string(),
+      },
+      goods_detail: None,
+    })
+    .await
+    .unwrap();
+  server
+    .complete_refund("4208450740201411110007820472")
+    .unwrap();
+  let notification = server
+    .refund_notification("4208450740201411110007820472")
+    .unwrap();
+  let webhook = client
+    .verify_webhook(&notification.headers, notification.body.as_bytes())
+    .await
+    .unwrap();
+  webhook.parse::<Value>(client).unwrap()
+}
+
+async fn event(client: &Client, notification: &MockNotification) -> WebhookEvent {
+  client
+    .webhook_event(&notification.headers, notification.body.as_bytes())
+    .await
+    .unwrap()
+    .resource
+}
+
+#[tokio::test]
+async fn transaction_and_combine_transaction() {
+  let server = MockServer::start().await.unwrap();
+  let client = server.client();
+
+  let notification = paid_order(&server, &client, "1217752501201407033233368018").await;
+  match event(&client, &notification).await {
+    WebhookEvent::TransactionSuccess(transaction) => {
+      assert_eq!(transaction.out_trade_no, "1217752501201407033233368018")
+    }
+    event => panic!("unexpected event: {:?}", event),
+  }
+
+  // 合单通知同为 TRANSACTION.SUCCESS，通过 combine_out_trade_no 区分
+  let notification = paid_combine(&server, &client).await;
+  match event(&client, &notification).await {
+    WebhookEvent::CombineTransactionSuccess(combine) => {
+      assert_eq!(combine.combine_out_trade_no, "P20150806125346")
+    }
+    event => panic!("unexpected event: {:?}", event),
+  }
+}
+
+#[tokio::test]
+async fn refund_events() {
+  let server = MockServer::start().await.unwrap();
+  let client = server.client();
+  let resource = refund_resource(&server, &client).await;
+
+  for (event_type, status) in [
+    ("REFUND.SUCCESS", "SUCCESS"),
+    ("REFUND.ABNORMAL", "ABNORMAL"),
+    ("REFUND.CLOSED", "CLOSED"),
+  ] {
+    let mut resource = resource.clone();
+    resource["refund_status"] = json!(status);
+    let notification = server.notification(event_type, "refund", "退款通知", &resource);
+    let refund = match (event_type, event(&client, &notification).await) {
+      ("REFUND.SUCCESS", WebhookEvent::RefundSuccess(refund))
+      | ("REFUND.ABNORMAL", WebhookEvent::RefundAbnormal(refund))
+      | ("REFUND.CLOSED", WebhookEvent::RefundClosed(refund)) => refund,
+      (event_type, event) => panic!("unexpected event for {}: {:?}", event_type, event),
+    };
+    assert_eq!(refund.out_refund_no, "4208450740201411110007820472");
+    assert_eq!(refund.refund_status, status);
+  }
+}
+
+#[tokio::test]
+async fn unknown_event_is_raw() {
+  let server = MockServer::start().await.unwrap();
+  let client = server.client();
+  let resource = json!({ "mchid": server.merchant_id(), "state": "NEW" });
+  let notification = server.notification("SOMETHING.NEW", "something", "新通知", &resource);
+
+  match event(&client, &notification).await {
+    WebhookEvent::Raw(value) => assert_eq!(value, resource),
+    event => panic!("unexpected event: {:?}", event),
+  }
+}
+
+#[tokio::test]
+async fn router_dispatches_by_event_type() {
+  let server = MockServer::start().await.unwrap();
+  let client = server.client();
+  let handled = Arc::new(Mutex::new(Vec::new()));
+  let on_transaction = handled.clone();
+  let router = WebhookRouter::new()
+    .on(TRANSACTION_SUCCESS, move |notification| {
+      let handled = on_transaction.clone();
+      async move {
+        if let WebhookEvent::TransactionSuccess(transaction) = notification.resource {
+          handled.lock().unwrap().push(transaction.out_trade_no);
+        }
+        Ok::<_, String>(())
+      }
+    })
+    .on(REFUND_SUCCESS, |_| async { Err("退款处理失败") });
+
+  let notification = paid_order(&server, &client, "1217752501201407033233368018").await;
+  let ack = router
+    .handle(&client, &notification.headers, notification.body.as_bytes())
+    .await;
+  assert_eq!(ack.status, StatusCode::OK);
+  assert_eq!(
+    *handled.lock().unwrap(),
+    vec!["1217752501201407033233368018".to_string()]
+  );
+
+  // 处理函数返回错误时应答失败，微信支付会重新通知
+  let resource = refund_resource(&server, &client).await;
+  let notification = server.notification("REFUND.SUCCESS", "refund", "退款成功", &resource);
+  let ack = router
+    .handle(&client, &notification.headers, notification.body.as_bytes())
+    .await;
+  assert!(ack.status.is_server_error());
+
+  // 未注册的通知直接按接收成功应答
+  let notification = server.notification("SOMETHING.NEW", "something", "新通知", &json!({}));
+  let ack = router
+    .handle(&client, &notification.headers, notification.body.as_bytes())
+    .await;
+  assert_eq!(ack.status, StatusCode::OK);
+  assert_eq!(handled.lock().unwrap().len(), 1);
+}
+
+#[tokio::test]
+async fn router_fallback_receives_unregistered_events() {
+  let server = MockServer::start().await.unwrap();
+  let client = server.client();
+  let seen = Arc::new(Mutex::new(Vec::new()));
+  let fallback = seen.clone();
+  let router = WebhookRouter::new()
+    .on(TRANSACTION_SUCCESS, |_| async { Ok::<_, String>(()) })
+    .fallback(move |notification| {
+      let seen = fallback.clone();
+      async move {
+        seen.lock().unwrap().push(notification.webhook.event_type);
+        Ok::<_, String>(())
+      }
+    });
+
+  let notification = server.notification("SOMETHING.NEW", "something", "新通知", &json!({}));
+  let ack = router
+    .handle(&client, &notification.headers, notification.body.as_bytes())
+    .await;
+  assert_eq!(ack.status, StatusCode::OK);
+  assert_eq!(*seen.lock().unwrap(), vec!["SOMETHING.NEW".to_string()]);
+}